@@ -7,6 +7,7 @@ use crate::{TableIndex, TimeStamp};
 
 use chalk_ir::interner::Interner;
 use chalk_ir::{Goal, InEnvironment, Substitution, UCanonical};
+use chalk_solve::SolverStats;
 use tracing::debug;
 
 pub(crate) struct Forest<I: Interner> {
@@ -17,6 +18,9 @@ pub(crate) struct Forest<I: Interner> {
     /// This effectively gives us way to track what depth
     /// and loop a table or strand was last followed.
     pub(crate) clock: TimeStamp,
+
+    /// The statistics collected about the work done, if requested.
+    pub(crate) stats: Option<SolverStats>,
 }
 
 impl<I: Interner> Forest<I> {
@@ -24,6 +28,7 @@ impl<I: Interner> Forest<I> {
         Forest {
             tables: Tables::new(),
             clock: TimeStamp::default(),
+            stats: None,
         }
     }
 
@@ -87,6 +92,9 @@ impl<'me, I: Interner> AnswerStream<I> for ForestSolver<'me, I> {
                 }
 
                 Err(RootSearchFail::QuantumExceeded) => {
+                    // The forest is consistent between quanta, so this is
+                    // where it's safe to unwind.
+                    self.context.cancellation().check();
                     if !should_continue() {
                         return AnswerResult::QuantumExceeded;
                     }
@@ -15,11 +15,12 @@ use chalk_ir::{
     AnswerSubst, Canonical, ConstrainedSubst, Constraints, FallibleOrFloundered, Floundered, Goal,
     GoalData, InEnvironment, NoSolution, ProgramClause, Substitution, UCanonical, UniverseMap,
 };
-use chalk_solve::clauses::program_clauses_that_could_match;
+use chalk_solve::clauses::program_clauses_that_could_match_cancellable;
 use chalk_solve::coinductive_goal::IsCoinductive;
 use chalk_solve::infer::ucanonicalize::UCanonicalized;
 use chalk_solve::infer::InferenceTable;
 use chalk_solve::solve::truncate;
+use chalk_solve::Cancelled;
 use tracing::{debug, debug_span, info, instrument};
 
 type RootSearchResult<T> = Result<T, RootSearchFail>;
@@ -98,6 +99,9 @@ impl<I: Interner> Forest<I> {
         match state.ensure_root_answer(table, answer_index) {
             Ok(()) => {
                 assert!(state.stack.is_empty());
+                if state.forest.tables[table].is_retired(answer_index) {
+                    return Err(RootSearchFail::InvalidAnswer);
+                }
                 let answer = state.forest.answer(table, answer_index);
                 if !answer.subst.value.delayed_subgoals.is_empty() {
                     return Err(RootSearchFail::InvalidAnswer);
@@ -126,7 +130,7 @@ impl<I: Interner> Forest<I> {
         // Check any cached answers, starting at `answer_index`.
         while let Some(answer) = self.tables[table].answer(answer_index) {
             info!("answer cached = {:?}", answer);
-            if test(&answer.subst.value.subst) {
+            if !self.tables[table].is_retired(answer_index) && test(&answer.subst.value.subst) {
                 return true;
             }
             answer_index.increment();
@@ -204,15 +208,22 @@ impl<I: Interner> Forest<I> {
     ) -> TableIndex {
         if let Some(table) = self.tables.index_of(&goal) {
             debug!(?table, "found existing table");
+            if let Some(stats) = &mut self.stats {
+                stats.cache_hits += 1;
+            }
             return table;
         }
+        if let Some(stats) = &mut self.stats {
+            stats.cache_misses += 1;
+            stats.tables_created += 1;
+        }
 
         info!(
             table = ?self.tables.next_index(),
             "creating new table with goal = {:#?}",
             goal,
         );
-        let table = Self::build_table(context, self.tables.next_index(), goal);
+        let table = self.build_table(context, self.tables.next_index(), goal);
         self.tables.insert(table)
     }
 
@@ -228,6 +239,7 @@ impl<I: Interner> Forest<I> {
     /// Clause Resolution* step being applied eagerly, as many times
     /// as possible.
     fn build_table(
+        &mut self,
         context: &SlgContextOps<I>,
         table_idx: TableIndex,
         goal: UCanonical<InEnvironment<Goal<I>>>,
@@ -255,7 +267,11 @@ impl<I: Interner> Forest<I> {
                     c.could_match(db.interner(), db.unification_database(), &canon_goal)
                 };
 
-                match program_clauses_that_could_match(db, &canon_domain_goal) {
+                match program_clauses_that_could_match_cancellable(
+                    db,
+                    &canon_domain_goal,
+                    context.cancellation(),
+                ) {
                     Ok(mut clauses) => {
                         clauses.retain(could_match);
                         clauses.extend(db.custom_clauses().into_iter().filter(could_match));
@@ -273,6 +289,9 @@ impl<I: Interner> Forest<I> {
                                 .cloned()
                                 .filter(could_match),
                         );
+                        if let Some(stats) = &mut self.stats {
+                            stats.record_clauses(db, &goal.goal, clauses.len());
+                        }
 
                         let InEnvironment { environment, goal } = goal;
 
@@ -356,19 +375,32 @@ impl<I: Interner> Forest<I> {
     /// abstraction function to yield the canonical form that will be
     /// used to pick a table. Typically, this abstraction has no
     /// effect, and hence we are simply returning the canonical form
-    /// of `subgoal`; but if the subgoal is getting too big, we return
-    /// `None`, which causes the subgoal to flounder.
+    /// of `subgoal`; but if the subgoal is getting too big, we
+    /// truncate it (see `truncate::truncate`) and pick the table for
+    /// the truncated goal instead. Its answers are more general than
+    /// we need, but as each answer is unified with the (untruncated)
+    /// subgoal when it is applied to the strand, the extra ones are
+    /// filtered out. Only if the subgoal is still too big (which can
+    /// happen if its big types refer to variables bound within it) do
+    /// we return `None`, which causes the subgoal to flounder.
     fn abstract_positive_literal(
         context: &SlgContextOps<I>,
         infer: &mut InferenceTable<I>,
         subgoal: InEnvironment<Goal<I>>,
     ) -> Option<(UCanonical<InEnvironment<Goal<I>>>, UniverseMap)> {
-        if truncate::needs_truncation(
-            context.program().interner(),
-            infer,
-            context.max_size(),
-            &subgoal,
-        ) {
+        let interner = context.program().interner();
+        let max_size = context.max_size();
+        let subgoal = if truncate::needs_truncation(interner, infer, max_size, &subgoal) {
+            // The environment is assumed to hold, so it mustn't be made
+            // more general.
+            let InEnvironment { environment, goal } = subgoal;
+            let goal = truncate::truncate(interner, infer, max_size, goal);
+            InEnvironment::new(&environment, goal)
+        } else {
+            subgoal
+        };
+
+        if truncate::needs_truncation(interner, infer, max_size, &subgoal) {
             None
         } else {
             let canonicalized_goal = infer
@@ -533,9 +565,21 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
             match next_strand {
                 Some(mut canonical_strand) => {
                     debug!("starting next strand = {:#?}", canonical_strand);
+                    if let Some(stats) = &mut self.forest.stats {
+                        stats.strands_processed += 1;
+                    }
 
                     canonical_strand.value.last_pursued_time = clock;
-                    match self.select_subgoal(&mut canonical_strand) {
+                    // Selecting a subgoal can create a table for it, which
+                    // unwinds if the solve is cancelled while generating its
+                    // program clauses (before the table is added). Put the
+                    // strand back first, so that it isn't lost.
+                    let selection = Cancelled::catch(|| self.select_subgoal(&mut canonical_strand))
+                        .unwrap_or_else(|cancelled| {
+                            self.stack.top().active_strand = Some(canonical_strand.clone());
+                            cancelled.resume()
+                        });
+                    match selection {
                         SubGoalSelection::Selected => {
                             // A subgoal has been selected. We now check this subgoal
                             // table for an existing answer or if it's in a cycle.
@@ -911,12 +955,17 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
     ) -> Result<(), RootSearchFail> {
         // This may be a newly selected subgoal or an existing selected subgoal.
 
+        let selected_subgoal = canonical_strand.value.selected_subgoal.as_mut().unwrap();
+        // Skip the answers which were retired in favor of more general ones
+        // (which come after them) since we last looked at this table.
+        selected_subgoal.answer_index = self.forest.tables[selected_subgoal.subgoal_table]
+            .skip_retired(selected_subgoal.answer_index);
         let SelectedSubgoal {
             subgoal_index: _,
             subgoal_table,
             answer_index,
             universe_map: _,
-        } = *canonical_strand.value.selected_subgoal.as_ref().unwrap();
+        } = *selected_subgoal;
 
         debug!(
             ?subgoal_table,
@@ -1341,8 +1390,11 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
                     continue;
                 }
 
-                let subgoal_index =
-                    SlgContext::next_subgoal_index(&canonical_strand.value.ex_clause);
+                let subgoal_index = SlgContext::next_subgoal_index(
+                    self.context.program(),
+                    self.context.subgoal_selection(),
+                    &canonical_strand.value.ex_clause,
+                );
 
                 // Get or create table for this subgoal.
                 let num_universes = self.forest.tables[self.stack.top().table]
@@ -1399,6 +1451,35 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
         }
     }
 
+    /// Truncates the substitution of an answer for `table` that has become
+    /// too large (see `pursue_answer`). Returns `None` if the substitution
+    /// is still too large afterwards.
+    fn truncate_answer(
+        &self,
+        table: TableIndex,
+        answer: Canonical<AnswerSubst<I>>,
+    ) -> Option<Canonical<AnswerSubst<I>>> {
+        let interner = self.context.program().interner();
+        let max_size = self.context.max_size();
+        let universes = self.forest.tables[table].table_goal.universes;
+        let (mut infer, _, answer) = InferenceTable::from_canonical(interner, universes, answer);
+        let AnswerSubst {
+            subst,
+            constraints,
+            delayed_subgoals,
+        } = answer;
+        let subst = truncate::truncate(interner, &mut infer, max_size, subst);
+        if truncate::needs_truncation(interner, &mut infer, max_size, &subst) {
+            return None;
+        }
+        let answer = AnswerSubst {
+            subst,
+            constraints,
+            delayed_subgoals,
+        };
+        Some(infer.canonicalize(interner, answer).quantified)
+    }
+
     /// Invoked when a strand represents an **answer**. This means
     /// that the strand has no subgoals left. There are two possibilities:
     ///
@@ -1416,7 +1497,7 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
         let ExClause {
             subst,
             constraints,
-            ambiguous,
+            mut ambiguous,
             subgoals,
             delayed_subgoals,
             answer_time: _,
@@ -1435,33 +1516,6 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
         // positive or negative subgoals. This duplicates some of the logic
         // in `merge_answer_into_strand`, so a bit of refactoring is needed.
 
-        // If the answer gets too large, mark the table as floundered.
-        // This is the *most conservative* course. There are a few alternatives:
-        // 1) Replace the answer with a truncated version of it. (This was done
-        //    previously, but turned out to be more complicated than we wanted and
-        //    and a source of multiple bugs.)
-        // 2) Mark this *strand* as floundered. We don't currently have a mechanism
-        //    for this (only floundered subgoals), so implementing this is more
-        //    difficult because we don't want to just *remove* this strand from the
-        //    table, because that might make the table give `NoMoreSolutions`, which
-        //    is *wrong*.
-        // 3) Do something fancy with delayed subgoals, effectively delayed the
-        //    truncated bits to a different strand (and a more "refined" answer).
-        //    (This one probably needs more thought, but is here for "completeness")
-        //
-        // Ultimately, the current decision to flounder the entire table mostly boils
-        // down to "it works as we expect for the current tests". And, we likely don't
-        // even *need* the added complexity just for potentially more answers.
-        if truncate::needs_truncation(
-            self.context.program().interner(),
-            &mut InferenceTable::new(),
-            self.context.max_size(),
-            &subst,
-        ) {
-            self.forest.tables[table].mark_floundered();
-            return None;
-        }
-
         let table_goal = &self.forest.tables[table].table_goal;
 
         let filtered_delayed_subgoals = delayed_subgoals
@@ -1479,7 +1533,7 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
             })
             .collect();
 
-        let subst = Canonical {
+        let mut subst = Canonical {
             binders,
             value: AnswerSubst {
                 subst,
@@ -1487,6 +1541,38 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
                 delayed_subgoals: filtered_delayed_subgoals,
             },
         };
+
+        // If the answer gets too large, replace it with a truncated version
+        // of it, in which the parts that are too large are replaced with
+        // fresh variables. Since there are only finitely many answers of a
+        // given size, this guarantees that the table is eventually
+        // completed. The truncated answer is more general than the answer
+        // we found, so it is only an approximation, and it is marked as
+        // ambiguous. (The alternative is to not produce the answer at all,
+        // but we can't just drop this strand either, as that might make the
+        // table give `NoMoreSolutions`, which is *wrong*.)
+        //
+        // If the answer is too large even after truncation (which can happen
+        // if its large types refer to variables bound within it), we mark
+        // the table as floundered instead. That is the *most conservative*
+        // course.
+        if truncate::needs_truncation(
+            self.context.program().interner(),
+            &mut InferenceTable::new(),
+            self.context.max_size(),
+            &subst.value.subst,
+        ) {
+            match self.truncate_answer(table, subst) {
+                Some(truncated) => {
+                    subst = truncated;
+                    ambiguous = true;
+                }
+                None => {
+                    self.forest.tables[table].mark_floundered();
+                    return None;
+                }
+            }
+        }
         debug!(?table, ?subst, ?floundered, "found answer");
 
         let answer = Answer { subst, ambiguous };
@@ -1560,7 +1646,15 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
                     .is_empty(self.context.program().interner())
         };
 
-        if let Some(answer_index) = self.forest.tables[table].push_answer(answer) {
+        let interner = self.context.program().interner();
+        if let Some(answer_index) = self.forest.tables[table].push_answer(
+            interner,
+            self.context.unification_database(),
+            answer,
+        ) {
+            if let Some(stats) = &mut self.forest.stats {
+                stats.answers_produced += 1;
+            }
             // See above, if we have a *complete* and trivial answer, we don't
             // want to follow any more strands
             if !ambiguous && is_trivial_answer {
@@ -1,13 +1,17 @@
-use crate::ExClause;
+use crate::{ExClause, Literal};
 
 use chalk_derive::HasInterner;
 use chalk_ir::interner::Interner;
+use chalk_ir::visit::{TypeVisitable, TypeVisitor};
 use chalk_ir::*;
 use chalk_solve::infer::InferenceTable;
-use chalk_solve::RustIrDatabase;
+use chalk_solve::split::Split;
+use chalk_solve::{CancellationToken, RustIrDatabase};
 
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::ops::ControlFlow;
 
 pub(crate) mod aggregate;
 mod resolvent;
@@ -17,16 +21,148 @@ pub(crate) struct SlgContext<I: Interner> {
     phantom: PhantomData<I>,
 }
 
+/// How the SLG solver picks which subgoal of a strand to work on next.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SubgoalSelection {
+    /// Always pick the last subgoal in the list.
+    #[default]
+    Last,
+    /// Pick the subgoal that is most likely to make progress. Subgoals which
+    /// are likely to flounder or to have many answers -- negative literals
+    /// with unresolved variables, and trait goals whose self type is
+    /// unresolved, like `?T: Bar` or `<?T as Iterator>::Item = ?U` -- are
+    /// put off for as long as possible, and those which are sure to flounder
+    /// (like `?T: Sized`, for a non-enumerable or auto trait) even longer.
+    /// Otherwise, the subgoal with the fewest unresolved variables is
+    /// picked, not counting the type an alias is equated with (which is an
+    /// output, like `?U` above), and ties go to the last one (as with
+    /// `Last`).
+    Heuristic,
+}
+
 impl<I: Interner> SlgContext<I> {
-    pub(crate) fn next_subgoal_index(ex_clause: &ExClause<I>) -> usize {
-        // For now, we always pick the last subgoal in the
-        // list.
-        //
-        // FIXME(rust-lang-nursery/chalk#80) -- we should be more
-        // selective. For example, we don't want to pick a
-        // negative literal that will flounder, and we don't want
-        // to pick things like `?T: Sized` if we can help it.
-        ex_clause.subgoals.len() - 1
+    pub(crate) fn next_subgoal_index(
+        db: &dyn RustIrDatabase<I>,
+        selection: SubgoalSelection,
+        ex_clause: &ExClause<I>,
+    ) -> usize {
+        let last = ex_clause.subgoals.len() - 1;
+        match selection {
+            SubgoalSelection::Last => last,
+            // `min_by_key` picks the first of equal subgoals, so we go
+            // through them backwards to prefer the later ones.
+            SubgoalSelection::Heuristic => (0..=last)
+                .rev()
+                .min_by_key(|&i| subgoal_cost(db, &ex_clause.subgoals[i]))
+                .unwrap(),
+        }
+    }
+}
+
+/// The cost of picking `literal` under `SubgoalSelection::Heuristic`; the
+/// subgoal with the lowest cost is picked. The literal is part of a canonical
+/// strand, so its unresolved variables are the bound variables of the strand.
+fn subgoal_cost<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    literal: &Literal<I>,
+) -> (bool, bool, usize) {
+    let interner = db.interner();
+    let (goal, negative) = match literal {
+        Literal::Positive(goal) => (goal, false),
+        Literal::Negative(goal) => (goal, true),
+    };
+
+    // The type on the right of an alias goal is an output, like the answer
+    // to any other goal, so only the variables of the alias itself count.
+    let alias = match goal.goal.data(interner) {
+        GoalData::DomainGoal(DomainGoal::Holds(WhereClause::AliasEq(AliasEq {
+            alias, ..
+        })))
+        | GoalData::DomainGoal(DomainGoal::Normalize(Normalize { alias, .. })) => Some(alias),
+        _ => None,
+    };
+
+    let mut visitor = UnresolvedVars {
+        interner,
+        vars: HashSet::new(),
+    };
+    match alias {
+        Some(alias) => {
+            let _ = goal
+                .environment
+                .visit_with(&mut visitor, DebruijnIndex::INNERMOST);
+            let _ = alias.visit_with(&mut visitor, DebruijnIndex::INNERMOST);
+        }
+        None => {
+            let _ = goal.visit_with(&mut visitor, DebruijnIndex::INNERMOST);
+        }
+    }
+    let unresolved = visitor.vars.len();
+
+    // Like `program_clauses_that_could_match`, which makes goals with an
+    // unresolved self type flounder if their trait is non-enumerable. An
+    // alias goal whose self type is unresolved does not flounder, but is
+    // likely to stay ambiguous, so it is put off like a trait goal.
+    let (unresolved_self_ty, flounders) = match (goal.goal.data(interner), alias) {
+        (GoalData::DomainGoal(DomainGoal::Holds(WhereClause::Implemented(trait_ref))), _)
+            if trait_ref
+                .self_type_parameter(interner)
+                .bound_var(interner)
+                .is_some() =>
+        {
+            let trait_datum = db.trait_datum(trait_ref.trait_id);
+            let flounders = trait_datum.is_non_enumerable_trait() || trait_datum.is_auto_trait();
+            (true, flounders)
+        }
+        (_, Some(AliasTy::Projection(projection))) => {
+            let self_ty = db
+                .trait_ref_from_projection(projection)
+                .self_type_parameter(interner);
+            (self_ty.bound_var(interner).is_some(), false)
+        }
+        _ => (false, false),
+    };
+    let postpone = (negative && unresolved > 0) || unresolved_self_ty;
+
+    (flounders, postpone, unresolved)
+}
+
+/// Collects the unresolved type and const variables of a canonical value.
+/// Lifetime variables are left out, as they never keep a goal from making
+/// progress.
+struct UnresolvedVars<I: Interner> {
+    interner: I,
+    vars: HashSet<usize>,
+}
+
+impl<I: Interner> TypeVisitor<I> for UnresolvedVars<I> {
+    type BreakTy = ();
+
+    fn as_dyn(&mut self) -> &mut dyn TypeVisitor<I, BreakTy = Self::BreakTy> {
+        self
+    }
+
+    fn interner(&self) -> I {
+        self.interner
+    }
+
+    fn visit_lifetime(
+        &mut self,
+        _lifetime: &Lifetime<I>,
+        _outer_binder: DebruijnIndex,
+    ) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_free_var(
+        &mut self,
+        bound_var: BoundVar,
+        outer_binder: DebruijnIndex,
+    ) -> ControlFlow<()> {
+        if let Some(bound_var) = bound_var.shifted_out_to(outer_binder) {
+            self.vars.insert(bound_var.index);
+        }
+        ControlFlow::Continue(())
     }
 }
 #[derive(Clone, Debug)]
@@ -34,6 +170,8 @@ pub(crate) struct SlgContextOps<'me, I: Interner> {
     program: &'me dyn RustIrDatabase<I>,
     max_size: usize,
     expected_answers: Option<usize>,
+    subgoal_selection: SubgoalSelection,
+    cancellation: CancellationToken,
 }
 
 impl<I: Interner> SlgContextOps<'_, I> {
@@ -41,14 +179,23 @@ impl<I: Interner> SlgContextOps<'_, I> {
         program: &dyn RustIrDatabase<I>,
         max_size: usize,
         expected_answers: Option<usize>,
+        subgoal_selection: SubgoalSelection,
     ) -> SlgContextOps<'_, I> {
         SlgContextOps {
             program,
             max_size,
             expected_answers,
+            subgoal_selection,
+            cancellation: CancellationToken::new(),
         }
     }
 
+    /// Makes the solve cancellable with `cancellation`.
+    pub(crate) fn with_cancellation(mut self, cancellation: &CancellationToken) -> Self {
+        self.cancellation = cancellation.clone();
+        self
+    }
+
     fn identity_constrained_subst(
         &self,
         goal: &UCanonical<InEnvironment<Goal<I>>>,
@@ -73,10 +220,18 @@ impl<I: Interner> SlgContextOps<'_, I> {
         self.program
     }
 
+    pub(crate) fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
     pub(crate) fn max_size(&self) -> usize {
         self.max_size
     }
 
+    pub(crate) fn subgoal_selection(&self) -> SubgoalSelection {
+        self.subgoal_selection
+    }
+
     pub(crate) fn unification_database(&self) -> &dyn UnificationDatabase<I> {
         self.program.unification_database()
     }
@@ -7,6 +7,7 @@ use chalk_ir::interner::Interner;
 use chalk_ir::*;
 use chalk_solve::ext::*;
 use chalk_solve::infer::InferenceTable;
+use chalk_solve::solve::regions;
 use chalk_solve::solve::{Guidance, Solution};
 
 use std::fmt::Debug;
@@ -58,7 +59,7 @@ impl<I: Interner> AggregateOps<I> for SlgContextOps<'_, I> {
             }
         }
         if next_answer.is_no_more_solutions() && !ambiguous {
-            return Some(Solution::Unique(subst));
+            return regions::simplify_solution(self.program, root_goal, Solution::Unique(subst));
         }
 
         // Otherwise, we either have >1 answer, or else we have
@@ -8,6 +8,7 @@ use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::zip::{Zip, Zipper};
 use chalk_ir::*;
 use chalk_solve::infer::InferenceTable;
+use chalk_solve::solve::regions;
 use tracing::{debug, instrument};
 
 ///////////////////////////////////////////////////////////////////////////
@@ -116,9 +117,11 @@ impl<I: Interner> ResolventOps<I> for InferenceTable<I> {
                 .map(Literal::Positive),
         );
 
-        ex_clause
-            .constraints
-            .extend(constraints.as_slice(interner).to_owned());
+        ex_clause.constraints.extend(regions::clause_constraints_in(
+            interner,
+            environment,
+            &constraints,
+        ));
 
         // Add the `conditions` from the program clause into the result too.
         ex_clause
@@ -1,17 +1,22 @@
 use crate::context::{AnswerResult, AnswerStream};
 use crate::forest::Forest;
 use crate::slg::aggregate::AggregateOps;
-use crate::slg::SlgContextOps;
+use crate::slg::{SlgContextOps, SubgoalSelection};
 use chalk_ir::interner::Interner;
 use chalk_ir::{Canonical, ConstrainedSubst, Goal, InEnvironment, UCanonical};
-use chalk_solve::{RustIrDatabase, Solution, Solver, SubstitutionResult};
+use chalk_solve::solve::regions;
+use chalk_solve::{
+    CancellationToken, Cancelled, RustIrDatabase, Solution, Solver, SolverStats, SubstitutionResult,
+};
 
 use std::fmt;
+use std::time::Instant;
 
 pub struct SLGSolver<I: Interner> {
     pub(crate) forest: Forest<I>,
     pub(crate) max_size: usize,
     pub(crate) expected_answers: Option<usize>,
+    pub(crate) subgoal_selection: SubgoalSelection,
 }
 
 impl<I: Interner> SLGSolver<I> {
@@ -20,6 +25,22 @@ impl<I: Interner> SLGSolver<I> {
             forest: Forest::new(),
             max_size,
             expected_answers,
+            subgoal_selection: SubgoalSelection::default(),
+        }
+    }
+
+    /// Sets how the solver picks the next subgoal to work on.
+    pub fn with_subgoal_selection(mut self, subgoal_selection: SubgoalSelection) -> Self {
+        self.subgoal_selection = subgoal_selection;
+        self
+    }
+
+    /// Records, if statistics are being collected, that a root goal was
+    /// solved, starting at `start`.
+    fn record_root_goal(&mut self, start: Instant) {
+        if let Some(stats) = &mut self.forest.stats {
+            stats.goals_solved += 1;
+            stats.time_spent += start.elapsed();
         }
     }
 }
@@ -36,8 +57,16 @@ impl<I: Interner> Solver<I> for SLGSolver<I> {
         program: &dyn RustIrDatabase<I>,
         goal: &UCanonical<InEnvironment<Goal<I>>>,
     ) -> Option<Solution<I>> {
-        let ops = SlgContextOps::new(program, self.max_size, self.expected_answers);
-        ops.make_solution(goal, self.forest.iter_answers(&ops, goal), || true)
+        let ops = SlgContextOps::new(
+            program,
+            self.max_size,
+            self.expected_answers,
+            self.subgoal_selection,
+        );
+        let start = Instant::now();
+        let solution = ops.make_solution(goal, self.forest.iter_answers(&ops, goal), || true);
+        self.record_root_goal(start);
+        solution
     }
 
     fn solve_limited(
@@ -46,8 +75,38 @@ impl<I: Interner> Solver<I> for SLGSolver<I> {
         goal: &UCanonical<InEnvironment<Goal<I>>>,
         should_continue: &dyn std::ops::Fn() -> bool,
     ) -> Option<Solution<I>> {
-        let ops = SlgContextOps::new(program, self.max_size, self.expected_answers);
-        ops.make_solution(goal, self.forest.iter_answers(&ops, goal), should_continue)
+        let ops = SlgContextOps::new(
+            program,
+            self.max_size,
+            self.expected_answers,
+            self.subgoal_selection,
+        );
+        let start = Instant::now();
+        let solution =
+            ops.make_solution(goal, self.forest.iter_answers(&ops, goal), should_continue);
+        self.record_root_goal(start);
+        solution
+    }
+
+    fn solve_cancellable(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        cancellation: &CancellationToken,
+    ) -> Result<Option<Solution<I>>, Cancelled> {
+        let ops = SlgContextOps::new(
+            program,
+            self.max_size,
+            self.expected_answers,
+            self.subgoal_selection,
+        )
+        .with_cancellation(cancellation);
+        let start = Instant::now();
+        let solution = Cancelled::catch(|| {
+            ops.make_solution(goal, self.forest.iter_answers(&ops, goal), || true)
+        });
+        self.record_root_goal(start);
+        solution
     }
 
     fn solve_multiple(
@@ -56,34 +115,63 @@ impl<I: Interner> Solver<I> for SLGSolver<I> {
         goal: &UCanonical<InEnvironment<Goal<I>>>,
         f: &mut dyn FnMut(SubstitutionResult<Canonical<ConstrainedSubst<I>>>, bool) -> bool,
     ) -> bool {
-        let ops = SlgContextOps::new(program, self.max_size, self.expected_answers);
+        let ops = SlgContextOps::new(
+            program,
+            self.max_size,
+            self.expected_answers,
+            self.subgoal_selection,
+        );
+        let start = Instant::now();
         let mut answers = self.forest.iter_answers(&ops, goal);
-        loop {
+        let processed_all = loop {
             let subst = match answers.next_answer(|| true) {
                 AnswerResult::Answer(answer) => {
+                    // Answers whose region constraints can't hold aren't
+                    // answers at all.
+                    let subst =
+                        match regions::simplify_constrained_subst(program, goal, answer.subst) {
+                            Ok(subst) => subst,
+                            Err(_) => continue,
+                        };
                     if !answer.ambiguous {
-                        SubstitutionResult::Definite(answer.subst)
-                    } else if answer
-                        .subst
+                        SubstitutionResult::Definite(subst)
+                    } else if subst
                         .value
                         .subst
                         .is_identity_subst(ops.program().interner())
                     {
                         SubstitutionResult::Floundered
                     } else {
-                        SubstitutionResult::Ambiguous(answer.subst)
+                        SubstitutionResult::Ambiguous(subst)
                     }
                 }
                 AnswerResult::Floundered => SubstitutionResult::Floundered,
                 AnswerResult::NoMoreSolutions => {
-                    return true;
+                    break true;
                 }
                 AnswerResult::QuantumExceeded => continue,
             };
 
             if !f(subst, !answers.peek_answer(|| true).is_no_more_solutions()) {
-                return false;
+                break false;
             }
+        };
+        drop(answers);
+        self.record_root_goal(start);
+        processed_all
+    }
+
+    fn collect_stats(&mut self) {
+        self.forest.stats.get_or_insert_with(SolverStats::default);
+    }
+
+    fn stats(&self) -> Option<SolverStats> {
+        self.forest.stats.clone()
+    }
+
+    fn reset_stats(&mut self) {
+        if let Some(stats) = &mut self.forest.stats {
+            *stats = SolverStats::default();
         }
     }
 }
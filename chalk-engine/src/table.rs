@@ -1,13 +1,18 @@
 use crate::index_struct;
 use crate::strand::CanonicalStrand;
 use crate::{Answer, AnswerMode};
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use std::collections::hash_map::Entry;
 use std::collections::VecDeque;
 use std::mem;
 
+use chalk_ir::could_match::CouldMatch;
 use chalk_ir::interner::Interner;
-use chalk_ir::{AnswerSubst, Canonical, Goal, InEnvironment, UCanonical};
+use chalk_ir::{
+    AnswerSubst, Canonical, ConstrainedSubst, Environment, Goal, InEnvironment, UCanonical,
+    UnificationDatabase, Variance,
+};
+use chalk_solve::infer::InferenceTable;
 use tracing::{debug, info, instrument};
 
 #[derive(Debug)]
@@ -40,6 +45,12 @@ pub(crate) struct Table<I: Interner> {
     /// to do so though it can result in more answers than we need.
     answers_hash: FxHashMap<Canonical<AnswerSubst<I>>, bool>,
 
+    /// The answers which are instances of answers that came after them
+    /// (see `push_answer`). They are kept in `answers`, so that the
+    /// indices of the other answers don't change, but they are skipped
+    /// by anyone who hasn't gotten to them yet.
+    retired: FxHashSet<AnswerIndex>,
+
     /// Stores the active strands that we can "pull on" to find more
     /// answers.
     strands: VecDeque<CanonicalStrand<I>>,
@@ -64,6 +75,7 @@ impl<I: Interner> Table<I> {
             answers: Vec::new(),
             floundered: false,
             answers_hash: FxHashMap::default(),
+            retired: FxHashSet::default(),
             strands: VecDeque::new(),
             answer_mode: AnswerMode::Complete,
         }
@@ -106,6 +118,7 @@ impl<I: Interner> Table<I> {
         self.floundered = true;
         self.strands = Default::default();
         self.answers = Default::default();
+        self.retired = Default::default();
     }
 
     /// Returns true if the table is floundered.
@@ -113,7 +126,9 @@ impl<I: Interner> Table<I> {
         self.floundered
     }
 
-    /// Adds `answer` to our list of answers, unless it is already present.
+    /// Adds `answer` to our list of answers, unless it is already present,
+    /// or it is an instance of an answer that is (see `subsumes`). Answers
+    /// which are instances of `answer` are retired.
     ///
     /// Returns true if `answer` was added.
     ///
@@ -124,7 +139,12 @@ impl<I: Interner> Table<I> {
     /// `true` is returned here, that a *new* answer was added (instead of an)
     /// existing answer replaced.
     #[instrument(level = "debug", skip(self))]
-    pub(super) fn push_answer(&mut self, answer: Answer<I>) -> Option<AnswerIndex> {
+    pub(super) fn push_answer(
+        &mut self,
+        interner: I,
+        db: &dyn UnificationDatabase<I>,
+        answer: Answer<I>,
+    ) -> Option<AnswerIndex> {
         assert!(!self.floundered);
         debug!(
             "pre-existing entry: {:?}",
@@ -154,6 +174,38 @@ impl<I: Interner> Table<I> {
             return None;
         }
 
+        // Checking whether one answer subsumes another needs an inference
+        // table of its own, which is too slow to do for every pair of
+        // answers: goals with many answers usually have ones that differ in
+        // their type constructors, which `could_match` rules out cheaply.
+        let universes = self.table_goal.universes;
+        let subst = answer.subst.value.subst.as_slice(interner);
+        let candidates: Vec<_> = self
+            .answers
+            .iter()
+            .enumerate()
+            .map(|(index, answer)| (AnswerIndex::from(index), answer))
+            .filter(|(index, _)| !self.retired.contains(index))
+            .filter(|(_, other)| {
+                let other_subst = other.subst.value.subst.as_slice(interner);
+                subst.could_match(interner, db, other_subst)
+            })
+            .collect();
+        if let Some((index, _)) = candidates
+            .iter()
+            .find(|(_, general)| subsumes(interner, db, universes, general, &answer))
+        {
+            debug!(?index, "answer is subsumed by an existing answer");
+            return None;
+        }
+        let subsumed: Vec<_> = candidates
+            .iter()
+            .filter(|(_, specific)| subsumes(interner, db, universes, &answer, specific))
+            .map(|&(index, _)| index)
+            .collect();
+        debug!(?subsumed, "retiring answers subsumed by the new answer");
+        self.retired.extend(subsumed);
+
         let index = self.answers.len();
         self.answers.push(answer);
         Some(AnswerIndex::from(index))
@@ -163,6 +215,21 @@ impl<I: Interner> Table<I> {
         self.answers.get(index.value)
     }
 
+    /// Whether the answer at `index` was retired in favor of a more general
+    /// one, which comes after it.
+    pub(super) fn is_retired(&self, index: AnswerIndex) -> bool {
+        self.retired.contains(&index)
+    }
+
+    /// Returns the first index starting at `index` whose answer hasn't been
+    /// retired (which may be `next_answer_index`).
+    pub(super) fn skip_retired(&self, mut index: AnswerIndex) -> AnswerIndex {
+        while self.is_retired(index) {
+            index.increment();
+        }
+        index
+    }
+
     pub(super) fn next_answer_index(&self) -> AnswerIndex {
         AnswerIndex::from(self.answers.len())
     }
@@ -171,3 +238,57 @@ impl<I: Interner> Table<I> {
 impl AnswerIndex {
     pub(crate) const ZERO: AnswerIndex = AnswerIndex { value: 0 };
 }
+
+/// Whether the `general` answer makes the `specific` one redundant: that
+/// is, whether `specific` is an instance of `general` (`Vec<u32>` is an
+/// instance of `Vec<?T>`), and `general` is no weaker in other respects:
+/// it has no delayed subgoals, is only ambiguous if `specific` is, and its
+/// region constraints are among those of `specific`.
+fn subsumes<I: Interner>(
+    interner: I,
+    db: &dyn UnificationDatabase<I>,
+    universes: usize,
+    general: &Answer<I>,
+    specific: &Answer<I>,
+) -> bool {
+    if !general.subst.value.delayed_subgoals.is_empty() || general.ambiguous && !specific.ambiguous
+    {
+        return false;
+    }
+
+    // Unify the two, and check that the variables of `specific` are left
+    // alone (i.e. they are still distinct, unbound variables).
+    let (mut infer, specific_vars, specific) =
+        InferenceTable::from_canonical(interner, universes, specific.subst.clone());
+    let general = infer.instantiate_canonical(interner, general.subst.clone());
+    let unified = infer.relate(
+        interner,
+        db,
+        &Environment::new(interner),
+        Variance::Invariant,
+        general.subst.as_slice(interner),
+        specific.subst.as_slice(interner),
+    );
+    if !matches!(unified, Ok(ref result) if result.goals.is_empty()) {
+        return false;
+    }
+    let mut canonicalize = |constraints| {
+        infer
+            .canonicalize(
+                interner,
+                ConstrainedSubst {
+                    subst: specific_vars.clone(),
+                    constraints,
+                },
+            )
+            .quantified
+            .value
+    };
+    let general = canonicalize(general.constraints);
+    let specific = canonicalize(specific.constraints);
+    general.subst.is_identity_subst(interner)
+        && general
+            .constraints
+            .iter(interner)
+            .all(|constraint| specific.constraints.as_slice(interner).contains(constraint))
+}
@@ -4,22 +4,30 @@ use crate::{
     lowering::lower_goal,
     program::Program,
     query::{Lowering, LoweringDatabase},
-    tls, SolverChoice,
+    solver_cache, tls, SolverChoice,
 };
+use chalk_engine::slg::SubgoalSelection;
 use chalk_ir::{
     AdtId, AssocTypeId, Binders, Canonical, CanonicalVarKinds, ClosureId, ConstrainedSubst,
     CoroutineId, Environment, FnDefId, GenericArg, Goal, ImplId, InEnvironment, OpaqueTyId,
     ProgramClause, ProgramClauses, Substitution, TraitId, Ty, TyKind, UCanonical,
     UnificationDatabase, Variances,
 };
+use chalk_recursive::BatchSolver;
+use chalk_solve::impl_lookup::{matching_impls, ImplBound, MatchingImpl};
 use chalk_solve::rust_ir::{
     AdtDatum, AdtRepr, AdtSizeAlign, AssociatedTyDatum, AssociatedTyValue, AssociatedTyValueId,
     ClosureKind, CoroutineDatum, CoroutineWitnessDatum, FnDefDatum, FnDefInputsAndOutputDatum,
     ImplDatum, OpaqueTyDatum, TraitDatum, WellKnownTrait,
 };
-use chalk_solve::{RustIrDatabase, Solution, SubstitutionResult};
+use chalk_solve::solve::proof::{FailureTree, ProofResult};
+use chalk_solve::{
+    Answers, Budget, BudgetExhausted, CancellationToken, Cancelled, RustIrDatabase, Solution,
+    SolverStats, SubstitutionResult,
+};
 use salsa::Database;
 use std::fmt;
+use std::path::Path;
 use std::sync::Arc;
 
 #[salsa::database(Lowering)]
@@ -35,6 +43,8 @@ impl ChalkDatabase {
         let mut db = ChalkDatabase::default();
         db.set_program_text(Arc::new(program_text.to_string()));
         db.set_solver_choice(solver_choice);
+        db.set_collect_solver_stats(false);
+        db.set_subgoal_selection(SubgoalSelection::default());
         db
     }
 
@@ -57,6 +67,77 @@ impl ChalkDatabase {
         solution
     }
 
+    /// Solves a given goal like `solve`, but only as far as `budget`
+    /// allows. Solving the goal again picks up where this left off.
+    pub fn solve_with_budget(
+        &self,
+        goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+        budget: &Budget,
+    ) -> Result<Option<Solution<ChalkIr>>, BudgetExhausted> {
+        let solver = self.solver();
+        let solution = solver.lock().unwrap().solve_with_budget(self, goal, budget);
+        solution
+    }
+
+    /// Solves a given goal like `solve`, unless `cancellation` is cancelled
+    /// first. The solver can still be used after a cancelled solve.
+    pub fn solve_cancellable(
+        &self,
+        goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+        cancellation: &CancellationToken,
+    ) -> Result<Option<Solution<ChalkIr>>, Cancelled> {
+        let solver = self.solver();
+        let solution = solver
+            .lock()
+            .unwrap()
+            .solve_cancellable(self, goal, cancellation);
+        solution
+    }
+
+    /// Solves a given goal like `solve`, and also explains the solution
+    /// with a proof tree, which is re-derived from the solution rather than
+    /// recorded while solving (see `Solver::solve_with_proof`).
+    pub fn solve_with_proof(
+        &self,
+        goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+    ) -> Option<(Solution<ChalkIr>, ProofResult<ChalkIr>)> {
+        let solver = self.solver();
+        let result = solver.lock().unwrap().solve_with_proof(self, goal);
+        result
+    }
+
+    /// Explains why a given goal has no solution, or returns `None` if it
+    /// has one.
+    pub fn explain_failure(
+        &self,
+        goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+    ) -> Option<FailureTree<ChalkIr>> {
+        let solver = self.solver();
+        let result = solver.lock().unwrap().explain_failure(self, goal);
+        result
+    }
+
+    /// Enumerates up to `limit` answers to a given goal, in an order that
+    /// doesn't depend on the solver.
+    pub fn enumerate_answers(
+        &self,
+        goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+        limit: usize,
+    ) -> Answers<ChalkIr> {
+        let solver = self.solver();
+        let answers = solver.lock().unwrap().enumerate_answers(self, goal, limit);
+        answers
+    }
+
+    /// Finds the impls whose headers could satisfy a trait bound, along with
+    /// the where clauses that would have to hold.
+    pub fn matching_impls(
+        &self,
+        bound: &Canonical<ImplBound<ChalkIr>>,
+    ) -> Vec<MatchingImpl<ChalkIr>> {
+        matching_impls(self, bound)
+    }
+
     /// Solves a given goal, producing the solution. This will do only
     /// as much work towards `goal` as it has to (and that works is
     /// cached for future attempts). Calls provided function `f` to
@@ -70,6 +151,75 @@ impl ChalkDatabase {
         let solution = solver.lock().unwrap().solve_multiple(self, goal, f);
         solution
     }
+
+    /// Solves each of the `goals` like `solve`, using up to `threads` threads
+    /// at once, and returns their solutions in the same order. The
+    /// solutions are the same for any number of threads.
+    ///
+    /// Only the recursive solver (with caching enabled) can solve goals in
+    /// parallel; with other solvers, the goals are solved one after the
+    /// other.
+    pub fn solve_batch(
+        &self,
+        goals: &[UCanonical<InEnvironment<Goal<ChalkIr>>>],
+        threads: usize,
+    ) -> Vec<Option<Solution<ChalkIr>>> {
+        match (self.solver_choice(), self.solver_cache()) {
+            (
+                SolverChoice::Recursive {
+                    overflow_depth,
+                    max_size,
+                    ..
+                },
+                Some(cache),
+            ) => {
+                // The database itself cannot be shared between threads, but
+                // the program it answers all queries from can.
+                let program = self.program_ir().unwrap();
+                BatchSolver::new(overflow_depth, max_size, threads, (**cache).clone())
+                    .solve(&*program, goals)
+            }
+            _ => goals.iter().map(|goal| self.solve(goal)).collect(),
+        }
+    }
+
+    /// Saves the results the solver has cached so far to `path`, returning
+    /// how many there were. Only the recursive solver keeps such a cache;
+    /// with other solvers, nothing is saved.
+    pub fn save_solver_cache(&self, path: &Path) -> Result<usize, ChalkError> {
+        match self.solver_cache() {
+            Some(cache) => solver_cache::save(&cache, self.solver_cache_fingerprint(), path),
+            None => Ok(0),
+        }
+    }
+
+    /// Loads the results saved by `save_solver_cache` into the solver's
+    /// cache, returning how many there were. Results saved for a different
+    /// program or solver are ignored.
+    pub fn load_solver_cache(&self, path: &Path) -> Result<usize, ChalkError> {
+        match self.solver_cache() {
+            Some(cache) => solver_cache::load(&cache, self.solver_cache_fingerprint(), path),
+            None => Ok(0),
+        }
+    }
+
+    /// The statistics the solver has collected, if `collect_solver_stats`
+    /// is set. A new solver (with new statistics) is created whenever the
+    /// program or the solver choice changes.
+    pub fn solver_stats(&self) -> Option<SolverStats> {
+        let solver = self.solver();
+        let stats = solver.lock().unwrap().stats();
+        stats
+    }
+
+    /// Resets the statistics the solver has collected to zero.
+    pub fn reset_solver_stats(&self) {
+        self.solver().lock().unwrap().reset_stats();
+    }
+
+    fn solver_cache_fingerprint(&self) -> u64 {
+        solver_cache::fingerprint(&self.program_text(), self.solver_choice())
+    }
 }
 
 impl UnificationDatabase<ChalkIr> for ChalkDatabase {
@@ -2,57 +2,186 @@ use crate::interner::ChalkIr;
 use chalk_parse::ast::{Identifier, Kind};
 use chalk_solve::coherence::CoherenceError;
 use chalk_solve::wf::WfError;
+use std::fmt::Write;
 use string_cache::DefaultAtom as Atom;
 
+pub use chalk_parse::ast::Span;
+
 /// Wrapper type for the various errors that can occur during chalk
 /// processing.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ChalkError {
-    /// For now, we just convert the error into a string, which makes
-    /// it trivially hashable etc.
-    error_text: String,
+    /// For now, we just convert the error into a diagnostic made of
+    /// strings and spans, which makes it trivially hashable etc.
+    diagnostic: Diagnostic,
+}
+
+impl ChalkError {
+    /// The structured form of this error, with the source locations
+    /// of the items it refers to (when known).
+    pub fn diagnostic(&self) -> &Diagnostic {
+        &self.diagnostic
+    }
+}
+
+impl From<Diagnostic> for ChalkError {
+    fn from(diagnostic: Diagnostic) -> Self {
+        ChalkError { diagnostic }
+    }
 }
 
 impl From<Box<dyn std::error::Error>> for ChalkError {
     fn from(value: Box<dyn std::error::Error>) -> Self {
-        ChalkError {
-            error_text: value.to_string(),
-        }
+        Diagnostic::new(value.to_string()).into()
     }
 }
 
 impl From<WfError<ChalkIr>> for ChalkError {
     fn from(value: WfError<ChalkIr>) -> Self {
-        ChalkError {
-            error_text: value.to_string(),
-        }
+        Diagnostic::new(value.to_string()).into()
     }
 }
 
 impl From<CoherenceError<ChalkIr>> for ChalkError {
     fn from(value: CoherenceError<ChalkIr>) -> Self {
-        ChalkError {
-            error_text: value.to_string(),
-        }
+        Diagnostic::new(value.to_string()).into()
     }
 }
 
 impl From<RustIrError> for ChalkError {
     fn from(value: RustIrError) -> Self {
-        ChalkError {
-            error_text: value.to_string(),
+        let diagnostic = Diagnostic::new(value.to_string());
+        match value.identifier() {
+            Some(identifier) => diagnostic.with_primary(identifier.span, value.label()),
+            None => diagnostic,
         }
+        .into()
     }
 }
 
 impl std::fmt::Display for ChalkError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.error_text)
+        write!(f, "{}", self.diagnostic.message)
     }
 }
 
 impl std::error::Error for ChalkError {}
 
+/// An error message together with the places in the program text it
+/// refers to.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    /// The main error message, e.g. "overlapping impls of trait `Foo`".
+    pub message: String,
+
+    /// The location of the offending item, if known.
+    pub primary: Option<Label>,
+
+    /// Other locations that are relevant to the error, e.g. the
+    /// impl that another impl overlaps with.
+    pub secondary: Vec<Label>,
+}
+
+/// A span in the program text with a short explanation attached.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>) -> Self {
+        Diagnostic {
+            message: message.into(),
+            primary: None,
+            secondary: vec![],
+        }
+    }
+
+    /// Sets the primary label. Does nothing if `span` is `None`, so
+    /// that callers can pass the result of a span lookup directly.
+    pub fn with_primary(
+        mut self,
+        span: impl Into<Option<Span>>,
+        message: impl Into<String>,
+    ) -> Self {
+        if let Some(span) = span.into() {
+            self.primary = Some(Label {
+                span,
+                message: message.into(),
+            });
+        }
+        self
+    }
+
+    /// Adds a secondary label. Does nothing if `span` is `None`.
+    pub fn with_secondary(
+        mut self,
+        span: impl Into<Option<Span>>,
+        message: impl Into<String>,
+    ) -> Self {
+        if let Some(span) = span.into() {
+            self.secondary.push(Label {
+                span,
+                message: message.into(),
+            });
+        }
+        self
+    }
+
+    /// Renders the diagnostic for humans, quoting the lines of
+    /// `source` that the labels point at:
+    ///
+    /// ```notrust
+    /// overlapping impls of trait `Foo`
+    ///  --> 3:18
+    ///   |
+    /// 3 |             impl Foo for Bar { }
+    ///   |                  ^^^ overlapping impl
+    /// ```
+    ///
+    /// The message is not prefixed with `error: `, so that callers
+    /// can decide how to present it. `source` must be the text the
+    /// program was parsed from, otherwise the spans are meaningless.
+    pub fn render(&self, source: &str) -> String {
+        let mut output = self.message.clone();
+        for label in self.primary.iter().chain(&self.secondary) {
+            render_label(&mut output, source, label);
+        }
+        output
+    }
+}
+
+fn render_label(output: &mut String, source: &str, label: &Label) {
+    let lo = label.span.lo.min(source.len());
+    let hi = label.span.hi.clamp(lo, source.len());
+    let line_start = source[..lo].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[lo..].find('\n').map_or(source.len(), |i| lo + i);
+    let line_number = source[..lo].matches('\n').count() + 1;
+    let line = &source[line_start..line_end];
+
+    // Columns are counted in characters, not bytes, so the carets
+    // line up with the quoted text.
+    let column = source[line_start..lo].chars().count();
+    let width = source[lo..hi.min(line_end)].chars().count().max(1);
+
+    let gutter = " ".repeat(line_number.to_string().len());
+    let _ = write!(
+        output,
+        "\n{gutter}--> {line}:{col}\n{gutter} |\n{number} | {text}\n{gutter} | {pad}{carets}",
+        gutter = gutter,
+        line = line_number,
+        col = column + 1,
+        number = line_number,
+        text = line,
+        pad = " ".repeat(column),
+        carets = "^".repeat(width),
+    );
+    if !label.message.is_empty() {
+        let _ = write!(output, " {}", label.message);
+    }
+}
+
 #[derive(Debug)]
 pub enum RustIrError {
     InvalidParameterName(Identifier),
@@ -205,3 +334,52 @@ impl std::fmt::Display for RustIrError {
 }
 
 impl std::error::Error for RustIrError {}
+
+impl RustIrError {
+    /// The identifier the error is about, if any.
+    fn identifier(&self) -> Option<&Identifier> {
+        match self {
+            RustIrError::InvalidParameterName(name)
+            | RustIrError::InvalidTraitName(name)
+            | RustIrError::NotTrait(name)
+            | RustIrError::NotStruct(name)
+            | RustIrError::AutoTraitAssociatedTypes(name)
+            | RustIrError::AutoTraitParameters(name)
+            | RustIrError::AutoTraitWhereClauses(name)
+            | RustIrError::InvalidFundamentalTypesParameters(name)
+            | RustIrError::NegativeImplAssociatedValues(name)
+            | RustIrError::MissingAssociatedType(name)
+            | RustIrError::CannotApplyTypeParameter(name) => Some(name),
+            RustIrError::IncorrectNumberOfVarianceParameters { identifier, .. }
+            | RustIrError::IncorrectNumberOfTypeParameters { identifier, .. }
+            | RustIrError::IncorrectNumberOfAssociatedTypeParameters { identifier, .. }
+            | RustIrError::IncorrectParameterKind { identifier, .. }
+            | RustIrError::IncorrectTraitParameterKind { identifier, .. }
+            | RustIrError::IncorrectAssociatedTypeParameterKind { identifier, .. } => {
+                Some(identifier)
+            }
+            RustIrError::DuplicateOrShadowedParameters | RustIrError::InvalidExternAbi(_) => None,
+        }
+    }
+
+    /// A short description to show next to the identifier's span.
+    fn label(&self) -> &'static str {
+        match self {
+            RustIrError::InvalidParameterName(_) => "not found in this scope",
+            RustIrError::InvalidTraitName(_) => "not a known trait",
+            RustIrError::NotTrait(_) => "not a trait",
+            RustIrError::NotStruct(_) => "not a struct",
+            RustIrError::CannotApplyTypeParameter(_) => "type parameter applied to arguments",
+            RustIrError::MissingAssociatedType(_) => "not found in trait",
+            RustIrError::IncorrectNumberOfVarianceParameters { .. }
+            | RustIrError::IncorrectNumberOfTypeParameters { .. }
+            | RustIrError::IncorrectNumberOfAssociatedTypeParameters { .. } => {
+                "wrong number of parameters"
+            }
+            RustIrError::IncorrectParameterKind { .. }
+            | RustIrError::IncorrectTraitParameterKind { .. }
+            | RustIrError::IncorrectAssociatedTypeParameterKind { .. } => "wrong parameter kind",
+            _ => "",
+        }
+    }
+}
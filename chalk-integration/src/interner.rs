@@ -1,6 +1,6 @@
 use crate::tls;
 use chalk_ir::{
-    interner::{HasInterner, Interner},
+    interner::{HasInterner, Interner, SerdeInterner},
     TyKind,
 };
 use chalk_ir::{
@@ -13,6 +13,7 @@ use chalk_ir::{
     GenericArg, GenericArgData, Goal, GoalData, LifetimeData, ProgramClause, ProgramClauseData,
     QuantifiedWhereClause, Variance,
 };
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::fmt::Debug;
 use std::sync::Arc;
@@ -20,7 +21,7 @@ use string_cache::DefaultAtom;
 
 pub type Identifier = DefaultAtom;
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct RawId {
     pub index: u32,
 }
@@ -31,7 +32,7 @@ impl Debug for RawId {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ChalkFnAbi {
     Rust,
     C,
@@ -389,6 +390,8 @@ impl Interner for ChalkIr {
     }
 }
 
+impl SerdeInterner for ChalkIr {}
+
 impl HasInterner for ChalkIr {
     type Interner = ChalkIr;
 }
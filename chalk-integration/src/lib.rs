@@ -8,15 +8,19 @@ pub mod lowering;
 pub mod program;
 pub mod program_environment;
 pub mod query;
+pub mod reduce;
+pub mod solver_cache;
 pub mod test_macros;
 pub mod tls;
 
+use chalk_engine::slg::SubgoalSelection;
 use chalk_engine::solve::SLGSolver;
 use chalk_ir::interner::HasInterner;
 use chalk_ir::Binders;
 use chalk_recursive::{Cache, RecursiveSolver};
 use chalk_solve::Solver;
 use interner::ChalkIr;
+use solver_cache::SolverCache;
 
 pub use interner::{Identifier, RawId};
 
@@ -92,24 +96,57 @@ impl SolverChoice {
     }
 
     pub fn into_solver(self) -> Box<dyn Solver<ChalkIr>> {
+        self.build_solver(self.new_cache(), false, SubgoalSelection::default())
+    }
+
+    /// Creates an empty cache for the solver to keep its results in, if it
+    /// uses one.
+    pub fn new_cache(self) -> Option<SolverCache> {
+        match self {
+            SolverChoice::Recursive {
+                caching_enabled: true,
+                ..
+            } => Some(Cache::default()),
+            _ => None,
+        }
+    }
+
+    /// Creates a solver that keeps its results in `cache` (which is ignored
+    /// by solvers that don't use one), along with the database reads they
+    /// were computed from, so that they can be reused once the cache is
+    /// revalidated for a changed program.
+    pub fn into_solver_with_cache(self, cache: Option<SolverCache>) -> Box<dyn Solver<ChalkIr>> {
+        self.build_solver(cache, true, SubgoalSelection::default())
+    }
+
+    /// Creates the solver, with `subgoal_selection` picking the order in
+    /// which the SLG solver works on subgoals.
+    pub(crate) fn build_solver(
+        self,
+        cache: Option<SolverCache>,
+        track_reads: bool,
+        subgoal_selection: SubgoalSelection,
+    ) -> Box<dyn Solver<ChalkIr>> {
         match self {
             SolverChoice::SLG {
                 max_size,
                 expected_answers,
-            } => Box::new(SLGSolver::new(max_size, expected_answers)),
+            } => Box::new(
+                SLGSolver::new(max_size, expected_answers)
+                    .with_subgoal_selection(subgoal_selection),
+            ),
             SolverChoice::Recursive {
-                overflow_depth,
-                caching_enabled,
-                max_size,
-            } => Box::new(RecursiveSolver::new(
                 overflow_depth,
                 max_size,
-                if caching_enabled {
-                    Some(Cache::default())
+                ..
+            } => {
+                let solver = RecursiveSolver::new(overflow_depth, max_size, cache);
+                if track_reads {
+                    Box::new(solver.with_read_tracking())
                 } else {
-                    None
-                },
-            )),
+                    Box::new(solver)
+                }
+            }
         }
     }
 }
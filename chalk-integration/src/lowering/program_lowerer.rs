@@ -15,7 +15,7 @@ use string_cache::DefaultAtom as Atom;
 
 use super::{env::*, lower_adt_size_align, Lower, LowerParameterMap, LowerWithEnv, FIXME_SELF};
 use crate::error::RustIrError;
-use crate::program::Program as LoweredProgram;
+use crate::program::{ItemSpans, ParameterNames, Program as LoweredProgram};
 use crate::RawId;
 use crate::{interner::ChalkIr, TypeKind, TypeSort};
 
@@ -160,8 +160,14 @@ impl ProgramLowerer {
         let mut coroutine_witness_data = BTreeMap::new();
         let mut hidden_opaque_types = BTreeMap::new();
         let mut custom_clauses = Vec::new();
+        let mut item_spans = ItemSpans::default();
+        let mut parameter_names = ParameterNames::default();
 
         for (item, &raw_id) in program.items.iter().zip(raw_ids) {
+            if let Some(span) = item.span() {
+                item_spans.insert(raw_id, span);
+            }
+
             let empty_env = Env {
                 adt_ids: &self.adt_ids,
                 adt_kinds: &self.adt_kinds,
@@ -185,6 +191,7 @@ impl ProgramLowerer {
                 Item::AdtDefn(ref d) => {
                     let identifier = d.name.clone();
                     let adt_id = AdtId(raw_id);
+                    parameter_names.insert(raw_id, names_of(&d.all_parameters()));
                     adt_data.insert(adt_id, Arc::new((d, adt_id).lower(&empty_env)?));
                     adt_reprs.insert(adt_id, Arc::new(d.repr.lower(&empty_env)?));
                     adt_size_aligns.insert(adt_id, Arc::new(lower_adt_size_align(&d.flags)));
@@ -319,6 +326,7 @@ impl ProgramLowerer {
                         (impl_defn, impl_id, &self.associated_ty_value_ids).lower(&empty_env)?,
                     );
                     impl_data.insert(impl_id, impl_datum.clone());
+                    parameter_names.insert(raw_id, names_of(&impl_defn.all_parameters()));
                     let trait_id = impl_datum.trait_id();
 
                     for atv in &impl_defn.assoc_ty_values {
@@ -332,6 +340,7 @@ impl ProgramLowerer {
                         // with the parameters from the impl.
                         let mut variable_kinds = atv.all_parameters();
                         variable_kinds.extend(impl_defn.all_parameters());
+                        parameter_names.insert(atv_id.0, names_of(&variable_kinds));
 
                         let value = empty_env.in_binders(variable_kinds, |env| {
                             Ok(rust_ir::AssociatedTyValueBound {
@@ -359,6 +368,7 @@ impl ProgramLowerer {
                             .iter()
                             .map(|k| k.lower())
                             .collect::<Vec<_>>();
+                        parameter_names.insert(opaque_ty_id.0, names_of(&variable_kinds));
 
                         // Introduce the parameters declared on the opaque type definition.
                         // So if we have `type Foo<P1..Pn> = impl Trait<T1..Tn>`, this would introduce `P1..Pn`
@@ -504,10 +514,20 @@ impl ProgramLowerer {
             custom_clauses,
             object_safe_traits: self.object_safe_traits,
             foreign_ty_ids: self.foreign_ty_ids,
+            item_spans,
+            parameter_names,
         })
     }
 }
 
+/// The names of `parameters`, as `ParameterNames` keeps them.
+fn names_of(parameters: &[chalk_ir::WithKind<ChalkIr, Atom>]) -> Vec<String> {
+    parameters
+        .iter()
+        .map(|parameter| parameter.skip_kind().trim_start_matches('\'').to_string())
+        .collect()
+}
+
 trait LowerTypeKind {
     fn lower_type_kind(&self) -> LowerResult<TypeKind>;
 }
@@ -1,5 +1,5 @@
 use crate::interner::ChalkIr;
-use crate::{tls, Identifier, TypeKind};
+use crate::{tls, Identifier, RawId, TypeKind};
 use chalk_ir::{could_match::CouldMatch, UnificationDatabase};
 use chalk_ir::{debug::Angle, Variance};
 use chalk_ir::{
@@ -8,6 +8,7 @@ use chalk_ir::{
     OpaqueTyId, ProgramClause, ProgramClauseImplication, ProgramClauses, ProjectionTy, Scalar,
     Substitution, TraitId, Ty, TyKind, UintTy, Variances,
 };
+use chalk_parse::ast::Span;
 use chalk_solve::rust_ir::{
     AdtDatum, AdtRepr, AdtSizeAlign, AssociatedTyDatum, AssociatedTyValue, AssociatedTyValueId,
     ClosureKind, CoroutineDatum, CoroutineWitnessDatum, FnDefDatum, FnDefInputsAndOutputDatum,
@@ -107,8 +108,70 @@ pub struct Program {
 
     /// For each foreign type `extern { type A; }`
     pub foreign_ty_ids: BTreeMap<Identifier, ForeignDefId<ChalkIr>>,
+
+    /// For each named item: the span of its name in the program text
+    /// (for impls, the span of the implemented trait's name). Used to
+    /// attach source locations to diagnostics.
+    pub item_spans: ItemSpans,
+
+    /// For ADTs, impls, associated type values and opaque types: the names
+    /// of their parameters, in the order of their binders. Used to show
+    /// goals about them in diagnostics.
+    pub parameter_names: ParameterNames,
+}
+
+/// The spans of the items in a program, keyed by item id.
+///
+/// Spans only matter for error reporting, so they are ignored when
+/// comparing programs: printing a program and parsing it back yields
+/// an equal program even though the spans have moved.
+#[derive(Clone, Debug, Default)]
+pub struct ItemSpans(BTreeMap<RawId, Span>);
+
+impl ItemSpans {
+    pub fn insert(&mut self, id: RawId, span: Span) {
+        self.0.insert(id, span);
+    }
+
+    pub fn get(&self, id: RawId) -> Option<Span> {
+        self.0.get(&id).copied()
+    }
+}
+
+impl PartialEq for ItemSpans {
+    fn eq(&self, _other: &ItemSpans) -> bool {
+        true
+    }
+}
+
+impl Eq for ItemSpans {}
+
+/// The names of the parameters of the items in a program, keyed by item id.
+/// Lifetime names are kept without their `'`.
+///
+/// Like spans, the names only matter for error reporting, so they are
+/// ignored when comparing programs.
+#[derive(Clone, Debug, Default)]
+pub struct ParameterNames(BTreeMap<RawId, Vec<String>>);
+
+impl ParameterNames {
+    pub fn insert(&mut self, id: RawId, names: Vec<String>) {
+        self.0.insert(id, names);
+    }
+
+    pub fn get(&self, id: RawId) -> &[String] {
+        self.0.get(&id).map_or(&[], |names| names)
+    }
 }
 
+impl PartialEq for ParameterNames {
+    fn eq(&self, _other: &ParameterNames) -> bool {
+        true
+    }
+}
+
+impl Eq for ParameterNames {}
+
 impl Program {
     /// Returns the ids for all impls declared in this crate.
     pub(crate) fn local_impl_ids(&self) -> Vec<ImplId<ChalkIr>> {
@@ -118,6 +181,11 @@ impl Program {
             .map(|(&impl_id, _)| impl_id)
             .collect()
     }
+
+    /// Returns the span of the item with the given id, if it has one.
+    pub fn item_span(&self, id: RawId) -> Option<Span> {
+        self.item_spans.get(id)
+    }
 }
 
 impl tls::DebugContext for Program {
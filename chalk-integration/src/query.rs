@@ -1,18 +1,20 @@
 // https://crates.io/crates/salsa
 // hello world https://github.com/salsa-rs/salsa/blob/master/examples/hello_world/main.rs
 
-use crate::error::ChalkError;
+use crate::error::{ChalkError, Diagnostic};
 use crate::interner::ChalkIr;
 use crate::lowering::Lower;
 use crate::program::Program;
 use crate::program_environment::ProgramEnvironment;
+use crate::solver_cache::SolverCache;
 use crate::tls;
-use crate::SolverChoice;
+use crate::{RawId, SolverChoice};
+use chalk_engine::slg::SubgoalSelection;
 use chalk_ir::TraitId;
 use chalk_solve::clauses::builder::ClauseBuilder;
 use chalk_solve::clauses::program_clauses::ToProgramClauses;
 use chalk_solve::coherence::orphan;
-use chalk_solve::coherence::{CoherenceSolver, SpecializationPriorities};
+use chalk_solve::coherence::{CoherenceError, CoherenceSolver, SpecializationPriorities};
 use chalk_solve::wf;
 use chalk_solve::RustIrDatabase;
 use chalk_solve::Solver;
@@ -34,6 +36,15 @@ pub trait LoweringDatabase:
     #[salsa::input]
     fn solver_choice(&self) -> SolverChoice;
 
+    /// Whether the solver collects statistics about the work it does (see
+    /// `ChalkDatabase::solver_stats`).
+    #[salsa::input]
+    fn collect_solver_stats(&self) -> bool;
+
+    /// The order in which the SLG solver works on the subgoals of a clause.
+    #[salsa::input]
+    fn subgoal_selection(&self) -> SubgoalSelection;
+
     fn program_ir(&self) -> Result<Arc<Program>, ChalkError>;
 
     /// Performs coherence check and computes which impls specialize
@@ -55,9 +66,23 @@ pub trait LoweringDatabase:
     /// mutex. Moreover, if the set of program clauses change, that
     /// cached state becomes invalid, so the query is marked as
     /// volatile, thus ensuring that the solver is recreated in every
-    /// revision (i.e., each time source program changes).
+    /// revision (i.e., each time source program changes). The results it
+    /// caches are kept across revisions though, see `solver_cache`.
     // HACK: salsa requires that queries return types that implement `Eq`
     fn solver(&self) -> ArcEq<Mutex<Box<dyn Solver<ChalkIr>>>>;
+
+    /// The cache the solver keeps its results in, if it has one. Unlike the
+    /// solver, the cache is kept when the program changes; it is only
+    /// recreated when the solver choice does.
+    ///
+    /// Only the recursive solver has one: the SLG solver keeps its answers
+    /// in its tables, which are dropped along with the solver.
+    fn persistent_solver_cache(&self) -> Option<ArcEq<SolverCache>>;
+
+    /// The solver's cache, revalidated for the current program: results
+    /// computed for a previous program are only reused if the database reads
+    /// they were computed from still give the same answers.
+    fn solver_cache(&self) -> Option<ArcEq<SolverCache>>;
 }
 
 // Needed to go from dyn LoweringDatabase -> dyn RustIrDatabase
@@ -134,7 +159,9 @@ fn orphan_check(db: &dyn LoweringDatabase) -> Result<(), ChalkError> {
         let local_impls = program.local_impl_ids();
         for impl_id in local_impls {
             let mut solver = db.solver_choice().into_solver();
-            orphan::perform_orphan_check::<ChalkIr>(db.upcast(), &mut *solver, impl_id)?;
+            orphan::perform_orphan_check::<ChalkIr>(db.upcast(), &mut *solver, impl_id).map_err(
+                |e| item_error(&program, e.display(db.upcast()), impl_id.0, "orphan impl"),
+            )?;
         }
         Ok(())
     })
@@ -153,7 +180,9 @@ fn coherence(
             .map(|&trait_id| {
                 let solver: CoherenceSolver<ChalkIr> =
                     CoherenceSolver::new(db.upcast(), &solver_builder, trait_id);
-                let priorities = solver.specialization_priorities()?;
+                let priorities = solver
+                    .specialization_priorities()
+                    .map_err(|e| coherence_error(db, &program, e))?;
                 Ok((trait_id, priorities))
             })
             .collect();
@@ -174,15 +203,21 @@ fn checked_program(db: &dyn LoweringDatabase) -> Result<Arc<Program>, ChalkError
         let solver_builder = || solver_choice.into_solver();
         let solver: wf::WfSolver<ChalkIr> = wf::WfSolver::new(db.upcast(), &solver_builder);
         for &id in program.adt_data.keys() {
-            solver.verify_adt_decl(id)?;
+            solver
+                .verify_adt_decl(id)
+                .map_err(|e| wf_error(db, &program, e, id.0, "ill-formed type"))?;
         }
 
         for &opaque_ty_id in program.opaque_ty_data.keys() {
-            solver.verify_opaque_ty_decl(opaque_ty_id)?;
+            solver
+                .verify_opaque_ty_decl(opaque_ty_id)
+                .map_err(|e| wf_error(db, &program, e, opaque_ty_id.0, "ill-formed opaque type"))?;
         }
 
         for &impl_id in program.impl_data.keys() {
-            solver.verify_trait_impl(impl_id)?;
+            solver
+                .verify_trait_impl(impl_id)
+                .map_err(|e| wf_error(db, &program, e, impl_id.0, "ill-formed impl"))?;
         }
 
         Ok(())
@@ -191,6 +226,59 @@ fn checked_program(db: &dyn LoweringDatabase) -> Result<Arc<Program>, ChalkError
     Ok(program)
 }
 
+/// Converts an error about the item `id` into a `ChalkError` whose
+/// diagnostic points at that item.
+fn item_error(program: &Program, error: impl ToString, id: RawId, label: &str) -> ChalkError {
+    Diagnostic::new(error.to_string())
+        .with_primary(program.item_span(id), label)
+        .into()
+}
+
+/// Converts a well-formedness error about the item `id` into a
+/// `ChalkError`, showing the goals it couldn't prove with the names the
+/// program gives to their parameters.
+fn wf_error(
+    db: &dyn LoweringDatabase,
+    program: &Program,
+    error: wf::WfError<ChalkIr>,
+    id: RawId,
+    label: &str,
+) -> ChalkError {
+    let parameter_names = |parameters| {
+        let id = match parameters {
+            wf::GoalParameters::Adt(id) => id.0,
+            wf::GoalParameters::OpaqueTy(id) => id.0,
+            wf::GoalParameters::Impl(id) => id.0,
+            wf::GoalParameters::AssociatedTyValue(id) => id.0,
+        };
+        program.parameter_names.get(id).to_vec()
+    };
+    let error = error
+        .display(db.upcast())
+        .with_parameter_names(&parameter_names);
+    item_error(program, error, id, label)
+}
+
+/// Converts a coherence error into a `ChalkError` whose diagnostic points
+/// at the impls that overlap.
+fn coherence_error(
+    db: &dyn LoweringDatabase,
+    program: &Program,
+    error: CoherenceError<ChalkIr>,
+) -> ChalkError {
+    let diagnostic = Diagnostic::new(error.display(db.upcast()).to_string());
+    match error {
+        CoherenceError::OverlappingImpls {
+            impls: (first, second),
+            ..
+        } => diagnostic
+            .with_primary(program.item_span(second.0), "conflicting impl")
+            .with_secondary(program.item_span(first.0), "first impl here"),
+        CoherenceError::FailedOrphanCheck(_) => diagnostic,
+    }
+    .into()
+}
+
 fn environment(db: &dyn LoweringDatabase) -> Result<Arc<ProgramEnvironment>, ChalkError> {
     let program = db.program_ir()?;
 
@@ -253,5 +341,22 @@ fn environment(db: &dyn LoweringDatabase) -> Result<Arc<ProgramEnvironment>, Cha
 fn solver(db: &dyn LoweringDatabase) -> ArcEq<Mutex<Box<dyn Solver<ChalkIr>>>> {
     db.salsa_runtime().report_untracked_read();
     let choice = db.solver_choice();
-    ArcEq::new(Mutex::new(choice.into_solver()))
+    let cache = db.solver_cache().map(|cache| (**cache).clone());
+    let mut solver = choice.build_solver(cache, true, db.subgoal_selection());
+    if db.collect_solver_stats() {
+        solver.collect_stats();
+    }
+    ArcEq::new(Mutex::new(solver))
+}
+
+fn persistent_solver_cache(db: &dyn LoweringDatabase) -> Option<ArcEq<SolverCache>> {
+    db.solver_choice().new_cache().map(ArcEq::new)
+}
+
+fn solver_cache(db: &dyn LoweringDatabase) -> Option<ArcEq<SolverCache>> {
+    // Re-executed whenever the program text changes.
+    let _ = db.program_text();
+    let cache = db.persistent_solver_cache()?;
+    cache.revalidate();
+    Some(cache)
 }
@@ -0,0 +1,361 @@
+//! Shrinking a program while it keeps some property, such as making the
+//! solvers disagree on a goal, so that bug reports are easier to look into.
+//!
+//! This is delta debugging on the program text: items, the members of their
+//! bodies (fields, variants, associated types), where clauses and generic
+//! parameters are removed, in chunks of decreasing size, for as long as the
+//! program still passes `checked_program` and keeps the property. Working on
+//! the text (rather than on the lowered program) keeps the comments and
+//! layout of what is left, and anything that no longer lowers is simply
+//! rejected.
+
+use crate::db::ChalkDatabase;
+use crate::error::{ChalkError, Diagnostic};
+use crate::query::LoweringDatabase;
+use crate::SolverChoice;
+use chalk_solve::ext::*;
+use chalk_solve::{RustIrDatabase, Solution};
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+
+/// A property of a goal in a program, which `reduce` preserves.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Predicate {
+    /// The SLG and the recursive solver (with their default settings) give
+    /// different solutions.
+    SolversDisagree,
+    /// Solving the goal with the given solver panics.
+    Panics(SolverChoice),
+    /// The given solver gives an ambiguous solution.
+    Ambiguous(SolverChoice),
+}
+
+impl Predicate {
+    /// Checks whether the predicate holds for `goal` in `program_text`.
+    /// Programs that don't pass `checked_program`, or in which the goal
+    /// can't be lowered, don't satisfy any predicate.
+    pub fn holds(&self, program_text: &str, goal: &str) -> bool {
+        let solver_choice = match *self {
+            Predicate::SolversDisagree => SolverChoice::default(),
+            Predicate::Panics(solver_choice) | Predicate::Ambiguous(solver_choice) => solver_choice,
+        };
+        let db = ChalkDatabase::with(program_text, solver_choice);
+        let goal = panic::catch_unwind(AssertUnwindSafe(|| {
+            db.checked_program().ok()?;
+            let goal = db.parse_and_lower_goal(goal).ok()?;
+            Some(goal.into_peeled_goal(db.interner()))
+        }));
+        let Ok(Some(goal)) = goal else {
+            return false;
+        };
+
+        db.with_program(|_| match self {
+            Predicate::SolversDisagree => {
+                let solve = |solver_choice: SolverChoice| {
+                    panic::catch_unwind(AssertUnwindSafe(|| {
+                        solver_choice.into_solver().solve(&db, &goal)
+                    }))
+                };
+                match (
+                    solve(SolverChoice::slg_default()),
+                    solve(SolverChoice::recursive_default()),
+                ) {
+                    (Ok(slg), Ok(recursive)) => slg != recursive,
+                    _ => false,
+                }
+            }
+            Predicate::Panics(_) => {
+                panic::catch_unwind(AssertUnwindSafe(|| db.solve(&goal))).is_err()
+            }
+            Predicate::Ambiguous(_) => matches!(
+                panic::catch_unwind(AssertUnwindSafe(|| db.solve(&goal))),
+                Ok(Some(Solution::Ambig(_)))
+            ),
+        })
+    }
+}
+
+/// Removes as much of `program_text` as possible while `predicate` still
+/// holds for `goal`. Fails if it doesn't hold to begin with.
+pub fn reduce(program_text: &str, goal: &str, predicate: Predicate) -> Result<String, ChalkError> {
+    if !predicate.holds(program_text, goal) {
+        return Err(Diagnostic::new(format!(
+            "the goal `{}` does not satisfy {:?} to begin with",
+            goal, predicate
+        ))
+        .into());
+    }
+    Ok(reduce_with(program_text, |text| {
+        predicate.holds(text, goal)
+    }))
+}
+
+/// Removes as much of `program_text` as possible while `holds` returns true
+/// for it. `holds` should return true for `program_text` itself.
+pub fn reduce_with(program_text: &str, mut holds: impl FnMut(&str) -> bool) -> String {
+    let passes: [Candidates; 4] = [items, members, where_clauses, generic_params];
+    let mut text = program_text.to_string();
+    loop {
+        let mut progress = false;
+        for pass in passes {
+            progress |= reduce_pass(&mut text, pass, &mut holds);
+        }
+        if !progress {
+            return tidy(&text);
+        }
+    }
+}
+
+/// Finds the ranges of a program's text that a pass tries to remove.
+type Candidates = fn(&str) -> Vec<Range<usize>>;
+
+/// Removes the parts of `text` found by `candidates`, trying chunks of them
+/// at a time, starting with half of them and halving down to single ones.
+/// Returns whether anything was removed.
+fn reduce_pass(
+    text: &mut String,
+    candidates: Candidates,
+    holds: &mut impl FnMut(&str) -> bool,
+) -> bool {
+    let mut progress = false;
+    let mut chunk = (candidates(text).len() / 2).max(1);
+    loop {
+        // The candidates are found again after each removal, so that their
+        // ranges are up to date; the ones after a removed chunk move up.
+        let mut start = 0;
+        loop {
+            let ranges = candidates(text);
+            if start >= ranges.len() {
+                break;
+            }
+            let end = (start + chunk).min(ranges.len());
+            let candidate = remove(text, &ranges[start..end]);
+            if holds(&candidate) {
+                *text = candidate;
+                progress = true;
+            } else {
+                start = end;
+            }
+        }
+        if chunk == 1 {
+            return progress;
+        }
+        chunk /= 2;
+    }
+}
+
+/// Removes the (possibly overlapping) `ranges` from `text`.
+fn remove(text: &str, ranges: &[Range<usize>]) -> String {
+    let mut ranges = ranges.to_vec();
+    ranges.sort_by_key(|range| range.start);
+    let mut result = String::with_capacity(text.len());
+    let mut pos = 0;
+    for range in ranges {
+        if range.start > pos {
+            result.push_str(&text[pos..range.start]);
+        }
+        pos = pos.max(range.end);
+    }
+    result.push_str(&text[pos..]);
+    result
+}
+
+/// Removes trailing whitespace and runs of blank lines left by `reduce`.
+fn tidy(text: &str) -> String {
+    let mut result = String::new();
+    let mut blank = true;
+    for line in text.lines().map(str::trim_end) {
+        if line.is_empty() && blank {
+            continue;
+        }
+        blank = line.is_empty();
+        result.push_str(line);
+        result.push('\n');
+    }
+    result.truncate(result.trim_end().len());
+    result.push('\n');
+    result
+}
+
+/// Splits `text` into tokens: words, the `->` arrow, string literals and
+/// single characters. Whitespace and comments are skipped.
+fn tokens(text: &str) -> Vec<Range<usize>> {
+    let mut tokens = vec![];
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let mut end = start + c.len_utf8();
+        if c.is_whitespace() {
+            continue;
+        } else if c == '/' && text[end..].starts_with('/') {
+            end = text[start..].find('\n').map_or(text.len(), |n| start + n);
+        } else if c.is_alphanumeric() || c == '_' {
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_') {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+        } else if c == '-' && text[end..].starts_with('>') {
+            end += 1;
+        } else if c == '"' {
+            end = text[end..].find('"').map_or(text.len(), |n| end + n + 1);
+        } else {
+            tokens.push(start..end);
+            continue;
+        }
+        while chars.peek().is_some_and(|&(i, _)| i < end) {
+            chars.next();
+        }
+        if !text[start..end].starts_with("//") {
+            tokens.push(start..end);
+        }
+    }
+    tokens
+}
+
+/// How a token changes the nesting depth.
+fn nesting(token: &str) -> isize {
+    match token {
+        "(" | "[" | "{" | "<" => 1,
+        ")" | "]" | "}" | ">" => -1,
+        _ => 0,
+    }
+}
+
+/// The top-level items, with their attributes and the comments before them.
+/// An item ends with the `}` or `;` that gets back to the top level.
+fn items(text: &str) -> Vec<Range<usize>> {
+    let tokens = tokens(text);
+    let mut items = vec![];
+    let mut depth = 0;
+    let mut item_start = None;
+    let mut previous_end = 0;
+    for token in &tokens {
+        let start = *item_start.get_or_insert(previous_end);
+        let s = &text[token.clone()];
+        if s == "{" || s == "}" {
+            depth += nesting(s);
+        }
+        if depth == 0 && (s == "}" || s == ";") {
+            items.push(start..token.end);
+            item_start = None;
+            previous_end = token.end;
+        }
+    }
+    items
+}
+
+/// The members of the bodies of the top-level items (fields, variants,
+/// associated types and values and the like).
+fn members(text: &str) -> Vec<Range<usize>> {
+    let tokens = tokens(text);
+    let mut members = vec![];
+    let mut braces = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        match &text[token.clone()] {
+            "{" => {
+                braces += 1;
+                if braces == 1 {
+                    members.extend(list(text, &tokens, i + 1, &[";", ","]).1);
+                }
+            }
+            "}" => braces -= 1,
+            _ => {}
+        }
+    }
+    members
+}
+
+/// The where clauses of items, associated types and so on.
+fn where_clauses(text: &str) -> Vec<Range<usize>> {
+    let tokens = tokens(text);
+    let mut clauses = vec![];
+    for (i, token) in tokens.iter().enumerate() {
+        if &text[token.clone()] == "where" {
+            let (end, ranges) = list(text, &tokens, i + 1, &[","]);
+            match ranges.len() {
+                0 => {}
+                // Remove the `where` along with the last clause.
+                1 => clauses.push(token.start..end),
+                _ => clauses.extend(ranges),
+            }
+        }
+    }
+    clauses
+}
+
+/// The generic parameters of items, associated types and so on.
+fn generic_params(text: &str) -> Vec<Range<usize>> {
+    const KEYWORDS: &[&str] = &[
+        "struct",
+        "enum",
+        "union",
+        "trait",
+        "fn",
+        "type",
+        "closure",
+        "coroutine",
+    ];
+    let tokens = tokens(text);
+    let mut params = vec![];
+    for (i, token) in tokens.iter().enumerate() {
+        let open = match &text[token.clone()] {
+            "impl" => i + 1,
+            keyword if KEYWORDS.contains(&keyword) => i + 2,
+            _ => continue,
+        };
+        if tokens.get(open).map(|t| &text[t.clone()]) != Some("<") {
+            continue;
+        }
+        let (end, ranges) = list(text, &tokens, open + 1, &[","]);
+        match ranges.len() {
+            0 => {}
+            // Remove the brackets along with the last parameter.
+            1 => params.push(tokens[open].start..end + 1),
+            _ => params.extend(ranges),
+        }
+    }
+    params
+}
+
+/// Splits the tokens from `start` up to the end of the enclosing brackets
+/// (or to a `{` or `;` at the same level) into a list of entries separated
+/// by `separators`. Returns where the list ends, and the ranges to remove to
+/// remove each entry: up to the start of the next one for all but the last.
+fn list(
+    text: &str,
+    tokens: &[Range<usize>],
+    start: usize,
+    separators: &[&str],
+) -> (usize, Vec<Range<usize>>) {
+    let mut entries: Vec<Range<usize>> = vec![];
+    let mut entry_start = None;
+    let mut depth = 0;
+    let mut end = tokens.get(start).map_or(text.len(), |t| t.start);
+    for token in &tokens[start.min(tokens.len())..] {
+        let s = &text[token.clone()];
+        if depth == 0 && (nesting(s) < 0 || (!separators.contains(&";") && (s == "{" || s == ";")))
+        {
+            end = token.start;
+            break;
+        }
+        depth += nesting(s);
+        let entry = *entry_start.get_or_insert(token.start);
+        end = token.end;
+        if depth == 0 && separators.contains(&s) {
+            entries.push(entry..token.end);
+            entry_start = None;
+        }
+    }
+    if let Some(entry) = entry_start {
+        entries.push(entry..end);
+    }
+
+    let mut ranges: Vec<_> = entries
+        .windows(2)
+        .map(|pair| pair[0].start..pair[1].start)
+        .collect();
+    ranges.extend(entries.last().cloned());
+    (end, ranges)
+}
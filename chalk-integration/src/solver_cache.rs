@@ -0,0 +1,100 @@
+//! Saving the results cached by the recursive solver to disk, so that a
+//! later run on the same program can start with them.
+//!
+//! The results are only valid for the program and solver parameters that
+//! produced them (item ids, for example, depend on the program text), so a
+//! saved cache is tagged with a fingerprint of both, and is ignored when
+//! loaded for anything else.
+//!
+//! The SLG solver has nothing to save: it keeps its answers in its tables,
+//! which hold strands with inference state and are dropped along with the
+//! solver.
+
+use crate::error::{ChalkError, Diagnostic};
+use crate::interner::ChalkIr;
+use crate::SolverChoice;
+use chalk_ir::Fallible;
+use chalk_recursive::{Cache, UCanonicalGoal};
+use chalk_solve::tracking_db::Reads;
+use chalk_solve::Solution;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// The cache the recursive solver keeps its completely solved goals in.
+pub type SolverCache = Cache<UCanonicalGoal<ChalkIr>, Fallible<Solution<ChalkIr>>, Reads<ChalkIr>>;
+
+#[derive(Serialize, Deserialize)]
+struct SavedCache {
+    fingerprint: u64,
+    entries: Vec<(UCanonicalGoal<ChalkIr>, Fallible<Solution<ChalkIr>>)>,
+}
+
+/// Just the fingerprint of a `SavedCache`, so that we can check it without
+/// decoding the entries (which may not even be decodable if they were saved
+/// by another version of chalk).
+#[derive(Deserialize)]
+struct SavedCacheHeader {
+    fingerprint: u64,
+}
+
+/// Computes the fingerprint that results solved for `program_text` with
+/// `solver_choice` are saved with.
+///
+/// The fingerprint is saved to disk, so it must not change between runs.
+/// That rules out `std`'s hashers (and its `Hash` impls), which are only
+/// stable within a single build; we use FNV-1a on the bytes instead.
+pub fn fingerprint(program_text: &str, solver_choice: SolverChoice) -> u64 {
+    let solver_choice = format!("{:?}", solver_choice);
+    let parts = [env!("CARGO_PKG_VERSION"), program_text, &solver_choice];
+    parts.iter().fold(FNV_OFFSET_BASIS, |hash, part| {
+        // Hash the length first, so that moving text from one part to the
+        // next changes the fingerprint.
+        let hash = fnv1a(hash, &(part.len() as u64).to_le_bytes());
+        fnv1a(hash, part.as_bytes())
+    })
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Writes the results in `cache` to `path`, returning how many there were.
+/// The database reads the results were computed from are not saved, so the
+/// loaded results are only used until the program changes.
+pub fn save(cache: &SolverCache, fingerprint: u64, path: &Path) -> Result<usize, ChalkError> {
+    let saved = SavedCache {
+        fingerprint,
+        entries: cache.entries(),
+    };
+    let text = serde_json::to_string(&saved).map_err(|e| error(path, e))?;
+    fs::write(path, text).map_err(|e| error(path, e))?;
+    Ok(saved.entries.len())
+}
+
+/// Adds the results saved at `path` to `cache`, returning how many there
+/// were. Nothing is loaded if the results were saved with a different
+/// fingerprint.
+pub fn load(cache: &SolverCache, fingerprint: u64, path: &Path) -> Result<usize, ChalkError> {
+    let text = fs::read_to_string(path).map_err(|e| error(path, e))?;
+    let header: SavedCacheHeader = serde_json::from_str(&text).map_err(|e| error(path, e))?;
+    if header.fingerprint != fingerprint {
+        return Ok(0);
+    }
+
+    let saved: SavedCache = serde_json::from_str(&text).map_err(|e| error(path, e))?;
+    let count = saved.entries.len();
+    for (goal, result) in saved.entries {
+        cache.insert(goal, result);
+    }
+    Ok(count)
+}
+
+fn error(path: &Path, error: impl std::fmt::Display) -> ChalkError {
+    Diagnostic::new(format!("solver cache `{}`: {}", path.display(), error)).into()
+}
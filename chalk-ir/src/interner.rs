@@ -42,6 +42,9 @@ use std::hash::Hash;
 use std::marker::PhantomData;
 use std::sync::Arc;
 
+#[cfg(feature = "serde")]
+use serde::{de::DeserializeOwned, Serialize};
+
 /// A "interner" encapsulates the concrete representation of
 /// certain "core types" from chalk-ir. All the types in chalk-ir are
 /// parameterized by a `I: Interner`, and so (e.g.) if they want to
@@ -643,6 +646,37 @@ pub trait Interner: Debug + Copy + Eq + Hash + Sized {
     fn variances_data(self, variances: &Self::InternedVariances) -> &[Variance];
 }
 
+/// An interner whose interned representations can be serialized, which
+/// makes all the types in chalk-ir that use it serializable as well (e.g.
+/// to save solver results to disk).
+///
+/// Deserialized values are only meaningful to the interner (and the
+/// program) that produced them: ids, for example, are stored as they are.
+#[cfg(feature = "serde")]
+pub trait SerdeInterner:
+    Interner<
+    InternedType: Serialize + DeserializeOwned,
+    InternedLifetime: Serialize + DeserializeOwned,
+    InternedConst: Serialize + DeserializeOwned,
+    InternedConcreteConst: Serialize + DeserializeOwned,
+    InternedGenericArg: Serialize + DeserializeOwned,
+    InternedGoal: Serialize + DeserializeOwned,
+    InternedGoals: Serialize + DeserializeOwned,
+    InternedSubstitution: Serialize + DeserializeOwned,
+    InternedProgramClauses: Serialize + DeserializeOwned,
+    InternedProgramClause: Serialize + DeserializeOwned,
+    InternedQuantifiedWhereClauses: Serialize + DeserializeOwned,
+    InternedVariableKinds: Serialize + DeserializeOwned,
+    InternedCanonicalVarKinds: Serialize + DeserializeOwned,
+    InternedConstraints: Serialize + DeserializeOwned,
+    InternedVariances: Serialize + DeserializeOwned,
+    DefId: Serialize + DeserializeOwned,
+    InternedAdtId: Serialize + DeserializeOwned,
+    FnAbi: Serialize + DeserializeOwned,
+>
+{
+}
+
 /// Implemented by types that have an associated interner (which
 /// are virtually all of the types in chalk-ir, for example).
 /// This lets us map from a type like `Ty<I>` to the parameter `I`.
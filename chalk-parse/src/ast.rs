@@ -1,7 +1,7 @@
 use std::fmt;
 use string_cache::DefaultAtom as Atom;
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct Span {
     pub lo: usize,
     pub hi: usize,
@@ -31,6 +31,25 @@ pub enum Item {
     Foreign(ForeignDefn),
 }
 
+impl Item {
+    /// The span of the name that identifies this item, used when
+    /// reporting errors about it. Impls are identified by the name of
+    /// the trait they implement; clauses have no name and hence no span.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Item::AdtDefn(defn) => Some(defn.name.span),
+            Item::FnDefn(defn) => Some(defn.name.span),
+            Item::ClosureDefn(defn) => Some(defn.name.span),
+            Item::TraitDefn(defn) => Some(defn.name.span),
+            Item::OpaqueTyDefn(defn) => Some(defn.name.span),
+            Item::CoroutineDefn(defn) => Some(defn.name.span),
+            Item::Impl(defn) => Some(defn.trait_ref.trait_name.span),
+            Item::Clause(_) => None,
+            Item::Foreign(ForeignDefn(name)) => Some(name.span),
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct ForeignDefn(pub Identifier);
 
@@ -0,0 +1,118 @@
+use crate::fixed_point::Cache;
+use crate::recursive::RecursiveSolver;
+use crate::UCanonicalGoal;
+use chalk_ir::interner::Interner;
+use chalk_ir::Fallible;
+use chalk_solve::tracking_db::Reads;
+use chalk_solve::{RustIrDatabase, Solution, Solver};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Solves batches of independent goals on several threads, with a cache that
+/// is shared amongst all of them (and across batches).
+///
+/// The solutions do not depend on the number of threads, or on how the
+/// goals happen to be scheduled: goals are solved in rounds of
+/// `ROUND_SIZE`, and the results each goal adds to the cache are only shared
+/// with the rounds after its own, in the order of the goals.
+///
+/// This means that the goals of a round don't see each other's results: two
+/// goals in the same round which need the same subgoal both solve it. We
+/// accept that because sharing results within a round can't be made
+/// deterministic. With the recursive solver, whether a result is cached can
+/// make a difference for goals involved in cycles, so a goal's solution
+/// would depend on which of the other goals happened to finish before it
+/// started. Only results from previous rounds are guaranteed to be there no
+/// matter what, so for batches with a lot of overlap between their goals,
+/// most of the sharing happens from the second round on.
+pub struct BatchSolver<I: Interner> {
+    overflow_depth: usize,
+    max_size: usize,
+    threads: usize,
+    cache: BatchCache<I>,
+}
+
+type BatchCache<I> = Cache<UCanonicalGoal<I>, Fallible<Solution<I>>, Reads<I>>;
+
+/// The number of goals solved at once, before their results are shared.
+/// Smaller rounds share results sooner, but leave threads idle while the
+/// slowest goal of each round finishes. This can't depend on the number of
+/// threads, or the solutions would too.
+const ROUND_SIZE: usize = 64;
+
+impl<I: Interner> BatchSolver<I>
+where
+    UCanonicalGoal<I>: Send + Sync,
+    Solution<I>: Send,
+    Reads<I>: Send,
+{
+    pub fn new(
+        overflow_depth: usize,
+        max_size: usize,
+        threads: usize,
+        cache: BatchCache<I>,
+    ) -> Self {
+        Self {
+            overflow_depth,
+            max_size,
+            threads: threads.max(1),
+            cache,
+        }
+    }
+
+    /// Solves each of the `goals`, returning their solutions in the same
+    /// order.
+    pub fn solve(
+        &self,
+        program: &(dyn RustIrDatabase<I> + Sync),
+        goals: &[UCanonicalGoal<I>],
+    ) -> Vec<Option<Solution<I>>> {
+        let mut solutions = Vec::with_capacity(goals.len());
+        for round in goals.chunks(ROUND_SIZE) {
+            let mut results = self.solve_round(program, round);
+            results.sort_by_key(|(index, _, _)| *index);
+            for (_, solution, cache) in results {
+                cache.merge_into_base();
+                solutions.push(solution);
+            }
+        }
+        solutions
+    }
+
+    /// Solves the goals of one round, each with its own cache on top of the
+    /// shared one. Returns the index of each goal along with its solution
+    /// and cache, in no particular order.
+    fn solve_round(
+        &self,
+        program: &(dyn RustIrDatabase<I> + Sync),
+        goals: &[UCanonicalGoal<I>],
+    ) -> Vec<(usize, Option<Solution<I>>, BatchCache<I>)> {
+        let next_goal = AtomicUsize::new(0);
+        let worker = || {
+            let mut results = vec![];
+            loop {
+                let index = next_goal.fetch_add(1, Ordering::Relaxed);
+                let Some(goal) = goals.get(index) else {
+                    return results;
+                };
+                let cache = Cache::layered_over(&self.cache);
+                // The shared cache outlives program changes, so its results
+                // need their reads to be reused after those.
+                let mut solver =
+                    RecursiveSolver::new(self.overflow_depth, self.max_size, Some(cache.clone()))
+                        .with_read_tracking();
+                let solution = solver.solve(program, goal);
+                results.push((index, solution, cache));
+            }
+        };
+
+        let threads = self.threads.min(goals.len());
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads).map(|_| scope.spawn(worker)).collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        })
+    }
+}
@@ -1,3 +1,4 @@
+use chalk_solve::{CancellationToken, SolverStats};
 use std::fmt::Debug;
 use std::hash::Hash;
 use tracing::debug;
@@ -11,10 +12,11 @@ pub use cache::Cache;
 use search_graph::{DepthFirstNumber, SearchGraph};
 use stack::{Stack, StackDepth};
 
-pub(super) struct RecursiveContext<K, V>
+pub(super) struct RecursiveContext<K, V, R>
 where
     K: Hash + Eq + Debug + Clone,
     V: Debug + Clone,
+    R: Clone,
 {
     stack: Stack,
 
@@ -25,60 +27,105 @@ where
     /// The "cache" stores results for goals that we have completely solved.
     /// Things are added to the cache when we have completely processed their
     /// result.
-    cache: Option<Cache<K, V>>,
+    cache: Option<Cache<K, V, R>>,
 
     /// The maximum size for goals.
     max_size: usize,
+
+    /// Set once `should_continue` has asked us to stop solving the current
+    /// root goal. The results computed after that are incomplete, so they
+    /// are not cached.
+    interrupted: bool,
+
+    /// The statistics collected about the work done, if requested.
+    stats: Option<SolverStats>,
+
+    /// Cancels the current root goal.
+    cancellation: CancellationToken,
 }
 
-pub(super) trait SolverStuff<K, V>: Copy
+pub(super) trait SolverStuff<K, V, R>: Copy
 where
     K: Hash + Eq + Debug + Clone,
     V: Debug + Clone,
+    R: Clone,
 {
     fn is_coinductive_goal(self, goal: &K) -> bool;
     fn initial_value(self, goal: &K, coinductive_goal: bool) -> V;
     fn solve_iteration(
         self,
-        context: &mut RecursiveContext<K, V>,
+        context: &mut RecursiveContext<K, V, R>,
         goal: &K,
         minimums: &mut Minimums,
         should_continue: impl std::ops::Fn() -> bool + Clone,
     ) -> V;
     fn reached_fixed_point(self, old_value: &V, new_value: &V) -> bool;
     fn error_value(self) -> V;
+    /// The answer to a goal whose answers depend on themselves not holding.
+    fn negative_cycle_value(self) -> V;
+
+    /// Starts recording the reads made while solving a new goal.
+    fn start_reads(self);
+    /// Stops recording the reads for the innermost goal and returns them,
+    /// if they are being recorded.
+    fn finish_reads(self) -> Option<R>;
+    /// Records that the innermost goal depends on a cached result, which
+    /// was computed from `reads` (if they are known).
+    fn reuse_reads(self, reads: Option<&R>);
+    /// Checks whether a cached result computed from `reads` still holds.
+    fn reads_hold(self, reads: &R) -> bool;
 }
 
 /// The `minimums` struct is used while solving to track whether we encountered
 /// any cycles in the process.
+///
+/// `positive` is the earliest goal in the search graph that the answers depend
+/// on. `negative` is the earliest goal that a goal the answers depend on *not*
+/// holding (as in `not { G }`) depends on. Unlike `positive`, it is not
+/// propagated to the goals that depend on ours: it is only used to detect a
+/// cycle through negation, which is when our answers depend on our own
+/// answers not holding.
 #[derive(Copy, Clone, Debug)]
 pub(super) struct Minimums {
     positive: DepthFirstNumber,
+    negative: DepthFirstNumber,
 }
 
 impl Minimums {
     pub fn new() -> Self {
         Minimums {
             positive: DepthFirstNumber::MAX,
+            negative: DepthFirstNumber::MAX,
         }
     }
 
     pub fn update_from(&mut self, minimums: Minimums) {
         self.positive = ::std::cmp::min(self.positive, minimums.positive);
     }
+
+    /// Records that the answers depend on the answers found with `minimums`
+    /// not holding.
+    pub fn update_from_negated(&mut self, minimums: Minimums) {
+        self.update_from(minimums);
+        self.negative = ::std::cmp::min(self.negative, minimums.positive);
+    }
 }
 
-impl<K, V> RecursiveContext<K, V>
+impl<K, V, R> RecursiveContext<K, V, R>
 where
     K: Hash + Eq + Debug + Clone,
     V: Debug + Clone,
+    R: Clone,
 {
-    pub fn new(overflow_depth: usize, max_size: usize, cache: Option<Cache<K, V>>) -> Self {
+    pub fn new(overflow_depth: usize, max_size: usize, cache: Option<Cache<K, V, R>>) -> Self {
         RecursiveContext {
             stack: Stack::new(overflow_depth),
             search_graph: SearchGraph::new(),
             cache,
             max_size,
+            interrupted: false,
+            stats: None,
+            cancellation: CancellationToken::new(),
         }
     }
 
@@ -86,6 +133,38 @@ where
         self.max_size
     }
 
+    pub fn interrupt(&mut self) {
+        self.interrupted = true;
+    }
+
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    pub fn set_cancellation(&mut self, cancellation: CancellationToken) {
+        self.cancellation = cancellation;
+    }
+
+    /// Forgets the goals that were being solved when solving the root goal
+    /// was cancelled. Their results were only ever in the search graph, so
+    /// the cache is unaffected.
+    pub fn clear_cancelled(&mut self) {
+        self.stack.clear();
+        self.search_graph = SearchGraph::new();
+    }
+
+    pub fn collect_stats(&mut self) {
+        self.stats.get_or_insert_with(SolverStats::default);
+    }
+
+    pub fn stats(&self) -> Option<&SolverStats> {
+        self.stats.as_ref()
+    }
+
+    pub fn stats_mut(&mut self) -> Option<&mut SolverStats> {
+        self.stats.as_mut()
+    }
+
     /// Solves a canonical goal. The substitution returned in the
     /// solution will be for the fully decomposed goal. For example, given the
     /// program
@@ -104,11 +183,12 @@ where
     pub fn solve_root_goal(
         &mut self,
         canonical_goal: &K,
-        solver_stuff: impl SolverStuff<K, V>,
+        solver_stuff: impl SolverStuff<K, V, R>,
         should_continue: impl std::ops::Fn() -> bool + Clone,
     ) -> V {
         debug!("solve_root_goal(canonical_goal={:?})", canonical_goal);
         assert!(self.stack.is_empty());
+        self.interrupted = false;
         let minimums = &mut Minimums::new();
         self.solve_goal(canonical_goal, minimums, solver_stuff, should_continue)
     }
@@ -121,15 +201,24 @@ where
         &mut self,
         goal: &K,
         minimums: &mut Minimums,
-        solver_stuff: impl SolverStuff<K, V>,
+        solver_stuff: impl SolverStuff<K, V, R>,
         should_continue: impl std::ops::Fn() -> bool + Clone,
     ) -> V {
         // First check the cache.
         if let Some(cache) = &self.cache {
-            if let Some(value) = cache.get(goal) {
+            if let Some((value, reads)) =
+                cache.get_checked(goal, |reads| solver_stuff.reads_hold(reads))
+            {
                 debug!("solve_reduced_goal: cache hit, value={:?}", value);
+                solver_stuff.reuse_reads(reads.as_ref());
+                if let Some(stats) = &mut self.stats {
+                    stats.cache_hits += 1;
+                }
                 return value;
             }
+            if let Some(stats) = &mut self.stats {
+                stats.cache_misses += 1;
+            }
         }
 
         // Next, check if the goal is in the search tree already.
@@ -161,9 +250,14 @@ where
             let initial_solution = solver_stuff.initial_value(goal, coinductive_goal);
             let depth = self.stack.push(coinductive_goal);
             let dfn = self.search_graph.insert(goal, depth, initial_solution);
+            if let Some(stats) = &mut self.stats {
+                stats.tables_created += 1;
+            }
 
+            solver_stuff.start_reads();
             let subgoal_minimums =
                 self.solve_new_subgoal(goal, depth, dfn, solver_stuff, should_continue);
+            let reads = solver_stuff.finish_reads();
 
             self.search_graph[dfn].links = subgoal_minimums;
             self.search_graph[dfn].stack_depth = None;
@@ -172,14 +266,23 @@ where
 
             // Read final result from table.
             let result = self.search_graph[dfn].solution.clone();
+            if let Some(stats) = &mut self.stats {
+                stats.answers_produced += 1;
+            }
 
             // If processing this subgoal did not involve anything
             // outside of its subtree, then we can promote it to the
             // cache now. This is a sort of hack to alleviate the
             // worst of the repeated work that we do during tabling.
+            //
+            // The results of the other goals in the SCC were computed from
+            // a subset of the head's reads, so they are cached with those.
             if subgoal_minimums.positive >= dfn {
-                if let Some(cache) = &mut self.cache {
-                    self.search_graph.move_to_cache(dfn, cache);
+                if self.interrupted {
+                    debug!("solve_reduced_goal: SCC head encountered, rolling back as interrupted");
+                    self.search_graph.rollback_to(dfn);
+                } else if let Some(cache) = &mut self.cache {
+                    self.search_graph.move_to_cache(dfn, cache, reads);
                     debug!("solve_reduced_goal: SCC head encountered, moving to cache");
                 } else {
                     debug!(
@@ -200,7 +303,7 @@ where
         canonical_goal: &K,
         depth: StackDepth,
         dfn: DepthFirstNumber,
-        solver_stuff: impl SolverStuff<K, V>,
+        solver_stuff: impl SolverStuff<K, V, R>,
         should_continue: impl std::ops::Fn() -> bool + Clone,
     ) -> Minimums {
         // We start with `answer = None` and try to solve the goal. At the end of the iteration,
@@ -213,6 +316,9 @@ where
         // the function which maps the loop iteration to `answer` is a nondecreasing function
         // so this function will eventually be constant and the loop terminates.
         loop {
+            if let Some(stats) = &mut self.stats {
+                stats.fixed_point_iterations += 1;
+            }
             let minimums = &mut Minimums::new();
             let current_answer = solver_stuff.solve_iteration(
                 self,
@@ -226,6 +332,17 @@ where
                 current_answer, minimums
             );
 
+            // A goal can't depend on its own answers not holding: it would
+            // hold exactly when it doesn't. The SLG solver treats this as a
+            // bug in the program (or the lowering rules) and panics, but
+            // that would turn a program the user is still writing into a
+            // crash, so we decide that the goal is neither true nor false.
+            if minimums.negative <= dfn {
+                self.stack[depth].read_and_reset_cycle_flag();
+                self.search_graph[dfn].solution = solver_stuff.negative_cycle_value();
+                return *minimums;
+            }
+
             if !self.stack[depth].read_and_reset_cycle_flag() {
                 // None of our subgoals depended on us directly.
                 // We can return.
@@ -7,51 +7,212 @@ use tracing::instrument;
 /// The "cache" stores results for goals that we have completely solved.
 /// Things are added to the cache when we have completely processed their
 /// result, and it can be shared amongst many solvers.
-pub struct Cache<K, V>
+///
+/// Each result can be stored along with the reads `R` that it was computed
+/// from. When the program changes, [`revalidate`] marks all results as
+/// possibly stale; a stale result is only used again once its reads have
+/// been checked to still hold (results without reads are evicted instead).
+///
+/// [`revalidate`]: Cache::revalidate
+pub struct Cache<K, V, R = ()>
 where
     K: Hash + Eq + Debug,
     V: Debug + Clone,
 {
-    data: Arc<Mutex<CacheData<K, V>>>,
+    data: Arc<Mutex<CacheData<K, V, R>>>,
 }
-struct CacheData<K, V>
+struct CacheData<K, V, R>
 where
     K: Hash + Eq + Debug,
     V: Debug + Clone,
 {
-    cache: FxHashMap<K, V>,
+    cache: FxHashMap<K, CacheEntry<V, R>>,
+    /// The cache to look in for results this one does not have.
+    base: Option<Cache<K, V, R>>,
 }
 
-impl<K, V> Cache<K, V>
+struct CacheEntry<V, R> {
+    result: V,
+    reads: Option<R>,
+    /// False if the program may have changed since the result was cached.
+    verified: bool,
+}
+
+impl<K, V, R> Cache<K, V, R>
 where
     K: Hash + Eq + Debug,
     V: Debug + Clone,
+    R: Clone,
 {
     pub fn new() -> Self {
         Self::default()
     }
 
-    /// Record a cache result.
+    /// Creates an empty cache that falls back to `base` for the results it
+    /// does not have. New results are only added to `base` once they are
+    /// moved there with `merge_into_base`.
+    pub fn layered_over(base: &Self) -> Self {
+        let data = CacheData {
+            cache: Default::default(),
+            base: Some(base.clone()),
+        };
+        Self {
+            data: Arc::new(Mutex::new(data)),
+        }
+    }
+
+    /// Moves the results of a cache created with `layered_over` into its
+    /// base. Results the base already has are kept as they are.
+    pub fn merge_into_base(&self) {
+        let (cache, base) = {
+            let mut data = self.data.lock().unwrap();
+            let cache = std::mem::take(&mut data.cache);
+            (cache, data.base.clone())
+        };
+        let base = base.expect("`merge_into_base` on a cache without a base");
+        let mut base_data = base.data.lock().unwrap();
+        for (goal, entry) in cache {
+            base_data.cache.entry(goal).or_insert(entry);
+        }
+    }
+
+    /// Record a cache result. Without the reads it was computed from, the
+    /// result is only kept until the next `revalidate`.
     #[instrument(skip(self))]
     pub fn insert(&self, goal: K, result: V) {
+        self.insert_entry(goal, result, None);
+    }
+
+    /// Record a cache result, along with the reads it was computed from.
+    pub fn insert_with_reads(&self, goal: K, result: V, reads: R) {
+        debug!(?goal, ?result, "Cache insert");
+        self.insert_entry(goal, result, Some(reads));
+    }
+
+    fn insert_entry(&self, goal: K, result: V, reads: Option<R>) {
         let mut data = self.data.lock().unwrap();
-        data.cache.insert(goal, result);
+        let entry = CacheEntry {
+            result,
+            reads,
+            verified: true,
+        };
+        data.cache.insert(goal, entry);
     }
 
-    /// Record a cache result.
+    /// Lookup a cache result that is known to hold for the current program.
     pub fn get(&self, goal: &K) -> Option<V> {
-        let data = self.data.lock().unwrap();
-        if let Some(result) = data.cache.get(goal) {
-            debug!(?goal, ?result, "Cache hit");
-            Some(result.clone())
+        let base = {
+            let data = self.data.lock().unwrap();
+            match data.cache.get(goal) {
+                Some(entry) if entry.verified => {
+                    debug!(?goal, result = ?entry.result, "Cache hit");
+                    return Some(entry.result.clone());
+                }
+                Some(_) => None,
+                None => data.base.clone(),
+            }
+        };
+        match base {
+            Some(base) => base.get(goal),
+            None => {
+                debug!(?goal, "Cache miss");
+                None
+            }
+        }
+    }
+
+    /// Lookup a cache result, along with the reads it was computed from. If
+    /// the program may have changed since the result was cached, `still_hold`
+    /// is asked whether its reads still hold, and the result is evicted if
+    /// they do not.
+    pub fn get_checked(
+        &self,
+        goal: &K,
+        still_hold: impl FnOnce(&R) -> bool,
+    ) -> Option<(V, Option<R>)> {
+        let stale_reads = {
+            let data = self.data.lock().unwrap();
+            match data.cache.get(goal) {
+                None => match data.base.clone() {
+                    Some(base) => {
+                        drop(data);
+                        return base.get_checked(goal, still_hold);
+                    }
+                    None => {
+                        debug!(?goal, "Cache miss");
+                        return None;
+                    }
+                },
+                Some(entry) if entry.verified => {
+                    debug!(?goal, result = ?entry.result, "Cache hit");
+                    return Some((entry.result.clone(), entry.reads.clone()));
+                }
+                Some(entry) => entry.reads.clone(),
+            }
+        };
+
+        // Check the reads without holding the lock: doing so queries the
+        // database, which may take a while.
+        let holds = stale_reads.as_ref().is_some_and(still_hold);
+
+        let mut data = self.data.lock().unwrap();
+        if holds {
+            let entry = data.cache.get_mut(goal)?;
+            entry.verified = true;
+            debug!(?goal, result = ?entry.result, "Cache hit, reads still hold");
+            Some((entry.result.clone(), entry.reads.clone()))
         } else {
-            debug!(?goal, "Cache miss");
+            debug!(?goal, "Cache miss, evicting stale result");
+            data.cache.remove(goal);
             None
         }
     }
+
+    /// Marks all cached results as possibly stale, e.g. because the program
+    /// has changed. They are checked again the next time they are looked up.
+    pub fn revalidate(&self) {
+        let mut data = self.data.lock().unwrap();
+        for entry in data.cache.values_mut() {
+            entry.verified = false;
+        }
+    }
+
+    /// Returns a copy of all the cached results that are known to hold, e.g.
+    /// so that they can be saved and `insert`ed into another cache later on.
+    pub fn entries(&self) -> Vec<(K, V)>
+    where
+        K: Clone,
+    {
+        let data = self.data.lock().unwrap();
+        data.cache
+            .iter()
+            .filter(|(_goal, entry)| entry.verified)
+            .map(|(goal, entry)| (goal.clone(), entry.result.clone()))
+            .collect()
+    }
+
+    /// The number of cached results.
+    pub fn len(&self) -> usize {
+        self.data.lock().unwrap().cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K, V, R> Debug for Cache<K, V, R>
+where
+    K: Hash + Eq + Debug,
+    V: Debug + Clone,
+{
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let len = self.data.lock().unwrap().cache.len();
+        fmt.debug_struct("Cache").field("len", &len).finish()
+    }
 }
 
-impl<K, V> Clone for Cache<K, V>
+impl<K, V, R> Clone for Cache<K, V, R>
 where
     K: Hash + Eq + Debug,
     V: Debug + Clone,
@@ -63,7 +224,7 @@ where
     }
 }
 
-impl<K, V> Default for Cache<K, V>
+impl<K, V, R> Default for Cache<K, V, R>
 where
     K: Hash + Eq + Debug,
     V: Debug + Clone,
@@ -75,7 +236,7 @@ where
     }
 }
 
-impl<K, V> Default for CacheData<K, V>
+impl<K, V, R> Default for CacheData<K, V, R>
 where
     K: Hash + Eq + Debug,
     V: Debug + Clone,
@@ -83,6 +244,7 @@ where
     fn default() -> Self {
         Self {
             cache: Default::default(),
+            base: None,
         }
     }
 }
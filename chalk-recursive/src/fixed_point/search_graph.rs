@@ -77,7 +77,10 @@ where
             goal: goal.clone(),
             solution,
             stack_depth: Some(stack_depth),
-            links: Minimums { positive: dfn },
+            links: Minimums {
+                positive: dfn,
+                negative: DepthFirstNumber::MAX,
+            },
         };
         self.nodes.push(node);
         let previous_index = self.indices.insert(goal.clone(), dfn);
@@ -94,14 +97,23 @@ where
 
     /// Removes all nodes with a depth-first-number greater than or
     /// equal to `dfn`, adding their final solutions into the cache.
-    #[instrument(level = "debug", skip(self, cache))]
-    pub(crate) fn move_to_cache(&mut self, dfn: DepthFirstNumber, cache: &Cache<K, V>) {
+    /// They are all cached with the given `reads`, if they are known.
+    #[instrument(level = "debug", skip(self, cache, reads))]
+    pub(crate) fn move_to_cache<R: Clone>(
+        &mut self,
+        dfn: DepthFirstNumber,
+        cache: &Cache<K, V, R>,
+        reads: Option<R>,
+    ) {
         self.indices.retain(|_key, value| *value < dfn);
         for node in self.nodes.drain(dfn.index..) {
             assert!(node.stack_depth.is_none());
             assert!(node.links.positive >= dfn);
             debug!("caching solution {:#?} for {:#?}", node.solution, node.goal);
-            cache.insert(node.goal, node.solution);
+            match &reads {
+                Some(reads) => cache.insert_with_reads(node.goal, node.solution, reads.clone()),
+                None => cache.insert(node.goal, node.solution),
+            }
         }
     }
 }
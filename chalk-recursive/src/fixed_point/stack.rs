@@ -24,6 +24,10 @@ pub(super) struct StackEntry {
 }
 
 impl Stack {
+    pub(super) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
     pub(super) fn new(
         // program: &Arc<ProgramEnvironment>,
         overflow_depth: usize,
@@ -13,12 +13,20 @@ use chalk_ir::{
 };
 use chalk_solve::debug_span;
 use chalk_solve::infer::{InferenceTable, ParameterEnaVariableExt};
-use chalk_solve::solve::truncate;
+use chalk_solve::solve::{regions, truncate};
 use chalk_solve::{Guidance, Solution};
 use rustc_hash::FxHashSet;
 use std::fmt::Debug;
 use tracing::{debug, instrument};
 
+/// How many answers to a positive goal `split_for_negation` tries out.
+const MAX_SPLIT_ANSWERS: usize = 10;
+
+/// How many program clauses deep `split_for_negation` looks for those
+/// answers. This runs whenever a negative goal is stuck, so it is kept much
+/// shallower than a full enumeration.
+const MAX_SPLIT_DEPTH: usize = 4;
+
 enum Outcome {
     Complete,
     Incomplete,
@@ -132,6 +140,16 @@ pub(super) struct Fulfill<'s, I: Interner, Solver: SolveDatabase<I>> {
     /// refuted. In such a case the solution will be either `CannotProve`, or `Err`
     /// in the case where some other goal leads to an error.
     cannot_prove: bool,
+
+    /// Record that a goal was too big, and was replaced by a truncated (more
+    /// general) version of it. In such a case, the solution is only an
+    /// approximation, so it is ambiguous even if all goals were proven.
+    truncated: bool,
+
+    /// Whether we may try out the answers to a positive goal one by one, to
+    /// refute the negative goals which still contain inference variables.
+    /// This isn't done again for each of those answers.
+    split_for_negation: bool,
 }
 
 impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
@@ -150,6 +168,8 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
             obligations: vec![],
             constraints: FxHashSet::default(),
             cannot_prove: false,
+            truncated: false,
+            split_for_negation: true,
         };
 
         let ProgramClauseImplication {
@@ -162,9 +182,11 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
             .instantiate_binders_existentially(fulfill.solver.interner(), clause.clone());
 
         debug!(?consequence, ?conditions, ?constraints);
-        fulfill
-            .constraints
-            .extend(constraints.as_slice(fulfill.interner()).to_owned());
+        fulfill.constraints.extend(regions::clause_constraints_in(
+            fulfill.interner(),
+            &canonical_goal.environment,
+            &constraints,
+        ));
 
         debug!("the subst is {:?}", fulfill.subst);
 
@@ -200,6 +222,8 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
             obligations: vec![],
             constraints: FxHashSet::default(),
             cannot_prove: false,
+            truncated: false,
+            split_for_negation: true,
         };
 
         if let Err(e) = fulfill.push_goal(&canonical_goal.environment, canonical_goal.goal.clone())
@@ -212,33 +236,38 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
 
     fn push_obligation(&mut self, obligation: Obligation<I>) {
         // truncate to avoid overflows
-        match &obligation {
-            Obligation::Prove(goal) => {
-                if truncate::needs_truncation(
-                    self.solver.interner(),
-                    &mut self.infer,
-                    self.solver.max_size(),
-                    goal,
-                ) {
-                    // the goal is too big. Record that we should return Ambiguous
-                    self.cannot_prove = true;
-                    return;
+        match obligation {
+            Obligation::Prove(mut goal) => {
+                let interner = self.solver.interner();
+                let max_size = self.solver.max_size();
+                if truncate::needs_truncation(interner, &mut self.infer, max_size, &goal) {
+                    // the goal is too big. Prove a truncated (more general)
+                    // version of it instead, and record that our solution is
+                    // only an approximation
+                    goal.goal = truncate::truncate(interner, &mut self.infer, max_size, goal.goal);
+                    if truncate::needs_truncation(interner, &mut self.infer, max_size, &goal) {
+                        // still too big. Record that we should return Ambiguous
+                        self.cannot_prove = true;
+                        return;
+                    }
+                    self.truncated = true;
                 }
+                self.obligations.push(Obligation::Prove(goal));
             }
             Obligation::Refute(goal) => {
                 if truncate::needs_truncation(
                     self.solver.interner(),
                     &mut self.infer,
                     self.solver.max_size(),
-                    goal,
+                    &goal,
                 ) {
                     // the goal is too big. Record that we should return Ambiguous
                     self.cannot_prove = true;
                     return;
                 }
+                self.obligations.push(Obligation::Refute(goal));
             }
-        };
-        self.obligations.push(obligation);
+        }
     }
 
     /// Unifies `a` and `b` in the given environment.
@@ -365,6 +394,7 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
     fn refute(
         &mut self,
         goal: InEnvironment<Goal<I>>,
+        minimums: &mut Minimums,
         should_continue: impl std::ops::Fn() -> bool + Clone,
     ) -> Fallible<NegativeSolution> {
         let canonicalized = match self
@@ -382,11 +412,15 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
         // Negate the result
         let (quantified, _) =
             u_canonicalize(&mut self.infer, self.solver.interner(), &canonicalized);
-        let mut minimums = Minimums::new(); // FIXME -- minimums here seems wrong
-        if let Ok(solution) = self
+        // The negated goal is solved on its own, so that we can tell whether
+        // it depends on any of the goals being solved (including ours), which
+        // would be a cycle through negation.
+        let mut negated_minimums = Minimums::new();
+        let result = self
             .solver
-            .solve_goal(quantified, &mut minimums, should_continue)
-        {
+            .solve_goal(quantified, &mut negated_minimums, should_continue);
+        minimums.update_from_negated(negated_minimums);
+        if let Ok(solution) = result {
             if solution.is_unique() {
                 Err(NoSolution)
             } else {
@@ -498,7 +532,8 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
                         solution.is_ambig()
                     }
                     Obligation::Refute(goal) => {
-                        let answer = self.refute(goal.clone(), should_continue.clone())?;
+                        let answer =
+                            self.refute(goal.clone(), minimums, should_continue.clone())?;
                         answer == NegativeSolution::Ambiguous
                     }
                 };
@@ -543,6 +578,23 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
             return Ok(Solution::Ambig(Guidance::Unknown));
         }
 
+        if outcome.is_complete() && self.truncated {
+            // We have solved our goals, but only after truncating some of
+            // them, so the current inference state is merely what *every*
+            // solution must agree with.
+            debug!("Goals were truncated, returning ambiguous");
+            let canonical_subst =
+                canonicalize(&mut self.infer, self.solver.interner(), self.subst.clone());
+            if canonical_subst
+                .0
+                .value
+                .is_identity_subst(self.solver.interner())
+            {
+                return Ok(Solution::Ambig(Guidance::Unknown));
+            }
+            return Ok(Solution::Ambig(Guidance::Definite(canonical_subst.0)));
+        }
+
         if outcome.is_complete() {
             // No obligations remain, so we have definitively solved our goals,
             // and the current inference state is the unique way to solve them.
@@ -559,6 +611,10 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
             return Ok(Solution::Unique(constrained.0));
         }
 
+        if let Some(solution) = self.split_for_negation(minimums, should_continue.clone()) {
+            return solution;
+        }
+
         // Otherwise, we have (positive or negative) obligations remaining, but
         // haven't proved that it's *impossible* to satisfy out obligations. we
         // need to determine how to package up what we learned about type
@@ -621,6 +677,93 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
         }
     }
 
+    /// Negative goals can only be refuted once they no longer contain
+    /// inference variables, so `exists<T> { T: Enumerable, not { T: IsFoo } }`
+    /// gets stuck if there are several `T: Enumerable`. Like the SLG solver
+    /// does, we then try out the answers to such a positive goal one by one,
+    /// and see which of them are left once the negative goals are refuted.
+    ///
+    /// This only gives a result if it is a unique solution (or no solution);
+    /// otherwise, what we learned without trying out the answers is as good.
+    fn split_for_negation(
+        &mut self,
+        minimums: &mut Minimums,
+        should_continue: impl std::ops::Fn() -> bool + Clone,
+    ) -> Option<Fallible<Solution<I>>> {
+        if !self.split_for_negation {
+            return None;
+        }
+        let interner = self.interner();
+
+        // The inference variables which keep negative goals from being
+        // refuted.
+        let mut blocking_vars = vec![];
+        for obligation in &self.obligations {
+            if let Obligation::Refute(goal) = obligation {
+                blocking_vars.extend(canonicalize(&mut self.infer, interner, goal.clone()).1);
+            }
+        }
+        if blocking_vars.is_empty() {
+            return None;
+        }
+
+        // Find a positive goal which could determine them.
+        let infer = &mut self.infer;
+        let (goal, free_vars) = self.obligations.iter().find_map(|obligation| {
+            let Obligation::Prove(goal) = obligation else {
+                return None;
+            };
+            let (goal, free_vars) = canonicalize(infer, interner, goal.clone());
+            free_vars
+                .iter()
+                .any(|var| blocking_vars.contains(var))
+                .then_some((goal, free_vars))
+        })?;
+        let (goal, universes) = u_canonicalize(&mut self.infer, interner, &goal);
+        let answers = self.solver.enumerate_answers(
+            &goal,
+            MAX_SPLIT_ANSWERS,
+            MAX_SPLIT_DEPTH,
+            minimums,
+            should_continue.clone(),
+        );
+        if !answers.complete
+            || answers
+                .answers
+                .iter()
+                .any(|answer| is_trivial_canonical_subst(interner, &answer.subst.value.subst))
+        {
+            return None;
+        }
+        debug!(?goal, ?answers, "split_for_negation");
+
+        let mut solution: Option<Solution<I>> = None;
+        for answer in answers.answers {
+            let mut branch = Fulfill {
+                solver: &mut *self.solver,
+                subst: self.subst.clone(),
+                infer: self.infer.clone(),
+                obligations: self.obligations.clone(),
+                constraints: self.constraints.clone(),
+                cannot_prove: false,
+                truncated: self.truncated,
+                split_for_negation: false,
+            };
+            branch.apply_solution(free_vars.clone(), universes.clone(), answer.subst);
+            if let Ok(branch_solution) = branch.solve(minimums, should_continue.clone()) {
+                solution = Some(match solution {
+                    Some(solution) => solution.combine(branch_solution, interner),
+                    None => branch_solution,
+                });
+            }
+        }
+        match solution {
+            None => Some(Err(NoSolution)),
+            Some(solution) if solution.is_unique() => Some(Ok(solution)),
+            Some(_) => None,
+        }
+    }
+
     fn interner(&self) -> I {
         self.solver.interner()
     }
@@ -2,10 +2,20 @@ use crate::fixed_point::{Cache, Minimums, RecursiveContext, SolverStuff};
 use crate::solve::{SolveDatabase, SolveIteration};
 use crate::UCanonicalGoal;
 use chalk_ir::{interner::Interner, NoSolution};
-use chalk_ir::{Canonical, ConstrainedSubst, Goal, InEnvironment, UCanonical};
+use chalk_ir::{Canonical, ConstrainedSubst, DomainGoal, Goal, InEnvironment, UCanonical};
 use chalk_ir::{Constraints, Fallible};
-use chalk_solve::{coinductive_goal::IsCoinductive, RustIrDatabase, Solution};
+use chalk_solve::solve::enumerate::{enumerate_by_clauses, enumerate_by_clauses_to_depth};
+use chalk_solve::solve::regions;
+use chalk_solve::tracking_db::{Reads, TrackingRustIrDatabase};
+use chalk_solve::{
+    coinductive_goal::IsCoinductive, Answers, CancellationToken, Cancelled, Guidance,
+    RustIrDatabase, Solution, SolverStats,
+};
 use std::fmt;
+use std::time::Instant;
+
+type Context<I> = RecursiveContext<UCanonicalGoal<I>, Fallible<Solution<I>>, Reads<I>>;
+type ContextCache<I> = Cache<UCanonicalGoal<I>, Fallible<Solution<I>>, Reads<I>>;
 
 /// A Solver is the basic context in which you can propose goals for a given
 /// program. **All questions posed to the solver are in canonical, closed form,
@@ -13,23 +23,57 @@ use std::fmt;
 /// allows for better caching, and simplifies management of the inference
 /// context.
 struct Solver<'me, I: Interner> {
-    program: &'me dyn RustIrDatabase<I>,
-    context: &'me mut RecursiveContext<UCanonicalGoal<I>, Fallible<Solution<I>>>,
+    program: &'me TrackingRustIrDatabase<'me, I>,
+    context: &'me mut Context<I>,
 }
 
+/// If asked to (see `with_read_tracking`), the solver records the database
+/// reads each cached result was computed from, so that the cache can be kept
+/// when the program changes: after `Cache::revalidate`, results are only
+/// reused if their reads still hold. Otherwise, revalidating the cache
+/// evicts all of its results.
 pub struct RecursiveSolver<I: Interner> {
-    ctx: Box<RecursiveContext<UCanonicalGoal<I>, Fallible<Solution<I>>>>,
+    ctx: Box<Context<I>>,
+    track_reads: bool,
 }
 
 impl<I: Interner> RecursiveSolver<I> {
-    pub fn new(
-        overflow_depth: usize,
-        max_size: usize,
-        cache: Option<Cache<UCanonicalGoal<I>, Fallible<Solution<I>>>>,
-    ) -> Self {
+    pub fn new(overflow_depth: usize, max_size: usize, cache: Option<ContextCache<I>>) -> Self {
         Self {
             ctx: Box::new(RecursiveContext::new(overflow_depth, max_size, cache)),
+            track_reads: false,
+        }
+    }
+
+    /// Records the database reads each result is computed from along with
+    /// it in the cache. This makes every database query more expensive, so
+    /// it is only worth it if the cache is revalidated and kept for a
+    /// changed program.
+    pub fn with_read_tracking(mut self) -> Self {
+        self.track_reads = true;
+        self
+    }
+}
+
+impl<I: Interner> RecursiveSolver<I> {
+    fn solve_root_goal(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonicalGoal<I>,
+        should_continue: &dyn std::ops::Fn() -> bool,
+    ) -> Option<Solution<I>> {
+        let start = Instant::now();
+        let program = TrackingRustIrDatabase::new(program, self.track_reads);
+        let solution = self
+            .ctx
+            .solve_root_goal(goal, &program, should_continue)
+            .ok()
+            .and_then(|solution| regions::simplify_solution(&program, goal, solution));
+        if let Some(stats) = self.ctx.stats_mut() {
+            stats.goals_solved += 1;
+            stats.time_spent += start.elapsed();
         }
+        solution
     }
 }
 
@@ -41,14 +85,16 @@ impl<I: Interner> fmt::Debug for RecursiveSolver<I> {
 
 impl<'me, I: Interner> Solver<'me, I> {
     pub(crate) fn new(
-        context: &'me mut RecursiveContext<UCanonicalGoal<I>, Fallible<Solution<I>>>,
-        program: &'me dyn RustIrDatabase<I>,
+        context: &'me mut Context<I>,
+        program: &'me TrackingRustIrDatabase<'me, I>,
     ) -> Self {
         Self { program, context }
     }
 }
 
-impl<I: Interner> SolverStuff<UCanonicalGoal<I>, Fallible<Solution<I>>> for &dyn RustIrDatabase<I> {
+impl<'db, I: Interner> SolverStuff<UCanonicalGoal<I>, Fallible<Solution<I>>, Reads<I>>
+    for &'db TrackingRustIrDatabase<'db, I>
+{
     fn is_coinductive_goal(self, goal: &UCanonicalGoal<I>) -> bool {
         goal.is_coinductive(self)
     }
@@ -73,7 +119,7 @@ impl<I: Interner> SolverStuff<UCanonicalGoal<I>, Fallible<Solution<I>>> for &dyn
 
     fn solve_iteration(
         self,
-        context: &mut RecursiveContext<UCanonicalGoal<I>, Fallible<Solution<I>>>,
+        context: &mut Context<I>,
         goal: &UCanonicalGoal<I>,
         minimums: &mut Minimums,
         should_continue: impl std::ops::Fn() -> bool + Clone,
@@ -102,6 +148,26 @@ impl<I: Interner> SolverStuff<UCanonicalGoal<I>, Fallible<Solution<I>>> for &dyn
     fn error_value(self) -> Fallible<Solution<I>> {
         Err(NoSolution)
     }
+
+    fn negative_cycle_value(self) -> Fallible<Solution<I>> {
+        Ok(Solution::Ambig(Guidance::Unknown))
+    }
+
+    fn start_reads(self) {
+        self.enter();
+    }
+
+    fn finish_reads(self) -> Option<Reads<I>> {
+        self.exit()
+    }
+
+    fn reuse_reads(self, reads: Option<&Reads<I>>) {
+        self.reuse(reads);
+    }
+
+    fn reads_hold(self, reads: &Reads<I>) -> bool {
+        reads.still_hold(self.db())
+    }
 }
 
 impl<'me, I: Interner> SolveDatabase<I> for Solver<'me, I> {
@@ -115,6 +181,32 @@ impl<'me, I: Interner> SolveDatabase<I> for Solver<'me, I> {
             .solve_goal(&goal, minimums, self.program, should_continue)
     }
 
+    fn enumerate_answers(
+        &mut self,
+        goal: &UCanonicalGoal<I>,
+        limit: usize,
+        max_depth: usize,
+        minimums: &mut Minimums,
+        should_continue: impl std::ops::Fn() -> bool + Clone,
+    ) -> Answers<I> {
+        let program = self.program;
+        enumerate_by_clauses_to_depth(program, goal, limit, max_depth, &mut |goal| {
+            self.solve_goal(goal.clone(), minimums, should_continue.clone())
+                .ok()
+        })
+    }
+
+    fn interrupt(&mut self) {
+        self.context.interrupt();
+    }
+
+    fn record_clauses(&mut self, goal: &DomainGoal<I>, count: usize) {
+        if let Some(stats) = self.context.stats_mut() {
+            // Use the untracked database: this read doesn't affect the result.
+            stats.record_clauses(self.program.db(), goal, count);
+        }
+    }
+
     fn interner(&self) -> I {
         self.program.interner()
     }
@@ -126,6 +218,10 @@ impl<'me, I: Interner> SolveDatabase<I> for Solver<'me, I> {
     fn max_size(&self) -> usize {
         self.context.max_size()
     }
+
+    fn cancellation(&self) -> &CancellationToken {
+        self.context.cancellation()
+    }
 }
 
 impl<I: Interner> chalk_solve::Solver<I> for RecursiveSolver<I> {
@@ -134,7 +230,7 @@ impl<I: Interner> chalk_solve::Solver<I> for RecursiveSolver<I> {
         program: &dyn RustIrDatabase<I>,
         goal: &UCanonical<InEnvironment<Goal<I>>>,
     ) -> Option<chalk_solve::Solution<I>> {
-        self.ctx.solve_root_goal(goal, program, || true).ok()
+        self.solve_root_goal(program, goal, &|| true)
     }
 
     fn solve_limited(
@@ -143,9 +239,22 @@ impl<I: Interner> chalk_solve::Solver<I> for RecursiveSolver<I> {
         goal: &UCanonical<InEnvironment<Goal<I>>>,
         should_continue: &dyn std::ops::Fn() -> bool,
     ) -> Option<chalk_solve::Solution<I>> {
-        self.ctx
-            .solve_root_goal(goal, program, should_continue)
-            .ok()
+        self.solve_root_goal(program, goal, should_continue)
+    }
+
+    fn solve_cancellable(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        cancellation: &CancellationToken,
+    ) -> Result<Option<chalk_solve::Solution<I>>, Cancelled> {
+        self.ctx.set_cancellation(cancellation.clone());
+        let solution = Cancelled::catch(|| self.solve_root_goal(program, goal, &|| true));
+        self.ctx.set_cancellation(CancellationToken::new());
+        if solution.is_err() {
+            self.ctx.clear_cancelled();
+        }
+        solution
     }
 
     fn solve_multiple(
@@ -159,4 +268,29 @@ impl<I: Interner> chalk_solve::Solver<I> for RecursiveSolver<I> {
     ) -> bool {
         unimplemented!("Recursive solver doesn't support multiple answers")
     }
+
+    fn enumerate_answers(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        limit: usize,
+    ) -> Answers<I> {
+        enumerate_by_clauses(program, goal, limit, &mut |goal| {
+            self.solve_root_goal(program, goal, &|| true)
+        })
+    }
+
+    fn collect_stats(&mut self) {
+        self.ctx.collect_stats();
+    }
+
+    fn stats(&self) -> Option<SolverStats> {
+        self.ctx.stats().cloned()
+    }
+
+    fn reset_stats(&mut self) {
+        if let Some(stats) = self.ctx.stats_mut() {
+            *stats = SolverStats::default();
+        }
+    }
 }
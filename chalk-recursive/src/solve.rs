@@ -9,10 +9,10 @@ use chalk_ir::{
     Canonical, ClausePriority, DomainGoal, Fallible, Floundered, Goal, GoalData, InEnvironment,
     NoSolution, ProgramClause, ProgramClauseData, Substitution, UCanonical,
 };
-use chalk_solve::clauses::program_clauses_that_could_match;
+use chalk_solve::clauses::program_clauses_that_could_match_cancellable;
 use chalk_solve::debug_span;
 use chalk_solve::infer::InferenceTable;
-use chalk_solve::{Guidance, RustIrDatabase, Solution};
+use chalk_solve::{Answers, CancellationToken, Guidance, RustIrDatabase, Solution};
 use tracing::{debug, instrument};
 
 pub(super) trait SolveDatabase<I: Interner>: Sized {
@@ -23,8 +23,31 @@ pub(super) trait SolveDatabase<I: Interner>: Sized {
         should_continue: impl std::ops::Fn() -> bool + Clone,
     ) -> Fallible<Solution<I>>;
 
+    /// Enumerates up to `limit` answers to `goal`, which are at most
+    /// `max_depth` program clauses deep; see
+    /// `chalk_solve::solve::enumerate::enumerate_by_clauses_to_depth`.
+    fn enumerate_answers(
+        &mut self,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        limit: usize,
+        max_depth: usize,
+        minimums: &mut Minimums,
+        should_continue: impl std::ops::Fn() -> bool + Clone,
+    ) -> Answers<I>;
+
+    /// Records that `should_continue` asked us to stop, so that the
+    /// (incomplete) results computed from now on are not cached.
+    fn interrupt(&mut self);
+
+    /// Records, if statistics are being collected, that `count` program
+    /// clauses were generated for `goal`.
+    fn record_clauses(&mut self, goal: &DomainGoal<I>, count: usize);
+
     fn max_size(&self) -> usize;
 
+    /// Cancels the current root goal; see `CancellationToken::check`.
+    fn cancellation(&self) -> &CancellationToken;
+
     fn interner(&self) -> I;
 
     fn db(&self) -> &dyn RustIrDatabase<I>;
@@ -43,7 +66,9 @@ pub(super) trait SolveIteration<I: Interner>: SolveDatabase<I> {
         minimums: &mut Minimums,
         should_continue: impl std::ops::Fn() -> bool + Clone,
     ) -> Fallible<Solution<I>> {
+        self.cancellation().check();
         if !should_continue() {
+            self.interrupt();
             return Ok(Solution::Ambig(Guidance::Unknown));
         }
 
@@ -143,7 +168,8 @@ trait SolveIterationHelpers<I: Interner>: SolveDatabase<I> {
             )
         };
         clauses.extend(db.custom_clauses().into_iter().filter(could_match));
-        match program_clauses_that_could_match(db, canonical_goal) {
+        match program_clauses_that_could_match_cancellable(db, canonical_goal, self.cancellation())
+        {
             Ok(goal_clauses) => clauses.extend(goal_clauses.into_iter().filter(could_match)),
             Err(Floundered) => {
                 return Ok(Solution::Ambig(Guidance::Unknown));
@@ -157,6 +183,7 @@ trait SolveIterationHelpers<I: Interner>: SolveDatabase<I> {
                 .cloned()
                 .filter(could_match),
         );
+        self.record_clauses(&goal.goal, clauses.len());
 
         let mut cur_solution = None;
         for program_clause in clauses {
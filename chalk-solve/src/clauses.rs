@@ -3,6 +3,7 @@ use self::env_elaborator::elaborate_env_clauses;
 use self::program_clauses::ToProgramClauses;
 use crate::goal_builder::GoalBuilder;
 use crate::rust_ir::{Movability, WellKnownTrait};
+use crate::solve::cancel::CancellationToken;
 use crate::split::Split;
 use crate::RustIrDatabase;
 use chalk_ir::cast::{Cast, Caster};
@@ -392,11 +393,24 @@ pub fn program_clauses_for_goal<'db, I: Interner>(
 /// `goal`. This can be any superset of the correct set, but the
 /// more precise you can make it, the more efficient solving will
 /// be.
-#[instrument(level = "debug", skip(db))]
 pub fn program_clauses_that_could_match<I: Interner>(
     db: &dyn RustIrDatabase<I>,
     goal: &UCanonical<InEnvironment<DomainGoal<I>>>,
 ) -> Result<Vec<ProgramClause<I>>, Floundered> {
+    program_clauses_that_could_match_cancellable(db, goal, &CancellationToken::new())
+}
+
+/// Like `program_clauses_that_could_match`, but unwinds if `cancellation` is
+/// cancelled (see [`CancellationToken::check`]). Cancellation relies on
+/// unwinding, so it doesn't work when built with `panic = "abort"`: the
+/// process is aborted instead.
+#[instrument(level = "debug", skip(db, cancellation))]
+pub fn program_clauses_that_could_match_cancellable<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    goal: &UCanonical<InEnvironment<DomainGoal<I>>>,
+    cancellation: &CancellationToken,
+) -> Result<Vec<ProgramClause<I>>, Floundered> {
+    cancellation.check();
     let interner = db.interner();
     let mut clauses: Vec<ProgramClause<I>> = vec![];
     let builder = &mut ClauseBuilder::new(db, &mut clauses);
@@ -481,6 +495,7 @@ pub fn program_clauses_that_could_match<I: Interner>(
                 trait_ref.substitution.as_slice(interner),
                 binders,
             ) {
+                cancellation.check();
                 db.impl_datum(impl_id)
                     .to_program_clauses(builder, environment);
             }
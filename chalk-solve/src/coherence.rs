@@ -2,10 +2,12 @@ use indexmap::IndexMap;
 use petgraph::prelude::*;
 use rustc_hash::FxHashMap;
 
+use crate::display;
+use crate::infer::InferenceTable;
 use crate::solve::Solver;
 use crate::RustIrDatabase;
 use chalk_ir::interner::Interner;
-use chalk_ir::{self, ImplId, TraitId};
+use chalk_ir::{self, Canonical, ImplId, Substitution, TraitId, TraitRef};
 use std::fmt;
 use std::sync::Arc;
 
@@ -20,15 +22,61 @@ pub struct CoherenceSolver<'a, I: Interner> {
 
 #[derive(Debug)]
 pub enum CoherenceError<I: Interner> {
-    OverlappingImpls(TraitId<I>),
+    /// Two impls of the same trait apply to the same types, and neither
+    /// specializes the other.
+    OverlappingImpls {
+        trait_id: TraitId<I>,
+        impls: (ImplId<I>, ImplId<I>),
+        /// Trait parameters (including `Self`) that both impls apply to.
+        /// Parameters which are not needed for the overlap are left free.
+        witness: Canonical<Substitution<I>>,
+    },
     FailedOrphanCheck(TraitId<I>),
 }
 
+impl<I: Interner> CoherenceError<I> {
+    /// Like the `Display` impl, but uses `db` to show the types involved as
+    /// Rust syntax (e.g. the types on which two impls overlap).
+    pub fn display<'a>(&'a self, db: &'a dyn RustIrDatabase<I>) -> CoherenceErrorDisplay<'a, I> {
+        CoherenceErrorDisplay { error: self, db }
+    }
+}
+
+pub struct CoherenceErrorDisplay<'a, I: Interner> {
+    error: &'a CoherenceError<I>,
+    db: &'a dyn RustIrDatabase<I>,
+}
+
+impl<I: Interner> fmt::Display for CoherenceErrorDisplay<'_, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.error {
+            CoherenceError::OverlappingImpls {
+                trait_id, witness, ..
+            } => {
+                let interner = self.db.interner();
+                let substitution =
+                    InferenceTable::new().instantiate_canonical(interner, witness.clone());
+                let trait_ref = TraitRef {
+                    trait_id: *trait_id,
+                    substitution,
+                };
+                write!(
+                    f,
+                    "{}: both impls apply to `{}`",
+                    self.error,
+                    display::display_value(self.db, &trait_ref)
+                )
+            }
+            CoherenceError::FailedOrphanCheck(_) => write!(f, "{}", self.error),
+        }
+    }
+}
+
 impl<I: Interner> fmt::Display for CoherenceError<I> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            CoherenceError::OverlappingImpls(id) => {
-                write!(f, "overlapping impls of trait `{:?}`", id)
+            CoherenceError::OverlappingImpls { trait_id, .. } => {
+                write!(f, "overlapping impls of trait `{:?}`", trait_id)
             }
             CoherenceError::FailedOrphanCheck(id) => {
                 write!(f, "impl for trait `{:?}` violates the orphan rules", id)
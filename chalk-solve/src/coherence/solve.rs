@@ -1,8 +1,9 @@
 use crate::coherence::{CoherenceError, CoherenceSolver};
 use crate::debug_span;
 use crate::ext::*;
+use crate::infer::{InferenceTable, ParameterEnaVariableExt};
 use crate::rust_ir::*;
-use crate::{goal_builder::GoalBuilder, Solution};
+use crate::{goal_builder::GoalBuilder, Guidance, Solution};
 use chalk_ir::cast::*;
 use chalk_ir::fold::shift::Shift;
 use chalk_ir::interner::Interner;
@@ -40,7 +41,11 @@ impl<I: Interner> CoherenceSolver<'_, I> {
                     (true, false) => record_specialization(l_id, r_id),
                     (false, true) => record_specialization(r_id, l_id),
                     (_, _) => {
-                        return Err(CoherenceError::OverlappingImpls(self.trait_id));
+                        return Err(CoherenceError::OverlappingImpls {
+                            trait_id: self.trait_id,
+                            impls: (l_id, r_id),
+                            witness: self.overlap_witness(lhs, rhs),
+                        });
                     }
                 }
             }
@@ -89,41 +94,11 @@ impl<I: Interner> CoherenceSolver<'_, I> {
         let (lhs_binders, lhs_bound) = lhs.binders.as_ref().into();
         let (rhs_binders, rhs_bound) = rhs.binders.as_ref().into();
 
-        // Upshift the rhs variables in params to account for the joined binders
-        let lhs_params = lhs_bound
-            .trait_ref
-            .substitution
-            .as_slice(interner)
-            .iter()
-            .cloned();
-        let rhs_params = rhs_bound
-            .trait_ref
-            .substitution
-            .as_slice(interner)
-            .iter()
-            .map(|param| param.clone().shifted_in(interner));
-
-        // Create an equality goal for every input type the trait, attempting
-        // to unify the inputs to both impls with one another
-        let params_goals = lhs_params
-            .zip(rhs_params)
-            .map(|(a, b)| GoalData::EqGoal(EqGoal { a, b }).intern(interner));
-
-        // Upshift the rhs variables in where clauses
-        let lhs_where_clauses = lhs_bound.where_clauses.iter().cloned();
-        let rhs_where_clauses = rhs_bound
-            .where_clauses
-            .iter()
-            .map(|wc| wc.clone().shifted_in(interner));
-
-        // Create a goal for each clause in both where clauses
-        let wc_goals = lhs_where_clauses
-            .chain(rhs_where_clauses)
-            .map(|wc| wc.cast(interner));
-
-        // Join all the goals we've created together with And, then quantify them
-        // over the joined binders. This is our query.
-        let goal = Box::new(Goal::all(interner, params_goals.chain(wc_goals)))
+        // Upshift the rhs variables to account for the joined binders, then
+        // join all the goals together with And, and quantify them over the
+        // joined binders. This is our query.
+        let rhs_bound = rhs_bound.clone().shifted_in(interner);
+        let goal = Box::new(overlap_goal(interner, lhs_bound, &rhs_bound))
             .quantify(interner, QuantifierKind::Exists, lhs_binders)
             .quantify(interner, QuantifierKind::Exists, rhs_binders)
             .compatible(interner)
@@ -145,6 +120,73 @@ impl<I: Interner> CoherenceSolver<'_, I> {
         result
     }
 
+    // Finds trait parameters that both of two overlapping impls apply to.
+    //
+    // We build the same query as `disjoint`, but with the impl parameters as
+    // inference variables rather than quantified ones, and without negating
+    // it. Solving it tells us what the lhs impl parameters have to be for the
+    // impls to overlap, which we apply to the lhs impl header. Parameters the
+    // solver doesn't pin down stay free (and are displayed as `_`).
+    //
+    // Example:
+    //
+    //  Impls:
+    //      impl<T> Foo for (T, Bar) { }    // lhs
+    //      impl<U> Foo for (Bar, U) { }    // rhs
+    //  Solves:
+    //      compatible { (?T, Bar) = (Bar, ?U) }
+    //  Witness:
+    //      [(Bar, Bar)]
+    fn overlap_witness(
+        &self,
+        lhs: &ImplDatum<I>,
+        rhs: &ImplDatum<I>,
+    ) -> Canonical<Substitution<I>> {
+        let interner = self.db.interner();
+        let environment = Environment::new(interner);
+        let mut infer = InferenceTable::new();
+
+        let lhs_bound = infer.instantiate_binders_existentially(interner, lhs.binders.clone());
+        let rhs_bound = infer.instantiate_binders_existentially(interner, rhs.binders.clone());
+        let goal = overlap_goal(interner, &lhs_bound, &rhs_bound).compatible(interner);
+
+        let canonicalized = infer.canonicalize(interner, InEnvironment::new(&environment, goal));
+        let u_canonical = InferenceTable::u_canonicalize(interner, &canonicalized.quantified);
+        let mut fresh_solver = (self.solver_builder)();
+        let subst = match fresh_solver.solve(self.db, &u_canonical.quantified) {
+            Some(Solution::Unique(constrained)) => Some(Canonical {
+                binders: constrained.binders,
+                value: constrained.value.subst,
+            }),
+            Some(Solution::Ambig(Guidance::Definite(subst))) => Some(subst),
+            Some(Solution::Ambig(_)) | None => None,
+        };
+
+        if let Some(subst) = subst {
+            let values = infer.instantiate_canonical(interner, subst);
+            let vars: Vec<_> = canonicalized
+                .free_vars
+                .iter()
+                .map(|var| var.to_generic_arg(interner))
+                .collect();
+            // The free variables are fresh, so this can only fail if the
+            // solution is inconsistent with itself; in that case, we just
+            // report the less precise witness.
+            let _ = infer.relate(
+                interner,
+                self.db.unification_database(),
+                &environment,
+                Variance::Invariant,
+                vars.as_slice(),
+                values.as_slice(interner),
+            );
+        }
+
+        infer
+            .canonicalize(interner, lhs_bound.trait_ref.substitution)
+            .quantified
+    }
+
     // Creates a goal which, if provable, means "more special" impl specializes the "less special" one.
     //
     // # General rule
@@ -258,3 +300,32 @@ impl<I: Interner> CoherenceSolver<'_, I> {
         result
     }
 }
+
+/// Creates the goals that must hold for both impls to apply to the same
+/// trait parameters: an equality goal for every trait parameter, plus the
+/// where clauses of both impls.
+fn overlap_goal<I: Interner>(
+    interner: I,
+    lhs: &ImplDatumBound<I>,
+    rhs: &ImplDatumBound<I>,
+) -> Goal<I> {
+    // Create an equality goal for every input type the trait, attempting
+    // to unify the inputs to both impls with one another
+    let params_goals = lhs
+        .trait_ref
+        .substitution
+        .iter(interner)
+        .cloned()
+        .zip(rhs.trait_ref.substitution.iter(interner).cloned())
+        .map(|(a, b)| GoalData::EqGoal(EqGoal { a, b }).intern(interner));
+
+    // Create a goal for each clause in both where clauses
+    let wc_goals = lhs
+        .where_clauses
+        .iter()
+        .chain(&rhs.where_clauses)
+        .cloned()
+        .map(|wc| wc.cast(interner));
+
+    Goal::all(interner, params_goals.chain(wc_goals))
+}
@@ -14,6 +14,7 @@ use crate::{logging_db::RecordedItemId, split::Split, RustIrDatabase};
 mod utils;
 
 mod bounds;
+mod goals;
 mod identifiers;
 mod items;
 mod render_trait;
@@ -102,6 +103,48 @@ where
     Ok(())
 }
 
+/// Displays a single value, such as a type or a where clause, as Rust syntax.
+///
+/// Unlike [`write_items`], this doesn't write out any item definitions; it's
+/// meant for error messages. Inference variables are written as `_`.
+pub fn display_value<'a, I: Interner, T>(
+    db: &'a dyn RustIrDatabase<I>,
+    value: &'a T,
+) -> DisplayValue<'a, I, T> {
+    DisplayValue {
+        db,
+        value,
+        parameter_names: &[],
+    }
+}
+
+/// Displays a value as Rust syntax. Created by [`display_value`].
+pub struct DisplayValue<'a, I: Interner, T> {
+    db: &'a dyn RustIrDatabase<I>,
+    value: &'a T,
+    parameter_names: &'a [String],
+}
+
+impl<'a, I: Interner, T> DisplayValue<'a, I, T> {
+    /// Writes the variables bound by the outermost binder of the value (like
+    /// the `forall` of a goal built for an item) with the given names, in
+    /// order, such as the names of the item's parameters. Lifetime names are
+    /// given without their `'`.
+    pub fn with_parameter_names(self, parameter_names: &'a [String]) -> Self {
+        DisplayValue {
+            parameter_names,
+            ..self
+        }
+    }
+}
+
+impl<I: Interner, T: RenderAsRust<I>> Display for DisplayValue<'_, I, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result {
+        let s = InternalWriterState::for_db(self.db).with_outer_names(self.parameter_names);
+        self.value.fmt(&s, f)
+    }
+}
+
 /// Displays a set of bounds, all targeting `Self`, as just the trait names,
 /// separated by `+`.
 ///
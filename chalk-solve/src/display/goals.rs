@@ -0,0 +1,130 @@
+//! Writer logic for goals and program clauses.
+//!
+//! These aren't part of any item definition, so they're written in the syntax
+//! chalk uses for goals, e.g. `forall<T> { if (FromEnv(T: Foo)) { T: Bar } }`.
+use std::fmt::{Formatter, Result};
+
+use chalk_ir::{interner::Interner, *};
+use itertools::Itertools;
+
+use super::{render_trait::RenderAsRust, state::InternalWriterState};
+
+impl<I: Interner> RenderAsRust<I> for Goal<I> {
+    fn fmt(&self, s: &InternalWriterState<'_, I>, f: &'_ mut Formatter<'_>) -> Result {
+        let interner = s.db().interner();
+        match self.data(interner) {
+            // A quantifier without any variables doesn't add anything (it's
+            // how where clauses without a `for<..>` end up), so we leave it out.
+            GoalData::Quantified(_, subgoal) if subgoal.binders.is_empty(interner) => {
+                subgoal.skip_binders().fmt(&s.add_debrujin_index(None), f)
+            }
+            GoalData::Quantified(kind, subgoal) => {
+                let keyword = match kind {
+                    QuantifierKind::ForAll => "forall",
+                    QuantifierKind::Exists => "exists",
+                };
+                let s = &s.add_debrujin_index(None);
+                write!(
+                    f,
+                    "{}<{}> {{ {} }}",
+                    keyword,
+                    s.binder_var_display(&subgoal.binders).format(", "),
+                    subgoal.skip_binders().display(s)
+                )?;
+                Ok(())
+            }
+            GoalData::Implies(clauses, subgoal) => write!(
+                f,
+                "if ({}) {{ {} }}",
+                clauses.iter(interner).map(|c| c.display(s)).format("; "),
+                subgoal.display(s)
+            ),
+            GoalData::All(goals) => {
+                if goals.is_empty(interner) {
+                    return write!(f, "true");
+                }
+                // Nested conjunctions are parenthesized so that they are
+                // still read as a single goal.
+                let goals = goals.iter(interner).map(|goal| match goal.data(interner) {
+                    GoalData::All(_) => format!("({})", goal.display(s)),
+                    _ => goal.display(s).to_string(),
+                });
+                write!(f, "{}", goals.format(", "))
+            }
+            GoalData::Not(subgoal) => write!(f, "not {{ {} }}", subgoal.display(s)),
+            GoalData::EqGoal(EqGoal { a, b }) => write!(f, "{} = {}", a.display(s), b.display(s)),
+            GoalData::SubtypeGoal(SubtypeGoal { a, b }) => {
+                write!(f, "Subtype({}, {})", a.display(s), b.display(s))
+            }
+            GoalData::DomainGoal(domain_goal) => domain_goal.fmt(s, f),
+            GoalData::CannotProve => write!(f, "CannotProve"),
+        }
+    }
+}
+
+impl<I: Interner> RenderAsRust<I> for DomainGoal<I> {
+    fn fmt(&self, s: &InternalWriterState<'_, I>, f: &'_ mut Formatter<'_>) -> Result {
+        match self {
+            DomainGoal::Holds(where_clause) => where_clause.fmt(s, f),
+            DomainGoal::WellFormed(WellFormed::Trait(trait_ref)) => {
+                write!(f, "WellFormed({})", trait_ref.display(s))
+            }
+            DomainGoal::WellFormed(WellFormed::Ty(ty)) => {
+                write!(f, "WellFormed({})", ty.display(s))
+            }
+            DomainGoal::FromEnv(FromEnv::Trait(trait_ref)) => {
+                write!(f, "FromEnv({})", trait_ref.display(s))
+            }
+            DomainGoal::FromEnv(FromEnv::Ty(ty)) => write!(f, "FromEnv({})", ty.display(s)),
+            DomainGoal::Normalize(Normalize { alias, ty }) => {
+                write!(f, "Normalize({} -> {})", alias.display(s), ty.display(s))
+            }
+            DomainGoal::IsLocal(ty) => write!(f, "IsLocal({})", ty.display(s)),
+            DomainGoal::IsUpstream(ty) => write!(f, "IsUpstream({})", ty.display(s)),
+            DomainGoal::IsFullyVisible(ty) => write!(f, "IsFullyVisible({})", ty.display(s)),
+            DomainGoal::LocalImplAllowed(trait_ref) => {
+                write!(f, "LocalImplAllowed({})", trait_ref.display(s))
+            }
+            DomainGoal::Compatible => write!(f, "Compatible"),
+            DomainGoal::DownstreamType(ty) => write!(f, "DownstreamType({})", ty.display(s)),
+            DomainGoal::Reveal => write!(f, "Reveal"),
+            DomainGoal::ObjectSafe(trait_id) => write!(f, "ObjectSafe({})", trait_id.display(s)),
+        }
+    }
+}
+
+/// Region constraints and clause priorities have no syntax, so they are not
+/// written out.
+impl<I: Interner> RenderAsRust<I> for ProgramClause<I> {
+    fn fmt(&self, s: &InternalWriterState<'_, I>, f: &'_ mut Formatter<'_>) -> Result {
+        let interner = s.db().interner();
+        let ProgramClauseData(implication) = self.data(interner);
+        let s = &s.add_debrujin_index(None);
+        let ProgramClauseImplication {
+            consequence,
+            conditions,
+            ..
+        } = implication.skip_binders();
+
+        let quantified = !implication.binders.is_empty(interner);
+        if quantified {
+            write!(
+                f,
+                "forall<{}> {{ ",
+                s.binder_var_display(&implication.binders).format(", ")
+            )?;
+        }
+        consequence.fmt(s, f)?;
+        if !conditions.is_empty(interner) {
+            write!(
+                f,
+                " :- {}",
+                conditions.iter(interner).map(|c| c.display(s)).format(", ")
+            )?;
+        }
+        if quantified {
+            write!(f, " }}")?;
+        }
+        Ok(())
+    }
+}
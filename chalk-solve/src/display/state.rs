@@ -185,6 +185,8 @@ pub(super) struct InternalWriterState<'a, I: Interner> {
     remapping: Rc<BTreeMap<InvertedBoundVar, InvertedBoundVar>>,
     // the inverted_bound_var which maps to "Self"
     self_mapping: Option<InvertedBoundVar>,
+    // the names of the variables bound by the outermost binder, if known
+    outer_names: &'a [String],
 }
 
 type IndexWithinBinding = usize;
@@ -202,6 +204,20 @@ impl<'a, I: Interner> InternalWriterState<'a, I> {
             debrujin_indices_deep: 0,
             remapping: Rc::new(BTreeMap::new()),
             self_mapping: None,
+            outer_names: &[],
+        }
+    }
+
+    /// Creates a state for writing a single value on its own, with no
+    /// names shared with other writes.
+    pub(super) fn for_db(db: &'a dyn RustIrDatabase<I>) -> Self {
+        InternalWriterState {
+            persistent_state: WriterState::new(db),
+            indent_level: 0,
+            debrujin_indices_deep: 0,
+            remapping: Rc::new(BTreeMap::new()),
+            self_mapping: None,
+            outer_names: &[],
         }
     }
 
@@ -209,6 +225,15 @@ impl<'a, I: Interner> InternalWriterState<'a, I> {
         self.persistent_state.db
     }
 
+    /// Names the variables bound by the outermost binder of the value being
+    /// written `names`, in order, instead of giving them generated names.
+    pub(super) fn with_outer_names(&self, names: &'a [String]) -> Self {
+        InternalWriterState {
+            outer_names: names,
+            ..self.clone()
+        }
+    }
+
     pub(super) fn add_indent(&self) -> Self {
         InternalWriterState {
             indent_level: self.indent_level + 1,
@@ -298,8 +323,14 @@ impl<'a, I: Interner> InternalWriterState<'a, I> {
 
     pub(super) fn apply_mappings(&self, b: InvertedBoundVar) -> impl Display {
         let remapped = self.remapping.get(&b).copied().unwrap_or(b);
+        let outer_name = match remapped.inverted_debrujin_idx {
+            1 => self.outer_names.get(remapped.within_idx),
+            _ => None,
+        };
         if self.self_mapping == Some(remapped) {
             "Self".to_owned()
+        } else if let Some(name) = outer_name {
+            name.clone()
         } else {
             remapped.to_string()
         }
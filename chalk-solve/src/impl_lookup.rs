@@ -0,0 +1,179 @@
+//! Looking up the impls that could satisfy a trait bound, for questions like
+//! "which types implement `Iterator<Item = u32>`?".
+//!
+//! Unlike solving `?T: Iterator<Item = u32>`, which flounders (the self type
+//! is unknown), this only unifies the bound with the header of each impl that
+//! `RustIrDatabase::impls_for_trait` returns (which uses `could_match` to
+//! rule out the ones that obviously don't apply). The where clauses of the
+//! impls are not checked; they are returned instead, for the caller to check
+//! or display as it sees fit.
+
+use crate::infer::{InferenceTable, ParameterEnaVariableExt};
+use crate::rust_ir::ImplDatum;
+use crate::RustIrDatabase;
+use chalk_derive::{HasInterner, TypeFoldable, TypeVisitable};
+use chalk_ir::cast::Cast;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use tracing::debug;
+
+/// A trait bound to look up impls for: a trait reference, along with the
+/// values of some of the trait's associated types (as in
+/// `Iterator<Item = u32>`). The canonical variables of the bound are its
+/// unknown parts.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, TypeFoldable, TypeVisitable, HasInterner)]
+pub struct ImplBound<I: Interner> {
+    pub trait_ref: TraitRef<I>,
+    /// `AliasEq` clauses for associated types of `trait_ref`.
+    pub associated_ty_values: Vec<AliasEq<I>>,
+}
+
+/// An impl whose header could satisfy an [`ImplBound`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MatchingImpl<I: Interner> {
+    pub impl_id: ImplId<I>,
+    /// What has to hold for the impl to satisfy the bound. The canonical
+    /// variables are the impl's parameters which the bound doesn't determine.
+    pub residual: Canonical<Residual<I>>,
+}
+
+/// What has to hold for a [`MatchingImpl`] to satisfy a bound.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, TypeFoldable, TypeVisitable, HasInterner)]
+pub struct Residual<I: Interner> {
+    /// Values for the canonical variables of the bound.
+    pub subst: Substitution<I>,
+    /// The where clauses of the impl.
+    pub where_clauses: Vec<QuantifiedWhereClause<I>>,
+    /// Goals left over from unifying the bound with the impl, for example
+    /// to normalize associated types which appear in the bound, as well as
+    /// the associated type values of the bound which the impl leaves to the
+    /// trait's defaults.
+    pub goals: Vec<InEnvironment<Goal<I>>>,
+}
+
+/// Finds the impls whose headers could satisfy `bound`, along with what has
+/// to hold for them to do so. Negative impls are left out.
+pub fn matching_impls<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    bound: &Canonical<ImplBound<I>>,
+) -> Vec<MatchingImpl<I>> {
+    let interner = db.interner();
+    let universes = bound
+        .binders
+        .iter(interner)
+        .map(|kind| kind.skip_kind().counter + 1)
+        .max()
+        .unwrap_or(1);
+    let trait_ref = &bound.value.trait_ref;
+    let impls = db.impls_for_trait(
+        trait_ref.trait_id,
+        trait_ref.substitution.as_slice(interner),
+        &bound.binders,
+    );
+
+    let (mut infer, subst, bound) =
+        InferenceTable::from_canonical(interner, universes, bound.clone());
+    let universe = UniverseIndex {
+        counter: universes - 1,
+    };
+    let mut matching = vec![];
+    for impl_id in impls {
+        let impl_datum = db.impl_datum(impl_id);
+        if !impl_datum.is_positive() {
+            continue;
+        }
+        let snapshot = infer.snapshot();
+        let impl_subst = Substitution::from_iter(
+            interner,
+            impl_datum.binders.binders.iter(interner).map(|kind| {
+                WithKind::new(kind.clone(), infer.new_variable(universe)).to_generic_arg(interner)
+            }),
+        );
+        if let Some(residual) = match_impl(db, &mut infer, &bound, &subst, &impl_datum, &impl_subst)
+        {
+            let residual = infer.canonicalize(interner, residual).quantified;
+            debug!(?impl_id, ?residual, "matching impl");
+            matching.push(MatchingImpl { impl_id, residual });
+        }
+        infer.rollback_to(snapshot);
+    }
+    matching
+}
+
+/// Unifies `bound`, whose canonical variables are `subst`, with the header
+/// of an impl, whose parameters are `impl_subst`. Returns what has to hold
+/// for the impl to satisfy the bound, if it could.
+fn match_impl<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    infer: &mut InferenceTable<I>,
+    bound: &ImplBound<I>,
+    subst: &Substitution<I>,
+    impl_datum: &ImplDatum<I>,
+    impl_subst: &Substitution<I>,
+) -> Option<Residual<I>> {
+    let interner = db.interner();
+    let environment = Environment::new(interner);
+    let impl_bound = impl_datum.binders.clone().substitute(interner, impl_subst);
+    let mut goals = infer
+        .relate(
+            interner,
+            db.unification_database(),
+            &environment,
+            Variance::Invariant,
+            &bound.trait_ref,
+            &impl_bound.trait_ref,
+        )
+        .ok()?
+        .goals;
+
+    let trait_params = bound.trait_ref.substitution.len(interner);
+    for alias_eq in &bound.associated_ty_values {
+        let AliasTy::Projection(projection) = &alias_eq.alias else {
+            goals.push(InEnvironment::new(
+                &environment,
+                alias_eq.clone().cast::<Goal<I>>(interner),
+            ));
+            continue;
+        };
+        let value = impl_datum
+            .associated_ty_value_ids
+            .iter()
+            .map(|&id| db.associated_ty_value(id))
+            .find(|value| value.associated_ty_id == projection.associated_ty_id);
+        let Some(value) = value else {
+            // The impl uses the trait's default.
+            goals.push(InEnvironment::new(
+                &environment,
+                alias_eq.clone().cast::<Goal<I>>(interner),
+            ));
+            continue;
+        };
+
+        // The value is bound by the impl's parameters, followed by those of
+        // the associated type itself.
+        let value_subst = Substitution::from_iter(
+            interner,
+            impl_subst
+                .iter(interner)
+                .chain(projection.substitution.iter(interner).skip(trait_params)),
+        );
+        let value = value.value.clone().substitute(interner, &value_subst);
+        let relation = infer
+            .relate(
+                interner,
+                db.unification_database(),
+                &environment,
+                Variance::Invariant,
+                &alias_eq.ty,
+                &value.ty,
+            )
+            .ok()?;
+        goals.extend(relation.goals);
+    }
+
+    Some(Residual {
+        subst: subst.clone(),
+        where_clauses: impl_bound.where_clauses,
+        goals,
+    })
+}
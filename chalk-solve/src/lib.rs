@@ -14,12 +14,14 @@ pub mod coinductive_goal;
 pub mod display;
 pub mod ext;
 pub mod goal_builder;
+pub mod impl_lookup;
 pub mod infer;
 pub mod logging;
 pub mod logging_db;
 pub mod rust_ir;
 pub mod solve;
 pub mod split;
+pub mod tracking_db;
 pub mod wf;
 
 /// Trait representing access to a database of rust types.
@@ -202,6 +204,10 @@ pub trait RustIrDatabase<I: Interner>: Debug {
 
 pub use clauses::program_clauses_for_env;
 
+pub use solve::budget::{Budget, BudgetExhausted};
+pub use solve::cancel::{CancellationToken, Cancelled};
+pub use solve::enumerate::{Answer, Answers};
+pub use solve::stats::SolverStats;
 pub use solve::Guidance;
 pub use solve::Solution;
 pub use solve::Solver;
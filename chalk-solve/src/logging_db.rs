@@ -1,5 +1,6 @@
 //! Provides wrappers over `RustIrDatabase` which record used definitions and write
-//! `.chalk` files containing those definitions.
+//! `.chalk` files containing those definitions, and a wrapper over `Solver`
+//! which records the goals solved in them as well.
 use std::{
     borrow::Borrow,
     fmt::{self, Debug, Display},
@@ -14,17 +15,22 @@ use crate::{
     display::{self, WriterState},
     RustIrDatabase,
 };
-use chalk_ir::{interner::Interner, *};
+use chalk_ir::{interner::Interner, visit::TypeVisitable, *};
 
 use indexmap::IndexSet;
 
 mod id_collector;
+mod replay;
+
+use self::replay::RecordedGoal;
+pub use self::replay::RecordingSolver;
 
 /// Wraps another `RustIrDatabase` (`DB`) and records which definitions are
 /// used.
 ///
 /// A full .chalk file containing all used definitions can be recovered through
-/// `LoggingRustIrDatabase`'s `Display` implementation.
+/// `LoggingRustIrDatabase`'s `Display` implementation. If goals were solved
+/// through a [`RecordingSolver`], they are written after the definitions.
 ///
 /// Uses a separate type, `P`, for the database stored inside to account for
 /// `Arc` or wrapping other storage mediums.
@@ -37,6 +43,7 @@ where
 {
     ws: WriterState<I, DB, P>,
     def_ids: Mutex<IndexSet<RecordedItemId<I>>>,
+    goals: Mutex<Vec<RecordedGoal>>,
     _phantom: PhantomData<DB>,
 }
 
@@ -50,6 +57,7 @@ where
         LoggingRustIrDatabase {
             ws: WriterState::new(db),
             def_ids: Default::default(),
+            goals: Default::default(),
             _phantom: PhantomData,
         }
     }
@@ -65,7 +73,11 @@ where
         let def_ids = self.def_ids.lock().unwrap();
         let stub_ids = id_collector::collect_unrecorded_ids(self.ws.db(), &def_ids);
         display::write_stub_items(f, &self.ws, stub_ids)?;
-        display::write_items(f, &self.ws, def_ids.iter().copied())
+        display::write_items(f, &self.ws, def_ids.iter().copied())?;
+        for goal in self.goals.lock().unwrap().iter() {
+            write!(f, "\n{}", goal)?;
+        }
+        Ok(())
     }
 }
 
@@ -89,6 +101,15 @@ where
             .unwrap()
             .extend(ids.into_iter().map(Into::into));
     }
+
+    /// Records the definitions `value` refers to.
+    fn record_ids_in<T: TypeVisitable<I>>(&self, value: &T) {
+        self.record_all(id_collector::collect_ids_in(self.ws.db(), value));
+    }
+
+    fn record_goal(&self, goal: RecordedGoal) {
+        self.goals.lock().unwrap().push(goal);
+    }
 }
 
 impl<I, DB, P> UnificationDatabase<I> for LoggingRustIrDatabase<I, DB, P>
@@ -4,7 +4,7 @@ use chalk_ir::{
     interner::Interner,
     visit::TypeVisitor,
     visit::{TypeSuperVisitable, TypeVisitable},
-    AliasTy, DebruijnIndex, TyKind, WhereClause,
+    AliasTy, DebruijnIndex, DomainGoal, FromEnv, TyKind, WellFormed, WhereClause,
 };
 use std::ops::ControlFlow;
 
@@ -95,6 +95,19 @@ pub fn collect_unrecorded_ids<I: Interner, DB: RustIrDatabase<I>>(
         .collect()
 }
 
+/// Collects the identifiers `value` refers to directly.
+pub fn collect_ids_in<I: Interner, DB: RustIrDatabase<I>, T: TypeVisitable<I>>(
+    db: &DB,
+    value: &T,
+) -> IndexSet<RecordedItemId<I>> {
+    let mut collector = IdCollector {
+        db,
+        found_identifiers: IndexSet::new(),
+    };
+    let _ = value.visit_with(&mut collector, DebruijnIndex::INNERMOST);
+    collector.found_identifiers
+}
+
 struct IdCollector<'i, I: Interner, DB: RustIrDatabase<I>> {
     db: &'i DB,
     found_identifiers: IndexSet<RecordedItemId<I>>,
@@ -159,4 +172,19 @@ impl<'i, I: Interner, DB: RustIrDatabase<I>> TypeVisitor<I> for IdCollector<'i,
         }
         where_clause.super_visit_with(self.as_dyn(), outer_binder)
     }
+
+    fn visit_domain_goal(
+        &mut self,
+        domain_goal: &DomainGoal<I>,
+        outer_binder: DebruijnIndex,
+    ) -> ControlFlow<()> {
+        match domain_goal {
+            DomainGoal::WellFormed(WellFormed::Trait(trait_ref))
+            | DomainGoal::FromEnv(FromEnv::Trait(trait_ref))
+            | DomainGoal::LocalImplAllowed(trait_ref) => self.record(trait_ref.trait_id),
+            DomainGoal::ObjectSafe(trait_id) => self.record(*trait_id),
+            _ => (),
+        }
+        domain_goal.super_visit_with(self.as_dyn(), outer_binder)
+    }
 }
@@ -0,0 +1,425 @@
+//! Recording the goals a solver is asked to solve, along with the solutions
+//! it gives, so that a solver session can be replayed.
+//!
+//! The goals are written after the program, in the blocks the `chalk`
+//! binary checks with `--goals`:
+//!
+//! ```text
+//! goal {
+//!     exists<T> { Vec<T>: Clone }
+//! } yields {
+//!     Ambiguous; no inference guidance
+//! }
+//! ```
+//!
+//! Goals solved with `solve_multiple` are written with `yields_all`, with one
+//! answer per line, and a last line of `...` if the caller stopped asking for
+//! answers before the solver ran out of them.
+use std::borrow::Borrow;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Display};
+
+use chalk_derive::FallibleTypeFolder;
+use chalk_ir::fold::{TypeFoldable, TypeFolder};
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+
+use super::LoggingRustIrDatabase;
+use crate::display::display_value;
+use crate::solve::cancel::{CancellationToken, Cancelled};
+use crate::solve::enumerate::Answers;
+use crate::solve::{Solution, Solver, SubstitutionResult};
+use crate::RustIrDatabase;
+
+/// A goal that was solved, and what the solver made of it.
+#[derive(Debug)]
+pub(super) struct RecordedGoal {
+    goal: String,
+    result: RecordedResult,
+}
+
+#[derive(Debug)]
+enum RecordedResult {
+    Solution(String),
+    Answers { answers: Vec<String>, more: bool },
+}
+
+impl Display for RecordedGoal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "goal {{")?;
+        writeln!(f, "    {}", self.goal)?;
+        match &self.result {
+            RecordedResult::Solution(solution) => {
+                writeln!(f, "}} yields {{")?;
+                writeln!(f, "    {}", solution)?;
+            }
+            RecordedResult::Answers { answers, more } => {
+                writeln!(f, "}} yields_all {{")?;
+                for answer in answers {
+                    writeln!(f, "    {}", answer)?;
+                }
+                if *more {
+                    writeln!(f, "    ...")?;
+                }
+            }
+        }
+        writeln!(f, "}}")
+    }
+}
+
+/// Wraps a [`Solver`], and records each goal it solves in a
+/// [`LoggingRustIrDatabase`], so that the database's output is a
+/// self-contained file that the `chalk` binary can replay with `--goals`.
+///
+/// The `program` passed to the solver's methods should be the logging
+/// database itself, so that the definitions the solver uses are recorded as
+/// well.
+pub struct RecordingSolver<'db, I, DB, P = DB>
+where
+    I: Interner,
+    DB: RustIrDatabase<I>,
+    P: Borrow<DB>,
+{
+    solver: Box<dyn Solver<I> + 'db>,
+    db: &'db LoggingRustIrDatabase<I, DB, P>,
+}
+
+impl<'db, I, DB, P> RecordingSolver<'db, I, DB, P>
+where
+    I: Interner,
+    DB: RustIrDatabase<I>,
+    P: Borrow<DB> + Debug,
+{
+    pub fn new(solver: Box<dyn Solver<I> + 'db>, db: &'db LoggingRustIrDatabase<I, DB, P>) -> Self {
+        RecordingSolver { solver, db }
+    }
+
+    fn display_goal(&self, goal: &UCanonical<InEnvironment<Goal<I>>>) -> String {
+        let goal = requantify(self.db.interner(), goal);
+        self.db.record_ids_in(&goal);
+        display_value(self.db, &goal).to_string()
+    }
+}
+
+impl<'db, I, DB, P> Debug for RecordingSolver<'db, I, DB, P>
+where
+    I: Interner,
+    DB: RustIrDatabase<I>,
+    P: Borrow<DB>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordingSolver")
+            .field("solver", &self.solver)
+            .finish()
+    }
+}
+
+impl<'db, I, DB, P> Solver<I> for RecordingSolver<'db, I, DB, P>
+where
+    I: Interner,
+    DB: RustIrDatabase<I>,
+    P: Borrow<DB> + Debug,
+{
+    fn solve(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Option<Solution<I>> {
+        let solution = self.solver.solve(program, goal);
+        self.record_solution(goal, &solution);
+        solution
+    }
+
+    fn solve_limited(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        should_continue: &dyn std::ops::Fn() -> bool,
+    ) -> Option<Solution<I>> {
+        // A solution found with less work than the solver wanted to do can't
+        // be replayed, so it isn't recorded.
+        let interrupted = Cell::new(false);
+        let solution = self.solver.solve_limited(program, goal, &|| {
+            let proceed = should_continue();
+            interrupted.set(interrupted.get() || !proceed);
+            proceed
+        });
+        if !interrupted.get() {
+            self.record_solution(goal, &solution);
+        }
+        solution
+    }
+
+    fn solve_cancellable(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        cancellation: &CancellationToken,
+    ) -> Result<Option<Solution<I>>, Cancelled> {
+        let solution = self.solver.solve_cancellable(program, goal, cancellation)?;
+        self.record_solution(goal, &solution);
+        Ok(solution)
+    }
+
+    fn solve_multiple(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        f: &mut dyn FnMut(SubstitutionResult<Canonical<ConstrainedSubst<I>>>, bool) -> bool,
+    ) -> bool {
+        let interner = self.db.interner();
+        let mut answers = vec![];
+        let mut more = false;
+        let processed_all = self
+            .solver
+            .solve_multiple(program, goal, &mut |answer, has_next| {
+                answers.push(answer.as_ref().map(|a| a.display(interner)).to_string());
+                more = has_next;
+                f(answer, has_next)
+            });
+        let goal = self.display_goal(goal);
+        self.db.record_goal(RecordedGoal {
+            goal,
+            result: RecordedResult::Answers { answers, more },
+        });
+        processed_all
+    }
+
+    fn enumerate_answers(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        limit: usize,
+    ) -> Answers<I> {
+        // Not every solver implements this with `solve_multiple`, so it is
+        // passed on as is (and not recorded).
+        self.solver.enumerate_answers(program, goal, limit)
+    }
+}
+
+impl<'db, I, DB, P> RecordingSolver<'db, I, DB, P>
+where
+    I: Interner,
+    DB: RustIrDatabase<I>,
+    P: Borrow<DB> + Debug,
+{
+    fn record_solution(
+        &self,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        solution: &Option<Solution<I>>,
+    ) {
+        let solution = match solution {
+            Some(solution) => solution.display(self.db.interner()).to_string(),
+            None => "No possible solution".to_string(),
+        };
+        let goal = self.display_goal(goal);
+        self.db.record_goal(RecordedGoal {
+            goal,
+            result: RecordedResult::Solution(solution),
+        });
+    }
+}
+
+/// Turns a goal that was peeled (see `into_peeled_goal`) and canonicalized
+/// back into a closed goal, which can be written out and parsed again.
+///
+/// The placeholders of each universe are bound by a `forall`, and the
+/// canonical variables of each universe by an `exists` just inside the
+/// `forall` of their universe (so that they can name its placeholders, but
+/// not those of the universes after it). The environment becomes an `if`.
+fn requantify<I: Interner>(interner: I, goal: &UCanonical<InEnvironment<Goal<I>>>) -> Goal<I> {
+    let canonical = &goal.canonical;
+    let InEnvironment { environment, goal } = &canonical.value;
+    let mut goal = goal.clone();
+    if !environment.clauses.is_empty(interner) {
+        goal = GoalData::Implies(environment.clauses.clone(), goal).intern(interner);
+    }
+
+    let mut collector = PlaceholderCollector {
+        interner,
+        placeholders: BTreeMap::new(),
+    };
+    let goal = goal.fold_with(&mut collector, DebruijnIndex::INNERMOST);
+
+    // Binder `2 * u` is the `exists` of universe `u`, and binder `2 * u - 1`
+    // the `forall` of universe `u`; binder 0 is the outermost one.
+    let max_universe = canonical
+        .binders
+        .iter(interner)
+        .map(|kind| kind.skip_kind().counter)
+        .chain(collector.placeholders.keys().map(|p| p.ui.counter))
+        .max()
+        .unwrap_or(0);
+    let mut binders = vec![vec![]; 2 * max_universe + 1];
+    let mut var_positions = vec![];
+    for kind in canonical.binders.iter(interner) {
+        let binder = 2 * kind.skip_kind().counter;
+        var_positions.push((binder, binders[binder].len()));
+        binders[binder].push(kind.kind.clone());
+    }
+    let mut placeholder_positions = BTreeMap::new();
+    for (placeholder, kind) in collector.placeholders {
+        let binder = 2 * placeholder.ui.counter - 1;
+        placeholder_positions.insert(placeholder, (binder, binders[binder].len()));
+        binders[binder].push(kind);
+    }
+
+    // Only the binders with variables are added to the goal, so the depth of
+    // a binder is the number of non-empty binders inside of it.
+    let mut depths = vec![0; binders.len()];
+    let mut depth = 0;
+    for (binder, kinds) in binders.iter().enumerate().rev() {
+        depths[binder] = depth;
+        if !kinds.is_empty() {
+            depth += 1;
+        }
+    }
+
+    let mut requantifier = Requantifier {
+        interner,
+        depths,
+        var_positions,
+        placeholder_positions,
+    };
+    let mut goal = goal.fold_with(&mut requantifier, DebruijnIndex::INNERMOST);
+    for (binder, kinds) in binders.into_iter().enumerate().rev() {
+        if kinds.is_empty() {
+            continue;
+        }
+        let kind = if binder % 2 == 0 {
+            QuantifierKind::Exists
+        } else {
+            QuantifierKind::ForAll
+        };
+        let kinds = VariableKinds::from_iter(interner, kinds);
+        goal = GoalData::Quantified(kind, Binders::new(kinds, goal)).intern(interner);
+    }
+    goal
+}
+
+/// Records the kind of each placeholder in a value, leaving it unchanged.
+#[derive(FallibleTypeFolder)]
+struct PlaceholderCollector<I: Interner> {
+    interner: I,
+    placeholders: BTreeMap<PlaceholderIndex, VariableKind<I>>,
+}
+
+impl<I: Interner> TypeFolder<I> for PlaceholderCollector<I> {
+    fn as_dyn(&mut self) -> &mut dyn TypeFolder<I> {
+        self
+    }
+
+    fn fold_free_placeholder_ty(
+        &mut self,
+        universe: PlaceholderIndex,
+        _outer_binder: DebruijnIndex,
+    ) -> Ty<I> {
+        self.placeholders
+            .insert(universe, VariableKind::Ty(TyVariableKind::General));
+        universe.to_ty(self.interner)
+    }
+
+    fn fold_free_placeholder_lifetime(
+        &mut self,
+        universe: PlaceholderIndex,
+        _outer_binder: DebruijnIndex,
+    ) -> Lifetime<I> {
+        self.placeholders.insert(universe, VariableKind::Lifetime);
+        universe.to_lifetime(self.interner)
+    }
+
+    fn fold_free_placeholder_const(
+        &mut self,
+        ty: Ty<I>,
+        universe: PlaceholderIndex,
+        _outer_binder: DebruijnIndex,
+    ) -> Const<I> {
+        self.placeholders
+            .insert(universe, VariableKind::Const(ty.clone()));
+        universe.to_const(self.interner, ty)
+    }
+
+    fn interner(&self) -> I {
+        self.interner
+    }
+}
+
+/// Replaces canonical variables and placeholders with variables bound by the
+/// binders `requantify` adds.
+#[derive(FallibleTypeFolder)]
+struct Requantifier<I: Interner> {
+    interner: I,
+    depths: Vec<usize>,
+    var_positions: Vec<(usize, usize)>,
+    placeholder_positions: BTreeMap<PlaceholderIndex, (usize, usize)>,
+}
+
+impl<I: Interner> Requantifier<I> {
+    fn bound_var(&self, (binder, index): (usize, usize), outer_binder: DebruijnIndex) -> BoundVar {
+        BoundVar::new(DebruijnIndex::new(self.depths[binder] as u32), index)
+            .shifted_in_from(outer_binder)
+    }
+}
+
+impl<I: Interner> TypeFolder<I> for Requantifier<I> {
+    fn as_dyn(&mut self) -> &mut dyn TypeFolder<I> {
+        self
+    }
+
+    fn fold_free_var_ty(&mut self, bound_var: BoundVar, outer_binder: DebruijnIndex) -> Ty<I> {
+        self.bound_var(self.var_positions[bound_var.index], outer_binder)
+            .to_ty(self.interner)
+    }
+
+    fn fold_free_var_lifetime(
+        &mut self,
+        bound_var: BoundVar,
+        outer_binder: DebruijnIndex,
+    ) -> Lifetime<I> {
+        self.bound_var(self.var_positions[bound_var.index], outer_binder)
+            .to_lifetime(self.interner)
+    }
+
+    fn fold_free_var_const(
+        &mut self,
+        ty: Ty<I>,
+        bound_var: BoundVar,
+        outer_binder: DebruijnIndex,
+    ) -> Const<I> {
+        self.bound_var(self.var_positions[bound_var.index], outer_binder)
+            .to_const(self.interner, ty)
+    }
+
+    fn fold_free_placeholder_ty(
+        &mut self,
+        universe: PlaceholderIndex,
+        outer_binder: DebruijnIndex,
+    ) -> Ty<I> {
+        self.bound_var(self.placeholder_positions[&universe], outer_binder)
+            .to_ty(self.interner)
+    }
+
+    fn fold_free_placeholder_lifetime(
+        &mut self,
+        universe: PlaceholderIndex,
+        outer_binder: DebruijnIndex,
+    ) -> Lifetime<I> {
+        self.bound_var(self.placeholder_positions[&universe], outer_binder)
+            .to_lifetime(self.interner)
+    }
+
+    fn fold_free_placeholder_const(
+        &mut self,
+        ty: Ty<I>,
+        universe: PlaceholderIndex,
+        outer_binder: DebruijnIndex,
+    ) -> Const<I> {
+        self.bound_var(self.placeholder_positions[&universe], outer_binder)
+            .to_const(self.interner, ty)
+    }
+
+    fn interner(&self) -> I {
+        self.interner
+    }
+}
@@ -5,10 +5,27 @@ use chalk_ir::*;
 use std::fmt;
 use tracing::debug;
 
+pub mod budget;
+pub mod cancel;
+pub mod enumerate;
+pub mod proof;
+pub mod regions;
+pub mod stats;
 pub mod truncate;
 
+use self::budget::{Budget, BudgetExhausted};
+use self::cancel::{CancellationToken, Cancelled};
+use self::enumerate::{AnswerCollector, Answers};
+use self::proof::{FailureTree, ProofResult};
+use self::stats::SolverStats;
+
 /// A (possible) solution for a proposed goal.
 #[derive(Clone, Debug, PartialEq, Eq, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound = "I: chalk_ir::interner::SerdeInterner")
+)]
 pub enum Solution<I: Interner> {
     /// The goal indeed holds, and there is a unique value for all existential
     /// variables. In this case, we also record a set of lifetime constraints
@@ -25,6 +42,11 @@ pub enum Solution<I: Interner> {
 /// When a goal holds ambiguously (e.g., because there are multiple possible
 /// solutions), we issue a set of *guidance* back to type inference.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound = "I: chalk_ir::interner::SerdeInterner")
+)]
 pub enum Guidance<I: Interner> {
     /// The existential variables *must* have the given values if the goal is
     /// ever to hold, but that alone isn't enough to guarantee the goal will
@@ -306,6 +328,51 @@ where
         should_continue: &dyn std::ops::Fn() -> bool,
     ) -> Option<Solution<I>>;
 
+    /// Like `solve_limited`, but the solving of the goal is limited by
+    /// `budget`. If the budget runs out before the solution is found,
+    /// returns `Err(BudgetExhausted)` instead of whatever partial solution
+    /// the solver had come up with.
+    ///
+    /// The work done before the budget ran out is not lost: solving the goal
+    /// again with the same solver picks up from the SLG solver's tables, or
+    /// from the results the recursive solver cached for the subgoals it did
+    /// finish.
+    fn solve_with_budget(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        budget: &Budget,
+    ) -> Result<Option<Solution<I>>, BudgetExhausted> {
+        let meter = budget.meter();
+        let solution = self.solve_limited(program, goal, &|| meter.take_step());
+        if meter.is_exhausted() {
+            debug!(steps_taken = meter.steps_taken(), "budget exhausted");
+            Err(BudgetExhausted)
+        } else {
+            Ok(solution)
+        }
+    }
+
+    /// Like `solve`, but stops as soon as possible once `cancellation` is
+    /// cancelled, returning `Err(Cancelled)`. As with `solve_with_budget`,
+    /// the work done before that is not lost.
+    ///
+    /// By default, this is `solve_limited`, checking the token whenever the
+    /// solver checks `should_continue`; solvers can check it more often.
+    fn solve_cancellable(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        cancellation: &CancellationToken,
+    ) -> Result<Option<Solution<I>>, Cancelled> {
+        let solution = self.solve_limited(program, goal, &|| !cancellation.is_cancelled());
+        if cancellation.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(solution)
+        }
+    }
+
     /// Attempts to solve the given goal, which must be in canonical
     /// form. Provides multiple solutions to function `f`.  This will do
     /// only as much work towards `goal` as it has to (and that work
@@ -335,6 +402,83 @@ where
         f: &mut dyn FnMut(SubstitutionResult<Canonical<ConstrainedSubst<I>>>, bool) -> bool,
     ) -> bool;
 
+    /// Enumerates the answers to the given goal, stopping once more than
+    /// `limit` have been found. Unlike `solve_multiple`, the answers are
+    /// deduplicated, and put in an order that doesn't depend on how the
+    /// solver found them; see [`Answers`].
+    ///
+    /// This is implemented with `solve_multiple`; solvers which can't
+    /// enumerate answers that way have to implement it differently.
+    fn enumerate_answers(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        limit: usize,
+    ) -> Answers<I> {
+        let mut collector = AnswerCollector::new(program.interner(), goal, limit);
+        let processed_all =
+            self.solve_multiple(program, goal, &mut |result, _| collector.add_result(result));
+        if !processed_all {
+            collector.incomplete();
+        }
+        collector.finish(program)
+    }
+
+    /// Like `solve`, but if the goal has a solution, also returns a
+    /// [`ProofTree`] explaining it: a program clause which proves each
+    /// sub-goal.
+    ///
+    /// The solver doesn't record how it proved the goal, so the tree is
+    /// re-derived once the goal is solved, by solving each sub-goal again
+    /// (though the solver's cache makes that cheaper). It agrees with the
+    /// solution, but where several clauses prove a sub-goal, it may show a
+    /// different one than the solver used.
+    ///
+    /// The tree is only built if the solver gives the same answers for
+    /// those sub-goals when asked about each of them on its own. The SLG
+    /// solver doesn't always do so for the goals in a coinductive cycle; a
+    /// [`ProofError`] naming the goal that couldn't be explained is then
+    /// returned instead of the tree.
+    ///
+    /// [`ProofTree`]: proof::ProofTree
+    /// [`ProofError`]: proof::ProofError
+    fn solve_with_proof(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Option<(Solution<I>, ProofResult<I>)> {
+        proof::prove(program, goal, &mut |goal| self.solve(program, goal))
+    }
+
+    /// If `solve` finds no solution for the goal, returns a [`FailureTree`]
+    /// explaining why: for each program clause which could have proven the
+    /// goal, the conditions of that clause which have no solution, and so on
+    /// down to the goals no clause applies to. Returns `None` if the goal
+    /// has a solution.
+    ///
+    /// Like `solve_with_proof`, this solves every sub-goal again.
+    fn explain_failure(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Option<FailureTree<I>> {
+        proof::explain_failure(program, goal, &mut |goal| self.solve(program, goal))
+    }
+
+    /// Starts collecting [`SolverStats`] about the goals solved from now on.
+    /// Does nothing if they are already being collected, or if the solver
+    /// does not support collecting them.
+    fn collect_stats(&mut self) {}
+
+    /// The statistics collected since `collect_stats` was called (or they
+    /// were last reset), or `None` if they are not being collected.
+    fn stats(&self) -> Option<SolverStats> {
+        None
+    }
+
+    /// Resets the collected statistics to zero, if they are being collected.
+    fn reset_stats(&mut self) {}
+
     /// A convenience method for when one doesn't need the actual solution,
     /// only whether or not one exists.
     fn has_unique_solution(
@@ -0,0 +1,105 @@
+//! Limits on how much work a solver may do for a goal.
+
+use std::cell::Cell;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// How much work a solver may do for a goal, as a number of steps and/or a
+/// wall-clock deadline. What a step is depends on the solver: the recursive
+/// solver takes one for each iteration on a goal, and the SLG solver one
+/// each time its quantum of work on the root goal runs out.
+///
+/// The default budget is unlimited.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Budget {
+    steps: Option<usize>,
+    deadline: Option<Instant>,
+}
+
+/// Returned by [`Solver::solve_with_budget`] when the budget ran out before
+/// the solver found the solution.
+///
+/// [`Solver::solve_with_budget`]: super::Solver::solve_with_budget
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BudgetExhausted;
+
+impl Budget {
+    /// A budget that allows any amount of work.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Allows at most `steps` steps.
+    pub fn with_steps(mut self, steps: usize) -> Self {
+        self.steps = Some(steps);
+        self
+    }
+
+    /// Allows work until `deadline`.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Allows work for `duration`, starting now.
+    pub fn with_timeout(self, duration: Duration) -> Self {
+        self.with_deadline(Instant::now() + duration)
+    }
+
+    /// Starts spending the budget.
+    pub fn meter(&self) -> BudgetMeter {
+        BudgetMeter {
+            budget: *self,
+            steps_taken: Cell::new(0),
+            exhausted: Cell::new(false),
+        }
+    }
+}
+
+/// Keeps track of how much of a [`Budget`] has been spent. It can be turned
+/// into the `should_continue` callback of [`Solver::solve_limited`] with
+/// [`take_step`].
+///
+/// [`Solver::solve_limited`]: super::Solver::solve_limited
+/// [`take_step`]: BudgetMeter::take_step
+#[derive(Debug)]
+pub struct BudgetMeter {
+    budget: Budget,
+    steps_taken: Cell<usize>,
+    exhausted: Cell<bool>,
+}
+
+impl BudgetMeter {
+    /// Takes a step, returning `false` (from then on) if the budget does
+    /// not allow it.
+    pub fn take_step(&self) -> bool {
+        if self.exhausted.get() {
+            return false;
+        }
+        let steps_taken = self.steps_taken.get() + 1;
+        self.steps_taken.set(steps_taken);
+        let out_of_steps = self.budget.steps.is_some_and(|steps| steps_taken > steps);
+        let out_of_time = self
+            .budget
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline);
+        self.exhausted.set(out_of_steps || out_of_time);
+        !self.exhausted.get()
+    }
+
+    /// Whether the budget ran out.
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted.get()
+    }
+
+    /// The number of steps taken so far.
+    pub fn steps_taken(&self) -> usize {
+        self.steps_taken.get()
+    }
+}
+
+impl fmt::Display for BudgetExhausted {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "budget exhausted")
+    }
+}
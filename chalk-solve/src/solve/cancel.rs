@@ -0,0 +1,84 @@
+//! Cancelling a solve from another thread, for example when an IDE no longer
+//! needs the result.
+
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Can be used (from any thread) to cancel the solves it was given to.
+///
+/// The solvers check the token between steps, and while generating program
+/// clauses. As `RustIrDatabase` calls can't fail, a solve that notices it was
+/// cancelled unwinds (with [`Cancelled`] as the panic payload) back to
+/// [`Solver::solve_cancellable`], which returns `Err(Cancelled)`. The solvers
+/// only unwind where their state is consistent, so they can keep being used
+/// afterwards: the work finished before the solve was cancelled is kept.
+///
+/// As this relies on unwinding, cancellation doesn't work when built with
+/// `panic = "abort"`: a cancelled solve aborts the process instead.
+///
+/// [`Solver::solve_cancellable`]: super::Solver::solve_cancellable
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Returned by [`Solver::solve_cancellable`] when the solve was cancelled
+/// before the solver found the solution.
+///
+/// [`Solver::solve_cancellable`]: super::Solver::solve_cancellable
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl CancellationToken {
+    /// A token that hasn't been cancelled (yet).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the solves this token was given to.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether `cancel` has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Unwinds with [`Cancelled`] if `cancel` has been called. This must
+    /// only be called within [`Cancelled::catch`].
+    pub fn check(&self) {
+        if self.is_cancelled() {
+            // Unlike `panic!`, this doesn't run the panic hook, so nothing is
+            // printed.
+            Cancelled.resume();
+        }
+    }
+}
+
+impl Cancelled {
+    /// Runs `op`, returning `Err(Cancelled)` if it was cancelled (i.e. a
+    /// [`CancellationToken::check`] unwound). Other panics are propagated.
+    pub fn catch<T>(op: impl FnOnce() -> T) -> Result<T, Cancelled> {
+        panic::catch_unwind(AssertUnwindSafe(op)).map_err(|payload| {
+            if payload.is::<Cancelled>() {
+                Cancelled
+            } else {
+                panic::resume_unwind(payload)
+            }
+        })
+    }
+
+    /// Unwinds again, after `catch` caught the cancellation to clean up.
+    pub fn resume(self) -> ! {
+        panic::resume_unwind(Box::new(self))
+    }
+}
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "cancelled")
+    }
+}
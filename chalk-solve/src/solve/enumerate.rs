@@ -0,0 +1,505 @@
+//! Enumerating the answers to a goal, for questions like "which types
+//! implement this trait?".
+//!
+//! The SLG solver enumerates answers itself (see `Solver::solve_multiple`);
+//! here they are deduplicated and put in a canonical order. The recursive
+//! solver only ever finds a single solution, so for it we enumerate answers
+//! by trying out the program clauses for the goal (as in SLD resolution), and
+//! use the solver to check each refinement of the goal. This is done depth
+//! first, but with iterative deepening, so that an infinite family of answers
+//! (such as `Vec<Vec<...<A>>>`) doesn't keep us from finding the others.
+
+use super::proof::{refine, SolveFn};
+use crate::clauses::program_clauses_for_goal;
+use crate::infer::InferenceTable;
+use crate::{RustIrDatabase, Solution, SubstitutionResult};
+use chalk_ir::interner::Interner;
+use chalk_ir::visit::{TypeSuperVisitable, TypeVisitable, TypeVisitor};
+use chalk_ir::*;
+use std::ops::ControlFlow;
+
+/// How many program clauses deep the recursive solver's enumeration goes
+/// before it gives up on a branch.
+const MAX_DEPTH: usize = 16;
+
+/// The answers to a goal, as returned by [`Solver::enumerate_answers`].
+///
+/// [`Solver::enumerate_answers`]: crate::Solver::enumerate_answers
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Answers<I: Interner> {
+    /// The answers, without duplicates (answers which only differ in how
+    /// their variables are numbered count as the same answer), ordered by
+    /// their structure: type constructors are compared by name, and by
+    /// their arguments if those are the same. Which answers there are, and
+    /// therefore their order, doesn't depend on the solver if `complete` is
+    /// true.
+    pub answers: Vec<Answer<I>>,
+    /// Whether these are all the answers. This is false if there were more
+    /// answers than the limit, or if the solver couldn't enumerate them all
+    /// (because of floundering, or because they are too deeply nested).
+    ///
+    /// If it is false, which of the answers were found (and so which of
+    /// them are returned) depends on the solver, and on the order in which
+    /// it tries things.
+    pub complete: bool,
+}
+
+/// One of the [`Answers`] to a goal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Answer<I: Interner> {
+    /// Values for the canonical variables of the goal, and the region
+    /// constraints that must hold for them to be an answer.
+    pub subst: Canonical<ConstrainedSubst<I>>,
+    /// Whether the solver couldn't tell whether `subst` is an answer, or
+    /// couldn't narrow it down further.
+    pub ambiguous: bool,
+}
+
+/// Collects answers as they are found, up to the limit.
+pub(super) struct AnswerCollector<I: Interner> {
+    interner: I,
+    universes: usize,
+    limit: usize,
+    answers: Vec<Answer<I>>,
+    complete: bool,
+}
+
+impl<I: Interner> AnswerCollector<I> {
+    pub(super) fn new(
+        interner: I,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        limit: usize,
+    ) -> Self {
+        AnswerCollector {
+            interner,
+            universes: goal.universes,
+            limit,
+            answers: vec![],
+            complete: true,
+        }
+    }
+
+    /// Adds an answer as `solve_multiple` gives it. Returns false once there
+    /// are more answers than the limit.
+    pub(super) fn add_result(
+        &mut self,
+        result: SubstitutionResult<Canonical<ConstrainedSubst<I>>>,
+    ) -> bool {
+        match result {
+            SubstitutionResult::Definite(subst) => self.add(subst, false),
+            SubstitutionResult::Ambiguous(subst) => self.add(subst, true),
+            SubstitutionResult::Floundered => {
+                self.complete = false;
+                true
+            }
+        }
+    }
+
+    /// Adds an answer, unless it's already there. Returns false once there
+    /// are more answers than the limit.
+    fn add(&mut self, subst: Canonical<ConstrainedSubst<I>>, ambiguous: bool) -> bool {
+        // Number the variables in the order they appear in, so that answers
+        // which differ only in that respect are equal.
+        let (mut infer, _, subst) =
+            InferenceTable::from_canonical(self.interner, self.universes, subst);
+        let subst = infer.canonicalize(self.interner, subst).quantified;
+
+        match self.answers.iter_mut().find(|answer| answer.subst == subst) {
+            Some(answer) => answer.ambiguous &= ambiguous,
+            None => self.answers.push(Answer { subst, ambiguous }),
+        }
+        if self.answers.len() > self.limit {
+            self.complete = false;
+        }
+        self.answers.len() <= self.limit
+    }
+
+    /// Marks the enumeration as incomplete.
+    pub(super) fn incomplete(&mut self) {
+        self.complete = false;
+    }
+
+    pub(super) fn finish(mut self, db: &dyn RustIrDatabase<I>) -> Answers<I> {
+        self.answers
+            .sort_by_cached_key(|answer| SortKey::of(db, &answer.subst));
+        self.answers.truncate(self.limit);
+        Answers {
+            answers: self.answers,
+            complete: self.complete,
+        }
+    }
+}
+
+/// A key to sort answers by, which only depends on their structure and on
+/// the names of the items they refer to (unlike their `Debug`
+/// representation, which may show the raw ids of those items instead).
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum KeyPart {
+    Ty(u8),
+    Lifetime(u8),
+    Const(u8),
+    Name(String),
+    Number(usize),
+    Scalar(Scalar),
+    Mutability(Mutability),
+    BoundVar(BoundVar),
+    Placeholder(PlaceholderIndex),
+}
+
+struct SortKey<'a, I: Interner> {
+    db: &'a dyn RustIrDatabase<I>,
+    parts: Vec<KeyPart>,
+}
+
+impl<'a, I: Interner> SortKey<'a, I> {
+    fn of(db: &'a dyn RustIrDatabase<I>, subst: &Canonical<ConstrainedSubst<I>>) -> Vec<KeyPart> {
+        let mut key = SortKey { db, parts: vec![] };
+        let _ = subst.visit_with(&mut key, DebruijnIndex::INNERMOST);
+        key.parts
+    }
+}
+
+impl<I: Interner> TypeVisitor<I> for SortKey<'_, I> {
+    type BreakTy = ();
+
+    fn as_dyn(&mut self) -> &mut dyn TypeVisitor<I, BreakTy = Self::BreakTy> {
+        self
+    }
+
+    fn interner(&self) -> I {
+        self.db.interner()
+    }
+
+    fn visit_ty(&mut self, ty: &Ty<I>, outer_binder: DebruijnIndex) -> ControlFlow<()> {
+        let db = self.db;
+        let (tag, part) = match ty.kind(db.interner()) {
+            TyKind::Adt(id, _) => (0, Some(KeyPart::Name(db.adt_name(*id)))),
+            TyKind::AssociatedType(id, _) => (1, Some(KeyPart::Name(db.assoc_type_name(*id)))),
+            TyKind::Scalar(scalar) => (2, Some(KeyPart::Scalar(*scalar))),
+            TyKind::Tuple(arity, _) => (3, Some(KeyPart::Number(*arity))),
+            TyKind::Array(..) => (4, None),
+            TyKind::Slice(_) => (5, None),
+            TyKind::Raw(mutability, _) => (6, Some(KeyPart::Mutability(*mutability))),
+            TyKind::Ref(mutability, ..) => (7, Some(KeyPart::Mutability(*mutability))),
+            TyKind::OpaqueType(id, _) => (8, Some(KeyPart::Name(db.opaque_type_name(*id)))),
+            TyKind::FnDef(id, _) => (9, Some(KeyPart::Name(db.fn_def_name(*id)))),
+            TyKind::Str => (10, None),
+            TyKind::Never => (11, None),
+            TyKind::Closure(..) => (12, None),
+            TyKind::Coroutine(..) => (13, None),
+            TyKind::CoroutineWitness(..) => (14, None),
+            TyKind::Foreign(..) => (15, None),
+            TyKind::Error => (16, None),
+            TyKind::Placeholder(index) => (17, Some(KeyPart::Placeholder(*index))),
+            TyKind::Dyn(_) => (18, None),
+            TyKind::Alias(AliasTy::Projection(projection)) => (
+                19,
+                Some(KeyPart::Name(
+                    db.assoc_type_name(projection.associated_ty_id),
+                )),
+            ),
+            TyKind::Alias(AliasTy::Opaque(opaque)) => (
+                20,
+                Some(KeyPart::Name(db.opaque_type_name(opaque.opaque_ty_id))),
+            ),
+            TyKind::Function(fn_ptr) => (21, Some(KeyPart::Number(fn_ptr.num_binders))),
+            TyKind::BoundVar(var) => (22, Some(KeyPart::BoundVar(*var))),
+            TyKind::InferenceVar(..) => (23, None),
+        };
+        self.parts.push(KeyPart::Ty(tag));
+        self.parts.extend(part);
+        ty.super_visit_with(self, outer_binder)
+    }
+
+    fn visit_lifetime(
+        &mut self,
+        lifetime: &Lifetime<I>,
+        _outer_binder: DebruijnIndex,
+    ) -> ControlFlow<()> {
+        let (tag, part) = match lifetime.data(self.db.interner()) {
+            LifetimeData::BoundVar(var) => (0, Some(KeyPart::BoundVar(*var))),
+            LifetimeData::Placeholder(index) => (1, Some(KeyPart::Placeholder(*index))),
+            LifetimeData::Static => (2, None),
+            LifetimeData::Erased => (3, None),
+            LifetimeData::Error => (4, None),
+            LifetimeData::InferenceVar(_) | LifetimeData::Phantom(..) => (5, None),
+        };
+        self.parts.push(KeyPart::Lifetime(tag));
+        self.parts.extend(part);
+        ControlFlow::Continue(())
+    }
+
+    fn visit_const(&mut self, constant: &Const<I>, outer_binder: DebruijnIndex) -> ControlFlow<()> {
+        let (tag, part) = match &constant.data(self.db.interner()).value {
+            ConstValue::BoundVar(var) => (0, Some(KeyPart::BoundVar(*var))),
+            ConstValue::Placeholder(index) => (1, Some(KeyPart::Placeholder(*index))),
+            ConstValue::Concrete(concrete) => (2, Some(KeyPart::Name(format!("{:?}", concrete)))),
+            ConstValue::InferenceVar(_) => (3, None),
+        };
+        self.parts.push(KeyPart::Const(tag));
+        self.parts.extend(part);
+        constant.super_visit_with(self, outer_binder)
+    }
+
+    fn visit_where_clause(
+        &mut self,
+        where_clause: &WhereClause<I>,
+        outer_binder: DebruijnIndex,
+    ) -> ControlFlow<()> {
+        if let WhereClause::Implemented(trait_ref) = where_clause {
+            self.parts
+                .push(KeyPart::Name(self.db.trait_name(trait_ref.trait_id)));
+        }
+        where_clause.super_visit_with(self.as_dyn(), outer_binder)
+    }
+}
+
+/// Enumerates up to `limit` answers to `goal` by trying out the program
+/// clauses for it, using `solve` (which is `Solver::solve`) to check each
+/// refinement. This is how solvers that only find a single solution
+/// implement `Solver::enumerate_answers`.
+pub fn enumerate_by_clauses<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    goal: &UCanonical<InEnvironment<Goal<I>>>,
+    limit: usize,
+    solve: &mut SolveFn<'_, I>,
+) -> Answers<I> {
+    enumerate_by_clauses_to_depth(db, goal, limit, MAX_DEPTH, solve)
+}
+
+/// Like `enumerate_by_clauses`, but gives up on branches which need more
+/// than `max_depth` program clauses (leaving the answers incomplete) rather
+/// than the default.
+pub fn enumerate_by_clauses_to_depth<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    goal: &UCanonical<InEnvironment<Goal<I>>>,
+    limit: usize,
+    max_depth: usize,
+    solve: &mut SolveFn<'_, I>,
+) -> Answers<I> {
+    let interner = db.interner();
+    let mut depth = 1;
+    loop {
+        let (infer, root_subst, root_goal) =
+            InferenceTable::from_canonical(interner, goal.universes, goal.canonical.clone());
+        let mut enumerator = Enumerator {
+            db,
+            solve: &mut *solve,
+            infer,
+            root_subst,
+            root_goal: root_goal.clone(),
+            collector: AnswerCollector::new(interner, goal, limit),
+            max_depth: depth,
+            cut_off: false,
+        };
+        let limit_reached = enumerator.search(vec![root_goal], 0).is_break();
+        let Enumerator {
+            mut collector,
+            cut_off,
+            ..
+        } = enumerator;
+
+        if limit_reached || !cut_off {
+            return collector.finish(db);
+        }
+        if depth >= max_depth {
+            collector.incomplete();
+            return collector.finish(db);
+        }
+        depth += 1;
+    }
+}
+
+struct Enumerator<'s, I: Interner> {
+    db: &'s dyn RustIrDatabase<I>,
+    solve: &'s mut SolveFn<'s, I>,
+    infer: InferenceTable<I>,
+    /// The canonical variables of the goal, as inference variables.
+    root_subst: Substitution<I>,
+    root_goal: InEnvironment<Goal<I>>,
+    collector: AnswerCollector<I>,
+    /// How many clauses deep to go.
+    max_depth: usize,
+    /// Whether any branch was cut off at `max_depth`.
+    cut_off: bool,
+}
+
+impl<I: Interner> Enumerator<'_, I> {
+    /// Finds the answers to the goal that are left once the `pending` goals
+    /// hold, having applied `depth` clauses so far. Breaks once there are
+    /// more answers than the limit.
+    fn search(&mut self, pending: Vec<InEnvironment<Goal<I>>>, depth: usize) -> ControlFlow<()> {
+        let snapshot = self.infer.snapshot();
+        let result = self.search_in_snapshot(pending, depth);
+        self.infer.rollback_to(snapshot);
+        result
+    }
+
+    fn search_in_snapshot(
+        &mut self,
+        mut pending: Vec<InEnvironment<Goal<I>>>,
+        depth: usize,
+    ) -> ControlFlow<()> {
+        let interner = self.db.interner();
+
+        // If the goal as a whole (with the values found so far) has a unique
+        // solution, that's an answer; if it has none, this branch is dead.
+        match refine(self.db, &mut self.infer, self.solve, &self.root_goal) {
+            None => return ControlFlow::Continue(()),
+            Some((Solution::Unique(_), constraints)) => {
+                return self.add_answer(constraints, false);
+            }
+            Some(_) => {}
+        }
+        if depth == self.max_depth {
+            self.cut_off = true;
+            return ControlFlow::Continue(());
+        }
+
+        // Otherwise, split on the first pending domain goal which doesn't
+        // have a unique solution either. `pending` is a stack, so the goals
+        // are pushed in reverse.
+        pending.reverse();
+        while let Some(goal) = pending.pop() {
+            let environment = &goal.environment;
+            match goal.goal.data(interner) {
+                GoalData::Quantified(QuantifierKind::ForAll, subgoal) => {
+                    let subgoal = self
+                        .infer
+                        .instantiate_binders_universally(interner, subgoal.clone());
+                    pending.push(InEnvironment::new(environment, subgoal));
+                }
+                GoalData::Quantified(QuantifierKind::Exists, subgoal) => {
+                    let subgoal = self
+                        .infer
+                        .instantiate_binders_existentially(interner, subgoal.clone());
+                    pending.push(InEnvironment::new(environment, subgoal));
+                }
+                GoalData::Implies(clauses, subgoal) => {
+                    let environment = environment
+                        .clone()
+                        .add_clauses(interner, clauses.iter(interner).cloned());
+                    pending.push(InEnvironment::new(&environment, subgoal.clone()));
+                }
+                GoalData::All(goals) => {
+                    pending.extend(
+                        goals
+                            .iter(interner)
+                            .rev()
+                            .map(|subgoal| InEnvironment::new(environment, subgoal.clone())),
+                    );
+                }
+                GoalData::DomainGoal(domain_goal) => {
+                    match refine(self.db, &mut self.infer, self.solve, &goal) {
+                        None => return ControlFlow::Continue(()),
+                        Some((Solution::Unique(_), _)) => {}
+                        Some(_) => {
+                            pending.reverse();
+                            return self.split(environment, domain_goal, pending, depth);
+                        }
+                    }
+                }
+                // Other goals can't be split up; all we can do is make sure
+                // they still have a solution.
+                _ => {
+                    if refine(self.db, &mut self.infer, self.solve, &goal).is_none() {
+                        return ControlFlow::Continue(());
+                    }
+                }
+            }
+        }
+
+        // There's nothing left to split on, but the goal still doesn't have
+        // a unique solution.
+        self.add_answer(Constraints::empty(interner), true)
+    }
+
+    /// Tries out each of the program clauses for `goal`, and searches for
+    /// the answers left once their conditions (and then the `rest`) hold.
+    fn split(
+        &mut self,
+        environment: &Environment<I>,
+        goal: &DomainGoal<I>,
+        rest: Vec<InEnvironment<Goal<I>>>,
+        depth: usize,
+    ) -> ControlFlow<()> {
+        let interner = self.db.interner();
+        let canonical = self
+            .infer
+            .canonicalize(interner, InEnvironment::new(environment, goal.clone()));
+        let canonical = InferenceTable::u_canonicalize(interner, &canonical.quantified).quantified;
+        let Ok(clauses) = program_clauses_for_goal(self.db, &canonical) else {
+            self.cut_off = true;
+            return ControlFlow::Continue(());
+        };
+
+        for clause in &clauses {
+            let snapshot = self.infer.snapshot();
+            if let Some(mut subgoals) = self.apply_clause(environment, goal, clause) {
+                subgoals.extend(rest.iter().cloned());
+                let result = self.search(subgoals, depth + 1);
+                if result.is_break() {
+                    self.infer.rollback_to(snapshot);
+                    return result;
+                }
+            }
+            self.infer.rollback_to(snapshot);
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Unifies `goal` with the consequence of `clause`, and returns the
+    /// goals that must hold for the clause to apply.
+    fn apply_clause(
+        &mut self,
+        environment: &Environment<I>,
+        goal: &DomainGoal<I>,
+        clause: &ProgramClause<I>,
+    ) -> Option<Vec<InEnvironment<Goal<I>>>> {
+        let interner = self.db.interner();
+        let ProgramClauseData(implication) = clause.data(interner);
+        let ProgramClauseImplication {
+            consequence,
+            conditions,
+            ..
+        } = self
+            .infer
+            .instantiate_binders_existentially(interner, implication.clone());
+
+        let relation = self
+            .infer
+            .relate(
+                interner,
+                self.db.unification_database(),
+                environment,
+                Variance::Invariant,
+                goal,
+                &consequence,
+            )
+            .ok()?;
+
+        Some(
+            conditions
+                .iter(interner)
+                .map(|condition| InEnvironment::new(environment, condition.clone()))
+                .chain(relation.goals)
+                .collect(),
+        )
+    }
+
+    /// Adds the values found for the canonical variables of the goal as an
+    /// answer.
+    fn add_answer(&mut self, constraints: Constraints<I>, ambiguous: bool) -> ControlFlow<()> {
+        let interner = self.db.interner();
+        let subst = ConstrainedSubst {
+            subst: self.root_subst.clone(),
+            constraints,
+        };
+        let subst = self.infer.canonicalize(interner, subst).quantified;
+        if self.collector.add(subst, ambiguous) {
+            ControlFlow::Continue(())
+        } else {
+            ControlFlow::Break(())
+        }
+    }
+}
@@ -0,0 +1,883 @@
+//! Proof trees, which explain why a goal holds, and failure trees, which
+//! explain why it doesn't.
+//!
+//! Proof trees are *re-derived*, not recorded: neither solver keeps track of
+//! how it proved a goal, so a proof tree is built after the fact. Starting
+//! from the solved goal, we look for a program clause whose conditions can
+//! all be proven, and then do the same for each of those conditions. The
+//! solver itself is used to check each condition (and to find values for any
+//! variables in it), so the tree agrees with the solution it returned, but
+//! when more than one clause proves a goal, the clause in the tree is the
+//! first one we find, which need not be the one the solver happened to use.
+//!
+//! This relies on the solver giving the same answer for a sub-goal when it
+//! is asked about it on its own as it did while solving the whole goal. The
+//! SLG solver doesn't always do so for coinductive cycles: once it has
+//! solved `List<Foo>: Send` it may find no solution for
+//! `Option<List<Foo>>: Send`, which is part of the cycle, so no clause
+//! proving `List<Foo>: Send` seems to apply. Rather than returning a tree
+//! with a hole in it, we then return a [`ProofError`] naming the goal we
+//! couldn't explain.
+//!
+//! Failure trees are built the same way, except that we keep every clause
+//! which could have proven a goal, along with the conditions of that clause
+//! which have no solution. Those conditions are then explained in turn, down
+//! to the goals no clause applies to at all.
+
+use std::fmt;
+
+use crate::clauses::program_clauses_for_goal;
+use crate::display;
+use crate::infer::ucanonicalize::UniverseMapExt;
+use crate::infer::{InferenceTable, ParameterEnaVariableExt};
+use crate::{Guidance, RustIrDatabase, Solution};
+use chalk_ir::fold::TypeFoldable;
+use chalk_ir::interner::{HasInterner, Interner};
+use chalk_ir::visit::TypeVisitable;
+use chalk_ir::*;
+use tracing::debug;
+
+/// How deep a proof tree can get before we stop explaining sub-goals. This is
+/// only reached by goals the solver itself gave up on (e.g. overflow).
+const MAX_DEPTH: usize = 64;
+
+/// Explains why a goal holds. Created by [`Solver::solve_with_proof`].
+///
+/// The tree is re-derived after the goal is solved rather than recorded while
+/// solving it (see the module docs), so it shows *a* proof of the goal, not
+/// necessarily the one the solver found.
+///
+/// [`Solver::solve_with_proof`]: crate::Solver::solve_with_proof
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofTree<I: Interner> {
+    /// The goal, with the values the solver found for its variables applied.
+    /// Variables which don't have a value are left free.
+    pub goal: Canonical<InEnvironment<Goal<I>>>,
+    /// How the goal was proven.
+    pub step: ProofStep<I>,
+    /// The sub-goals which had to be proven for `step` to apply.
+    pub children: Vec<ProofTree<I>>,
+}
+
+/// A single step in a [`ProofTree`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProofStep<I: Interner> {
+    /// A domain goal was proven by applying a program clause (e.g. one
+    /// created from an impl, a where clause, or a builtin or auto trait
+    /// rule). The clause's conditions are the children. If several clauses
+    /// prove the goal, this is the first one found to, preferring one whose
+    /// conditions have a unique solution.
+    Clause {
+        clause: ProgramClause<I>,
+        source: ClauseSource,
+    },
+    /// The goal was broken down into simpler goals (the children), as is done
+    /// for `forall`, `exists`, `if` and conjunctions.
+    Simplified,
+    /// The goal (`a = b` or `Subtype(a, b)`) was proven by unification.
+    Unified,
+    /// The goal is `not { G }`, and `G` has no solution.
+    Negated,
+    /// The goal is already being proven further up the tree. This happens
+    /// for coinductive goals such as auto traits.
+    Cycle,
+    /// The solver found a solution, but no clause explains it. This happens
+    /// when the solution is ambiguous, for example because of overflow.
+    Unexplained,
+}
+
+/// Returned instead of a [`ProofTree`] when the solver gives a different
+/// answer for a goal the solution depends on once it is asked about it on
+/// its own (see the module docs).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofError<I: Interner> {
+    /// The goal which couldn't be explained: either the solver found no
+    /// solution for it, or it has a unique solution but none of the clauses
+    /// which could prove it apply. The values found for its variables are
+    /// applied.
+    pub goal: Canonical<InEnvironment<Goal<I>>>,
+}
+
+/// Where the clause used by a [`ProofStep::Clause`] came from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ClauseSource {
+    /// The clause comes from the program: an impl, a trait or type
+    /// declaration, a builtin rule, or a custom clause.
+    Program,
+    /// The clause comes from the environment, i.e. it is an assumption (such
+    /// as a where clause) that holds where the goal is being proven.
+    Environment,
+}
+
+impl<I: Interner> ProofTree<I> {
+    /// Displays the tree as an indented list of goals, one per line, each
+    /// followed by the clause that was used to prove it.
+    pub fn display<'a>(&'a self, db: &'a dyn RustIrDatabase<I>) -> ProofTreeDisplay<'a, I> {
+        ProofTreeDisplay { tree: self, db }
+    }
+}
+
+pub struct ProofTreeDisplay<'a, I: Interner> {
+    tree: &'a ProofTree<I>,
+    db: &'a dyn RustIrDatabase<I>,
+}
+
+impl<I: Interner> ProofTreeDisplay<'_, I> {
+    fn fmt_node(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        tree: &ProofTree<I>,
+        depth: usize,
+    ) -> fmt::Result {
+        let interner = self.db.interner();
+        let InEnvironment { goal, .. } =
+            InferenceTable::new().instantiate_canonical(interner, tree.goal.clone());
+        write!(
+            f,
+            "{:indent$}`{}`",
+            "",
+            display::display_value(self.db, &goal),
+            indent = depth * 4
+        )?;
+        match &tree.step {
+            ProofStep::Clause { clause, source } => {
+                let source = match source {
+                    ClauseSource::Program => "clause",
+                    ClauseSource::Environment => "assumption",
+                };
+                write!(
+                    f,
+                    " by {} `{}`",
+                    source,
+                    display::display_value(self.db, clause)
+                )?;
+            }
+            ProofStep::Simplified => {}
+            ProofStep::Unified => write!(f, " by unification")?,
+            ProofStep::Negated => write!(f, " as the negated goal has no solution")?,
+            ProofStep::Cycle => write!(f, " by cycle")?,
+            ProofStep::Unexplained => write!(f, " (unexplained)")?,
+        }
+        writeln!(f)?;
+        for child in &tree.children {
+            self.fmt_node(f, child, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl<I: Interner> fmt::Display for ProofTreeDisplay<'_, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_node(f, self.tree, 0)
+    }
+}
+
+/// A proof tree, or the reason it couldn't be built.
+pub type ProofResult<I> = Result<ProofTree<I>, ProofError<I>>;
+
+impl<I: Interner> ProofError<I> {
+    pub fn display<'a>(&'a self, db: &'a dyn RustIrDatabase<I>) -> ProofErrorDisplay<'a, I> {
+        ProofErrorDisplay { error: self, db }
+    }
+}
+
+pub struct ProofErrorDisplay<'a, I: Interner> {
+    error: &'a ProofError<I>,
+    db: &'a dyn RustIrDatabase<I>,
+}
+
+impl<I: Interner> fmt::Display for ProofErrorDisplay<'_, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let interner = self.db.interner();
+        let InEnvironment { goal, .. } =
+            InferenceTable::new().instantiate_canonical(interner, self.error.goal.clone());
+        writeln!(
+            f,
+            "couldn't explain `{}`: the solver gives a different answer for it, \
+             or for one of its sub-goals, when asked about it on its own",
+            display::display_value(self.db, &goal)
+        )
+    }
+}
+
+/// Explains why a goal has no solution. Created by
+/// [`Solver::explain_failure`].
+///
+/// [`Solver::explain_failure`]: crate::Solver::explain_failure
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FailureTree<I: Interner> {
+    /// The goal which has no solution, with the values found for its
+    /// variables (by solving the goals next to it) applied.
+    pub goal: Canonical<InEnvironment<Goal<I>>>,
+    /// Why the goal has no solution.
+    pub reason: FailureReason<I>,
+}
+
+/// Why the goal of a [`FailureTree`] has no solution.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FailureReason<I: Interner> {
+    /// The goal is a domain goal, and no program clause could prove it.
+    NoClauses,
+    /// The goal is a domain goal, and every clause which could have proven
+    /// it was rejected.
+    Rejected(Vec<RejectedClause<I>>),
+    /// The goal was broken down into simpler goals, as is done for `forall`,
+    /// `exists`, `if` and conjunctions, and these have no solution.
+    Subgoals(Vec<FailureTree<I>>),
+    /// The goal (`a = b` or `Subtype(a, b)`) doesn't unify.
+    NotUnifiable,
+    /// The goal is `not { G }`, and `G` has a solution.
+    Negated,
+    /// The goal is already being explained further up the tree. This happens
+    /// for inductive goals which can only be proven by assuming they hold.
+    Cycle,
+    /// None of the sub-goals fail on their own. This happens when the
+    /// solver gives up on a goal, for example because of overflow.
+    Unexplained,
+}
+
+/// A clause which could have proven the goal of a [`FailureTree`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RejectedClause<I: Interner> {
+    pub clause: ProgramClause<I>,
+    pub source: ClauseSource,
+    pub reason: RejectionReason<I>,
+}
+
+/// Why a [`RejectedClause`] doesn't apply.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RejectionReason<I: Interner> {
+    /// The consequence of the clause doesn't unify with the goal.
+    Mismatch,
+    /// These conditions of the clause have no solution. If this is empty,
+    /// each condition has a solution, but not all of them at once.
+    Conditions(Vec<FailureTree<I>>),
+}
+
+impl<I: Interner> FailureTree<I> {
+    /// Displays the tree as an indented list of goals, one per line, each
+    /// followed by the reason why it has no solution. Rejected clauses are
+    /// listed below their goal, followed by their conditions which have no
+    /// solution.
+    pub fn display<'a>(&'a self, db: &'a dyn RustIrDatabase<I>) -> FailureTreeDisplay<'a, I> {
+        FailureTreeDisplay { tree: self, db }
+    }
+}
+
+pub struct FailureTreeDisplay<'a, I: Interner> {
+    tree: &'a FailureTree<I>,
+    db: &'a dyn RustIrDatabase<I>,
+}
+
+impl<I: Interner> FailureTreeDisplay<'_, I> {
+    fn fmt_node(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        tree: &FailureTree<I>,
+        depth: usize,
+    ) -> fmt::Result {
+        let interner = self.db.interner();
+        let InEnvironment { goal, .. } =
+            InferenceTable::new().instantiate_canonical(interner, tree.goal.clone());
+        write!(
+            f,
+            "{:indent$}`{}` has no solution",
+            "",
+            display::display_value(self.db, &goal),
+            indent = depth * 4
+        )?;
+        match &tree.reason {
+            FailureReason::NoClauses => writeln!(f, ": no clause applies"),
+            FailureReason::Rejected(clauses) => {
+                writeln!(f, ":")?;
+                for rejected in clauses {
+                    self.fmt_rejected(f, rejected, depth + 1)?;
+                }
+                Ok(())
+            }
+            FailureReason::Subgoals(children) => {
+                writeln!(f, ":")?;
+                for child in children {
+                    self.fmt_node(f, child, depth + 1)?;
+                }
+                Ok(())
+            }
+            FailureReason::NotUnifiable => writeln!(f, ": the two sides don't unify"),
+            FailureReason::Negated => writeln!(f, ": the negated goal has a solution"),
+            FailureReason::Cycle => writeln!(f, ": it can only be proven by assuming it holds"),
+            FailureReason::Unexplained => writeln!(f, " (unexplained)"),
+        }
+    }
+
+    fn fmt_rejected(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        rejected: &RejectedClause<I>,
+        depth: usize,
+    ) -> fmt::Result {
+        let source = match rejected.source {
+            ClauseSource::Program => "clause",
+            ClauseSource::Environment => "assumption",
+        };
+        write!(
+            f,
+            "{:indent$}{} `{}` rejected",
+            "",
+            source,
+            display::display_value(self.db, &rejected.clause),
+            indent = depth * 4
+        )?;
+        match &rejected.reason {
+            RejectionReason::Mismatch => writeln!(f, " as it doesn't match the goal"),
+            RejectionReason::Conditions(conditions) if conditions.is_empty() => {
+                writeln!(f, " as its conditions can't all hold at once")
+            }
+            RejectionReason::Conditions(conditions) => {
+                writeln!(f, " because:")?;
+                for condition in conditions {
+                    self.fmt_node(f, condition, depth + 1)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<I: Interner> fmt::Display for FailureTreeDisplay<'_, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_node(f, self.tree, 0)
+    }
+}
+
+/// Used to solve the sub-goals of a proof; this is `Solver::solve`.
+pub type SolveFn<'s, I> =
+    dyn FnMut(&UCanonical<InEnvironment<Goal<I>>>) -> Option<Solution<I>> + 's;
+
+/// Solves `goal` using `solve`, and builds a proof tree for it if it has a
+/// solution.
+pub(crate) fn prove<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    goal: &UCanonical<InEnvironment<Goal<I>>>,
+    solve: &mut SolveFn<'_, I>,
+) -> Option<(Solution<I>, ProofResult<I>)> {
+    let solution = solve(goal)?;
+
+    let interner = db.interner();
+    let (infer, _, goal) =
+        InferenceTable::from_canonical(interner, goal.universes, goal.canonical.clone());
+    let mut builder = ProofBuilder {
+        db,
+        solve,
+        infer,
+        stack: vec![],
+    };
+    let tree = builder.build(goal).map(|tree| builder.resolve(tree));
+    Some((solution, tree))
+}
+
+/// Solves `goal` using `solve`, and builds a failure tree for it if it has
+/// no solution.
+pub(crate) fn explain_failure<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    goal: &UCanonical<InEnvironment<Goal<I>>>,
+    solve: &mut SolveFn<'_, I>,
+) -> Option<FailureTree<I>> {
+    if solve(goal).is_some() {
+        return None;
+    }
+
+    let interner = db.interner();
+    let (infer, _, goal) =
+        InferenceTable::from_canonical(interner, goal.universes, goal.canonical.clone());
+    let mut builder = ProofBuilder {
+        db,
+        solve,
+        infer,
+        stack: vec![],
+    };
+    Some(builder.explain_failure(goal))
+}
+
+/// Solves `goal` (whose variables belong to `infer`) using `solve`, and
+/// unifies its variables with the values the solver found for them (if any).
+/// Returns the solution, along with the region constraints that come with
+/// those values if it is unique.
+pub(super) fn refine<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    infer: &mut InferenceTable<I>,
+    solve: &mut SolveFn<'_, I>,
+    goal: &InEnvironment<Goal<I>>,
+) -> Option<(Solution<I>, Constraints<I>)> {
+    let interner = db.interner();
+    let canonicalized = infer.canonicalize(interner, goal.clone());
+    let u_canonicalized = InferenceTable::u_canonicalize(interner, &canonicalized.quantified);
+    let solution = solve(&u_canonicalized.quantified)?;
+
+    let constrained = match &solution {
+        Solution::Unique(constrained) => Some(constrained.clone()),
+        Solution::Ambig(Guidance::Definite(subst)) => Some(Canonical {
+            binders: subst.binders.clone(),
+            value: ConstrainedSubst {
+                subst: subst.value.clone(),
+                constraints: Constraints::empty(interner),
+            },
+        }),
+        Solution::Ambig(_) => None,
+    };
+    let mut constraints = Constraints::empty(interner);
+    if let Some(constrained) = constrained {
+        let constrained = u_canonicalized
+            .universes
+            .map_from_canonical(interner, &constrained);
+        let constrained = infer.instantiate_canonical(interner, constrained);
+        for (var, value) in canonicalized
+            .free_vars
+            .iter()
+            .zip(constrained.subst.iter(interner))
+        {
+            // The solution is for this very goal, so this can't fail.
+            let _ = infer.relate(
+                interner,
+                db.unification_database(),
+                &goal.environment,
+                Variance::Invariant,
+                &var.to_generic_arg(interner),
+                value,
+            );
+        }
+        constraints = constrained.constraints;
+    }
+
+    Some((solution, constraints))
+}
+
+type Subgoal<I> = InEnvironment<Goal<I>>;
+
+/// A proof tree whose goals still refer to inference variables.
+struct Node<I: Interner> {
+    goal: InEnvironment<Goal<I>>,
+    step: ProofStep<I>,
+    children: Vec<Node<I>>,
+}
+
+struct ProofBuilder<'s, I: Interner> {
+    db: &'s dyn RustIrDatabase<I>,
+    solve: &'s mut SolveFn<'s, I>,
+    infer: InferenceTable<I>,
+    /// The goals currently being explained, used to detect cycles.
+    stack: Vec<UCanonical<InEnvironment<Goal<I>>>>,
+}
+
+impl<I: Interner> ProofBuilder<'_, I> {
+    /// Builds the proof tree for `goal`, which the solver found a solution
+    /// for, either on its own or as part of the goal above it.
+    fn build(&mut self, goal: InEnvironment<Goal<I>>) -> Result<Node<I>, ProofError<I>> {
+        let interner = self.db.interner();
+
+        // Give the variables in the goal the values the solver finds for
+        // them, so that the clauses we pick below are the ones that apply to
+        // the solution.
+        let solution = self.refine(&goal);
+
+        // Quantifiers without any variables are how where clauses are
+        // represented, and it's not worth a node of its own.
+        if let GoalData::Quantified(_, subgoal) = goal.goal.data(interner) {
+            if subgoal.binders.is_empty(interner) {
+                let subgoal = subgoal
+                    .clone()
+                    .substitute(interner, &Substitution::empty(interner));
+                return self.build(InEnvironment::new(&goal.environment, subgoal));
+            }
+        }
+
+        let canonical = self.u_canonicalize(&goal);
+        if self.stack.contains(&canonical) {
+            return Ok(Node::leaf(goal, ProofStep::Cycle));
+        }
+        let solution = match solution {
+            Some(solution) => solution,
+            None => return Err(self.error(&goal)),
+        };
+        if self.stack.len() >= MAX_DEPTH {
+            return Ok(Node::leaf(goal, ProofStep::Unexplained));
+        }
+
+        self.stack.push(canonical);
+        let environment = &goal.environment;
+        let (step, children) = match goal.goal.data(interner) {
+            GoalData::Quantified(QuantifierKind::ForAll, subgoal) => {
+                let subgoal = self
+                    .infer
+                    .instantiate_binders_universally(interner, subgoal.clone());
+                let child = self.build(InEnvironment::new(environment, subgoal))?;
+                (ProofStep::Simplified, vec![child])
+            }
+            GoalData::Quantified(QuantifierKind::Exists, subgoal) => {
+                let subgoal = self
+                    .infer
+                    .instantiate_binders_existentially(interner, subgoal.clone());
+                let child = self.build(InEnvironment::new(environment, subgoal))?;
+                (ProofStep::Simplified, vec![child])
+            }
+            GoalData::Implies(clauses, subgoal) => {
+                let environment = environment
+                    .clone()
+                    .add_clauses(interner, clauses.iter(interner).cloned());
+                let child = self.build(InEnvironment::new(&environment, subgoal.clone()))?;
+                (ProofStep::Simplified, vec![child])
+            }
+            GoalData::All(goals) => {
+                let children = goals
+                    .iter(interner)
+                    .map(|subgoal| self.build(InEnvironment::new(environment, subgoal.clone())))
+                    .collect::<Result<_, _>>()?;
+                (ProofStep::Simplified, children)
+            }
+            GoalData::Not(_) => (ProofStep::Negated, vec![]),
+            GoalData::EqGoal(_) | GoalData::SubtypeGoal(_) => (ProofStep::Unified, vec![]),
+            GoalData::DomainGoal(domain_goal) => match self.find_clause(environment, domain_goal) {
+                Some((clause, source, subgoals)) => {
+                    let children = subgoals
+                        .into_iter()
+                        .map(|subgoal| self.build(subgoal))
+                        .collect::<Result<_, _>>()?;
+                    (ProofStep::Clause { clause, source }, children)
+                }
+                // Only an ambiguous solution may lack a clause explaining it.
+                None if solution.is_unique() => return Err(self.error(&goal)),
+                None => (ProofStep::Unexplained, vec![]),
+            },
+            GoalData::CannotProve => (ProofStep::Unexplained, vec![]),
+        };
+        self.stack.pop();
+
+        Ok(Node {
+            goal,
+            step,
+            children,
+        })
+    }
+
+    /// Explains why `goal`, which the solver found no solution for, has no
+    /// solution.
+    ///
+    /// Unlike proof trees, failure trees are resolved as they are built, as
+    /// the variable bindings made while trying a clause are rolled back
+    /// afterwards.
+    fn explain_failure(&mut self, goal: InEnvironment<Goal<I>>) -> FailureTree<I> {
+        let interner = self.db.interner();
+
+        if let GoalData::Quantified(_, subgoal) = goal.goal.data(interner) {
+            if subgoal.binders.is_empty(interner) {
+                let subgoal = subgoal
+                    .clone()
+                    .substitute(interner, &Substitution::empty(interner));
+                return self.explain_failure(InEnvironment::new(&goal.environment, subgoal));
+            }
+        }
+
+        let canonical = self.u_canonicalize(&goal);
+        if self.stack.contains(&canonical) {
+            return self.failure(&goal, FailureReason::Cycle);
+        }
+        if self.stack.len() >= MAX_DEPTH {
+            return self.failure(&goal, FailureReason::Unexplained);
+        }
+
+        self.stack.push(canonical);
+        let environment = &goal.environment;
+        let reason = match goal.goal.data(interner) {
+            GoalData::Quantified(QuantifierKind::ForAll, subgoal) => {
+                let subgoal = self
+                    .infer
+                    .instantiate_binders_universally(interner, subgoal.clone());
+                let child = self.explain_failure(InEnvironment::new(environment, subgoal));
+                FailureReason::Subgoals(vec![child])
+            }
+            GoalData::Quantified(QuantifierKind::Exists, subgoal) => {
+                let subgoal = self
+                    .infer
+                    .instantiate_binders_existentially(interner, subgoal.clone());
+                let child = self.explain_failure(InEnvironment::new(environment, subgoal));
+                FailureReason::Subgoals(vec![child])
+            }
+            GoalData::Implies(clauses, subgoal) => {
+                let environment = environment
+                    .clone()
+                    .add_clauses(interner, clauses.iter(interner).cloned());
+                let child = self.explain_failure(InEnvironment::new(&environment, subgoal.clone()));
+                FailureReason::Subgoals(vec![child])
+            }
+            GoalData::All(goals) => {
+                let subgoals: Vec<_> = goals
+                    .iter(interner)
+                    .map(|subgoal| InEnvironment::new(environment, subgoal.clone()))
+                    .collect();
+                match self.explain_failed_subgoals(subgoals) {
+                    children if children.is_empty() => FailureReason::Unexplained,
+                    children => FailureReason::Subgoals(children),
+                }
+            }
+            GoalData::Not(_) => FailureReason::Negated,
+            GoalData::EqGoal(_) | GoalData::SubtypeGoal(_) => FailureReason::NotUnifiable,
+            GoalData::DomainGoal(domain_goal) => self.reject_clauses(environment, domain_goal),
+            GoalData::CannotProve => FailureReason::Unexplained,
+        };
+        self.stack.pop();
+
+        self.failure(&goal, reason)
+    }
+
+    /// Tries every clause which could prove `goal`, and explains why each of
+    /// them doesn't.
+    fn reject_clauses(
+        &mut self,
+        environment: &Environment<I>,
+        goal: &DomainGoal<I>,
+    ) -> FailureReason<I> {
+        let interner = self.db.interner();
+        let canonical = self.u_canonicalize(&InEnvironment::new(environment, goal.clone()));
+        let clauses = match program_clauses_for_goal(self.db, &canonical) {
+            Ok(clauses) => clauses,
+            Err(Floundered) => return FailureReason::Unexplained,
+        };
+        if clauses.is_empty() {
+            return FailureReason::NoClauses;
+        }
+        let env_clauses = self.db.program_clauses_for_env(environment);
+
+        let rejected: Vec<_> = clauses
+            .into_iter()
+            .map(|clause| {
+                let snapshot = self.infer.snapshot();
+                let reason = self.reject_clause(environment, goal, &clause);
+                self.infer.rollback_to(snapshot);
+                let source = if env_clauses.iter(interner).any(|c| *c == clause) {
+                    ClauseSource::Environment
+                } else {
+                    ClauseSource::Program
+                };
+                RejectedClause {
+                    clause,
+                    source,
+                    reason,
+                }
+            })
+            .filter(|rejected| !is_unused_assumption(interner, rejected))
+            .collect();
+        if rejected.is_empty() {
+            FailureReason::NoClauses
+        } else {
+            FailureReason::Rejected(rejected)
+        }
+    }
+
+    fn reject_clause(
+        &mut self,
+        environment: &Environment<I>,
+        goal: &DomainGoal<I>,
+        clause: &ProgramClause<I>,
+    ) -> RejectionReason<I> {
+        let interner = self.db.interner();
+        let ProgramClauseData(implication) = clause.data(interner);
+        let ProgramClauseImplication {
+            consequence,
+            conditions,
+            ..
+        } = self
+            .infer
+            .instantiate_binders_existentially(interner, implication.clone());
+
+        let relation = match self.infer.relate(
+            interner,
+            self.db.unification_database(),
+            environment,
+            Variance::Invariant,
+            goal,
+            &consequence,
+        ) {
+            Ok(relation) => relation,
+            Err(NoSolution) => return RejectionReason::Mismatch,
+        };
+
+        let subgoals = conditions
+            .iter(interner)
+            .map(|condition| InEnvironment::new(environment, condition.clone()))
+            .chain(relation.goals)
+            .collect();
+        RejectionReason::Conditions(self.explain_failed_subgoals(subgoals))
+    }
+
+    /// Solves each of `subgoals` in turn, and explains the ones which have no
+    /// solution. These are only explained once all of the subgoals have been
+    /// solved, so that they show the values the other subgoals give to their
+    /// variables.
+    fn explain_failed_subgoals(&mut self, subgoals: Vec<Subgoal<I>>) -> Vec<FailureTree<I>> {
+        let failed: Vec<_> = subgoals
+            .into_iter()
+            .filter(|subgoal| self.refine(subgoal).is_none())
+            .collect();
+        failed
+            .into_iter()
+            .map(|subgoal| self.explain_failure(subgoal))
+            .collect()
+    }
+
+    fn error(&mut self, goal: &InEnvironment<Goal<I>>) -> ProofError<I> {
+        let interner = self.db.interner();
+        ProofError {
+            goal: self.infer.canonicalize(interner, goal.clone()).quantified,
+        }
+    }
+
+    fn failure(
+        &mut self,
+        goal: &InEnvironment<Goal<I>>,
+        reason: FailureReason<I>,
+    ) -> FailureTree<I> {
+        let interner = self.db.interner();
+        FailureTree {
+            goal: self.infer.canonicalize(interner, goal.clone()).quantified,
+            reason,
+        }
+    }
+
+    /// Finds a clause which proves `goal`, and unifies `goal` with its
+    /// consequence. Returns the clause along with the goals that must hold
+    /// for it to apply.
+    ///
+    /// We prefer a clause whose conditions all have a unique solution, but
+    /// settle for one whose conditions are merely ambiguous.
+    fn find_clause(
+        &mut self,
+        environment: &Environment<I>,
+        goal: &DomainGoal<I>,
+    ) -> Option<(ProgramClause<I>, ClauseSource, Vec<Subgoal<I>>)> {
+        let interner = self.db.interner();
+        let canonical = self.u_canonicalize(&InEnvironment::new(environment, goal.clone()));
+        let clauses = program_clauses_for_goal(self.db, &canonical).ok()?;
+        let env_clauses = self.db.program_clauses_for_env(environment);
+
+        for require_unique in [true, false] {
+            for clause in &clauses {
+                let snapshot = self.infer.snapshot();
+                match self.try_clause(environment, goal, clause, require_unique) {
+                    Some(subgoals) => {
+                        self.infer.commit(snapshot);
+                        let source = if env_clauses.iter(interner).any(|c| c == clause) {
+                            ClauseSource::Environment
+                        } else {
+                            ClauseSource::Program
+                        };
+                        return Some((clause.clone(), source, subgoals));
+                    }
+                    None => self.infer.rollback_to(snapshot),
+                }
+            }
+        }
+
+        None
+    }
+
+    fn try_clause(
+        &mut self,
+        environment: &Environment<I>,
+        goal: &DomainGoal<I>,
+        clause: &ProgramClause<I>,
+        require_unique: bool,
+    ) -> Option<Vec<InEnvironment<Goal<I>>>> {
+        let interner = self.db.interner();
+        let ProgramClauseData(implication) = clause.data(interner);
+        let ProgramClauseImplication {
+            consequence,
+            conditions,
+            ..
+        } = self
+            .infer
+            .instantiate_binders_existentially(interner, implication.clone());
+
+        let relation = self
+            .infer
+            .relate(
+                interner,
+                self.db.unification_database(),
+                environment,
+                Variance::Invariant,
+                goal,
+                &consequence,
+            )
+            .ok()?;
+
+        let subgoals: Vec<_> = conditions
+            .iter(interner)
+            .map(|condition| InEnvironment::new(environment, condition.clone()))
+            .chain(relation.goals)
+            .collect();
+        for subgoal in &subgoals {
+            match self.refine(subgoal) {
+                Some(solution) if solution.is_unique() || !require_unique => {}
+                _ => return None,
+            }
+        }
+
+        debug!(?clause, "proof step");
+        Some(subgoals)
+    }
+
+    /// Solves `goal`, and unifies its variables with the values the solver
+    /// found for them (if any).
+    fn refine(&mut self, goal: &InEnvironment<Goal<I>>) -> Option<Solution<I>> {
+        refine(self.db, &mut self.infer, self.solve, goal).map(|(solution, _)| solution)
+    }
+
+    fn u_canonicalize<T>(&mut self, value: &T) -> UCanonical<T>
+    where
+        T: Clone + HasInterner<Interner = I> + TypeFoldable<I> + TypeVisitable<I>,
+    {
+        let interner = self.db.interner();
+        let canonicalized = self.infer.canonicalize(interner, value.clone());
+        InferenceTable::u_canonicalize(interner, &canonicalized.quantified).quantified
+    }
+
+    /// Applies the final values of all inference variables to the goals in
+    /// the tree.
+    fn resolve(&mut self, node: Node<I>) -> ProofTree<I> {
+        let interner = self.db.interner();
+        ProofTree {
+            goal: self.infer.canonicalize(interner, node.goal).quantified,
+            step: node.step,
+            children: node
+                .children
+                .into_iter()
+                .map(|child| self.resolve(child))
+                .collect(),
+        }
+    }
+}
+
+/// Every trait has a clause proving `T: Trait` if that is assumed (i.e.
+/// `FromEnv(T: Trait)` holds), which would show up under every trait goal.
+/// We leave it out when nothing like it is assumed.
+fn is_unused_assumption<I: Interner>(interner: I, rejected: &RejectedClause<I>) -> bool {
+    let conditions = match &rejected.reason {
+        RejectionReason::Conditions(conditions) if !conditions.is_empty() => conditions,
+        _ => return false,
+    };
+    conditions.iter().all(|condition| {
+        let is_from_env = matches!(
+            condition.goal.value.goal.data(interner),
+            GoalData::DomainGoal(DomainGoal::FromEnv(_))
+        );
+        is_from_env && condition.reason == FailureReason::NoClauses
+    })
+}
+
+impl<I: Interner> Node<I> {
+    fn leaf(goal: InEnvironment<Goal<I>>, step: ProofStep<I>) -> Self {
+        Node {
+            goal,
+            step,
+            children: vec![],
+        }
+    }
+}
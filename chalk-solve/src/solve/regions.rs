@@ -0,0 +1,455 @@
+//! Simplifying the region constraints of a solution, before it is returned to
+//! the caller.
+//!
+//! The solvers collect the `LifetimeOutlives` and `TypeOutlives` constraints
+//! that the goal requires as they go, without looking at them. This pass:
+//!
+//! - decomposes `T: 'a` into constraints on the lifetimes (and the unknown
+//!   types) within `T`, using the structure of `T` and, for ADTs, their
+//!   where clauses;
+//! - adds the constraints implied by transitivity (`'a: 'b` and `'b: 'c`
+//!   imply `'a: 'c`);
+//! - drops duplicates, and the constraints that always hold, which are
+//!   `'a: 'a`, `'static: 'a` and the ones implied by the environment;
+//! - reports the constraints which can't hold as `NoSolution`. Those are the
+//!   ones between a placeholder created while solving the goal (for example
+//!   for `for<'a> fn(&'a u8)`), which could be any lifetime, and another
+//!   placeholder or `'static` (what rustc calls the "leak check"). The
+//!   placeholders of the goal itself belong to the caller, which may know
+//!   more about them, so their constraints are returned as they are.
+
+use crate::solve::Solution;
+use crate::RustIrDatabase;
+use chalk_ir::fold::Shift;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use rustc_hash::FxHashSet;
+use tracing::debug;
+
+/// Simplifies the constraints of a `Unique` solution to `goal` (see the
+/// module docs). Returns `None` if they can't hold.
+pub fn simplify_solution<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    goal: &UCanonical<InEnvironment<Goal<I>>>,
+    solution: Solution<I>,
+) -> Option<Solution<I>> {
+    match solution {
+        Solution::Unique(subst) => simplify_constrained_subst(db, goal, subst)
+            .ok()
+            .map(Solution::Unique),
+        Solution::Ambig(guidance) => Some(Solution::Ambig(guidance)),
+    }
+}
+
+/// Simplifies the constraints of an answer to `goal` (see the module docs).
+pub fn simplify_constrained_subst<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    goal: &UCanonical<InEnvironment<Goal<I>>>,
+    subst: Canonical<ConstrainedSubst<I>>,
+) -> Fallible<Canonical<ConstrainedSubst<I>>> {
+    let Canonical {
+        binders,
+        value: ConstrainedSubst { subst, constraints },
+    } = subst;
+    let constraints = simplify_constraints(db, goal.universes, &constraints)?;
+    Ok(Canonical {
+        binders,
+        value: ConstrainedSubst { subst, constraints },
+    })
+}
+
+/// Simplifies `constraints` (see the module docs). The placeholders in the
+/// universes from `universes` onwards are the ones created while solving
+/// the goal.
+pub fn simplify_constraints<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    universes: usize,
+    constraints: &Constraints<I>,
+) -> Fallible<Constraints<I>> {
+    let interner = db.interner();
+
+    // Constraints from different environments can't be combined, so we
+    // simplify the constraints of each environment separately.
+    let mut groups: Vec<(Environment<I>, Vec<Constraint<I>>)> = vec![];
+    for InEnvironment { environment, goal } in constraints.iter(interner) {
+        let index = match groups.iter().position(|(env, _)| env == environment) {
+            Some(index) => index,
+            None => {
+                groups.push((environment.clone(), vec![]));
+                groups.len() - 1
+            }
+        };
+        let group = &mut groups[index].1;
+        match goal {
+            Constraint::LifetimeOutlives(..) => group.push(goal.clone()),
+            Constraint::TypeOutlives(ty, lifetime) => {
+                decompose_type_outlives(db, ty, lifetime, group)
+            }
+        }
+    }
+
+    let mut simplified = vec![];
+    for (environment, constraints) in groups {
+        let simplified_constraints =
+            RegionGraph::new(interner, universes, &environment).simplify(constraints)?;
+        simplified.extend(
+            simplified_constraints
+                .into_iter()
+                .map(|constraint| InEnvironment::new(&environment, constraint)),
+        );
+    }
+    Ok(Constraints::from_iter(interner, simplified))
+}
+
+/// The constraints that a program clause used to prove a goal in
+/// `environment` comes with. The clause can't name the goal's environment,
+/// so its constraints are stated without one: they have to hold in
+/// `environment`.
+pub fn clause_constraints_in<'a, I: Interner>(
+    interner: I,
+    environment: &'a Environment<I>,
+    constraints: &'a Constraints<I>,
+) -> impl Iterator<Item = InEnvironment<Constraint<I>>> + 'a {
+    constraints
+        .iter(interner)
+        .map(move |constraint| InEnvironment::new(environment, constraint.goal.clone()))
+}
+
+/// Pushes constraints equivalent to `ty: 'lifetime` to `out`: `ty` outlives
+/// `'lifetime` if all the lifetimes and types within it do. (A type like
+/// `u32` has none, so it always does.) What we don't know the structure of
+/// (like a type variable or an alias) is left as a `TypeOutlives`.
+fn decompose_type_outlives<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    ty: &Ty<I>,
+    lifetime: &Lifetime<I>,
+    out: &mut Vec<Constraint<I>>,
+) {
+    Decomposer { db, lifetime, out }.ty(ty, DebruijnIndex::INNERMOST);
+}
+
+/// Decomposes `T: 'lifetime` (see `decompose_type_outlives`). The types and
+/// lifetimes it visits are under `outer_binder` binders (those of function
+/// pointers) within `T`.
+struct Decomposer<'a, I: Interner> {
+    db: &'a dyn RustIrDatabase<I>,
+    lifetime: &'a Lifetime<I>,
+    out: &'a mut Vec<Constraint<I>>,
+}
+
+impl<I: Interner> Decomposer<'_, I> {
+    fn ty(&mut self, ty: &Ty<I>, outer_binder: DebruijnIndex) {
+        let interner = self.db.interner();
+        match ty.kind(interner) {
+            TyKind::Adt(id, substitution) => {
+                // A well-formed ADT satisfies its where clauses, so if it
+                // requires `T: 'b`, then `'b: 'lifetime` implies
+                // `T: 'lifetime`.
+                let where_clauses = self
+                    .db
+                    .adt_datum(*id)
+                    .binders
+                    .clone()
+                    .substitute(interner, substitution)
+                    .where_clauses;
+                for arg in substitution.iter(interner) {
+                    let bound = arg.ty(interner).and_then(|arg_ty| {
+                        where_clauses.iter().find_map(|where_clause| {
+                            if !where_clause.binders.is_empty(interner) {
+                                return None;
+                            }
+                            match where_clause
+                                .skip_binders()
+                                .clone()
+                                .shifted_out(interner)
+                                .ok()?
+                            {
+                                WhereClause::TypeOutlives(TypeOutlives {
+                                    ty,
+                                    lifetime: bound,
+                                }) if ty == *arg_ty => Some(bound),
+                                _ => None,
+                            }
+                        })
+                    });
+                    match bound {
+                        Some(bound) => self.lifetime(&bound, outer_binder),
+                        None => self.arg(arg, outer_binder),
+                    }
+                }
+            }
+            TyKind::Tuple(_, substitution)
+            | TyKind::FnDef(_, substitution)
+            | TyKind::Closure(_, substitution)
+            | TyKind::Coroutine(_, substitution) => self.args(substitution, outer_binder),
+            TyKind::Function(fn_ptr) => {
+                self.args(&fn_ptr.substitution.0, outer_binder.shifted_in())
+            }
+            TyKind::Ref(_, region, ty) => {
+                self.lifetime(region, outer_binder);
+                self.ty(ty, outer_binder);
+            }
+            TyKind::Raw(_, ty) | TyKind::Slice(ty) | TyKind::Array(ty, _) => {
+                self.ty(ty, outer_binder)
+            }
+            // An object type outlives `'lifetime` if its own lifetime and
+            // all the parameters of its bounds do (RFC 1214's
+            // OutlivesObject). The bounds are under the binder of the
+            // object's `Self` type and their own binders.
+            TyKind::Dyn(dyn_ty) if Self::decomposable(interner, dyn_ty) => {
+                self.lifetime(&dyn_ty.lifetime, outer_binder);
+                let outer_binder = outer_binder.shifted_in().shifted_in();
+                for bound in dyn_ty.bounds.skip_binders().iter(interner) {
+                    match bound.skip_binders() {
+                        WhereClause::Implemented(trait_ref) => {
+                            self.args(&trait_ref.substitution, outer_binder)
+                        }
+                        WhereClause::AliasEq(AliasEq { alias, ty }) => {
+                            match alias {
+                                AliasTy::Projection(ProjectionTy { substitution, .. })
+                                | AliasTy::Opaque(OpaqueTy { substitution, .. }) => {
+                                    self.args(substitution, outer_binder)
+                                }
+                            }
+                            self.ty(ty, outer_binder);
+                        }
+                        WhereClause::LifetimeOutlives(LifetimeOutlives { a, b }) => {
+                            self.lifetime(a, outer_binder);
+                            self.lifetime(b, outer_binder);
+                        }
+                        WhereClause::TypeOutlives(TypeOutlives { ty, lifetime }) => {
+                            self.ty(ty, outer_binder);
+                            self.lifetime(lifetime, outer_binder);
+                        }
+                    }
+                }
+            }
+            // The `Self` type of an object's bounds is the object itself.
+            TyKind::BoundVar(bound_var) if bound_var.shifted_out_to(outer_binder).is_none() => {}
+            TyKind::Scalar(_) | TyKind::Str | TyKind::Never | TyKind::Foreign(_) => {}
+            _ => match ty.clone().shifted_out_to(interner, outer_binder) {
+                Ok(ty) => self
+                    .out
+                    .push(Constraint::TypeOutlives(ty, self.lifetime.clone())),
+                // We can't state `T: 'lifetime` for a type that refers to the
+                // lifetimes of a function pointer, so we ask for all of its
+                // parameters to outlive `'lifetime` instead, which implies it.
+                Err(_) => match ty.kind(interner) {
+                    TyKind::Alias(AliasTy::Projection(ProjectionTy { substitution, .. }))
+                    | TyKind::Alias(AliasTy::Opaque(OpaqueTy { substitution, .. }))
+                    | TyKind::AssociatedType(_, substitution)
+                    | TyKind::OpaqueType(_, substitution) => self.args(substitution, outer_binder),
+                    _ => panic!("unexpected bound type in `{:?}: {:?}`", ty, self.lifetime),
+                },
+            },
+        }
+    }
+
+    /// Whether the bounds of an object type bind only lifetimes, so that
+    /// `decompose_type_outlives` can walk through them. A bound like
+    /// `for<T> Foo<T>` would have to hold for any `T`, so we keep the
+    /// constraint on the whole object type instead.
+    fn decomposable(interner: I, dyn_ty: &DynTy<I>) -> bool {
+        dyn_ty.bounds.skip_binders().iter(interner).all(|bound| {
+            bound
+                .binders
+                .iter(interner)
+                .all(|kind| matches!(kind, VariableKind::Lifetime))
+        })
+    }
+
+    fn args(&mut self, substitution: &Substitution<I>, outer_binder: DebruijnIndex) {
+        for arg in substitution.iter(self.db.interner()) {
+            self.arg(arg, outer_binder);
+        }
+    }
+
+    fn arg(&mut self, arg: &GenericArg<I>, outer_binder: DebruijnIndex) {
+        match arg.data(self.db.interner()) {
+            GenericArgData::Ty(ty) => self.ty(ty, outer_binder),
+            GenericArgData::Lifetime(region) => self.lifetime(region, outer_binder),
+            GenericArgData::Const(_) => {}
+        }
+    }
+
+    fn lifetime(&mut self, region: &Lifetime<I>, outer_binder: DebruijnIndex) {
+        // The lifetimes bound by a function pointer can be any lifetime, so
+        // they don't need to outlive anything.
+        if let Ok(region) = region
+            .clone()
+            .shifted_out_to(self.db.interner(), outer_binder)
+        {
+            self.out
+                .push(Constraint::LifetimeOutlives(region, self.lifetime.clone()));
+        }
+    }
+}
+
+/// The outlives relation between the lifetimes of the constraints of one
+/// environment.
+struct RegionGraph<I: Interner> {
+    interner: I,
+    universes: usize,
+    /// The lifetimes, numbered by their position.
+    lifetimes: Vec<Lifetime<I>>,
+    /// `implied[a]` holds the lifetimes which the environment implies that
+    /// `a` outlives.
+    implied: Vec<FxHashSet<usize>>,
+    /// The `TypeOutlives` facts of the environment.
+    type_outlives: Vec<(Ty<I>, usize)>,
+}
+
+impl<I: Interner> RegionGraph<I> {
+    fn new(interner: I, universes: usize, environment: &Environment<I>) -> Self {
+        let mut graph = RegionGraph {
+            interner,
+            universes,
+            lifetimes: vec![],
+            implied: vec![],
+            type_outlives: vec![],
+        };
+        let mut edges = vec![];
+        for clause in environment.clauses.iter(interner) {
+            let implication = &clause.data(interner).0;
+            if !implication.binders.is_empty(interner) {
+                continue;
+            }
+            let implication = implication.skip_binders();
+            if !implication.conditions.is_empty(interner) {
+                continue;
+            }
+            match &implication.consequence {
+                DomainGoal::Holds(WhereClause::LifetimeOutlives(LifetimeOutlives { a, b })) => {
+                    edges.push((graph.index(a), graph.index(b)));
+                }
+                DomainGoal::Holds(WhereClause::TypeOutlives(TypeOutlives { ty, lifetime })) => {
+                    let lifetime = graph.index(lifetime);
+                    graph.type_outlives.push((ty.clone(), lifetime));
+                }
+                _ => {}
+            }
+        }
+        graph.implied = graph.closure(&edges);
+        graph
+    }
+
+    /// The number of `lifetime`, adding it if it is new.
+    fn index(&mut self, lifetime: &Lifetime<I>) -> usize {
+        match self.lifetimes.iter().position(|l| l == lifetime) {
+            Some(index) => index,
+            None => {
+                self.lifetimes.push(lifetime.clone());
+                self.implied.push(FxHashSet::default());
+                self.lifetimes.len() - 1
+            }
+        }
+    }
+
+    /// For each lifetime, the lifetimes it outlives according to `edges` and
+    /// transitivity.
+    fn closure(&self, edges: &[(usize, usize)]) -> Vec<FxHashSet<usize>> {
+        let mut successors = vec![vec![]; self.lifetimes.len()];
+        for &(a, b) in edges {
+            successors[a].push(b);
+        }
+        (0..self.lifetimes.len())
+            .map(|start| {
+                let mut reached = FxHashSet::default();
+                let mut stack = successors[start].clone();
+                while let Some(next) = stack.pop() {
+                    if reached.insert(next) {
+                        stack.extend(successors[next].iter().copied());
+                    }
+                }
+                reached
+            })
+            .collect()
+    }
+
+    fn always_holds(&self, a: usize, b: usize) -> bool {
+        let is_static =
+            |index: usize| self.lifetimes[index].data(self.interner) == &LifetimeData::Static;
+        a == b
+            || is_static(a)
+            || self.implied[a].contains(&b)
+            || self.implied[a].iter().any(|&c| is_static(c))
+    }
+
+    /// Whether `a: b` can't hold (see the module docs).
+    fn contradicts(&self, a: usize, b: usize) -> bool {
+        let placeholder = |index: usize| match self.lifetimes[index].data(self.interner) {
+            LifetimeData::Placeholder(placeholder) => Some(placeholder.ui.counter),
+            _ => None,
+        };
+        let is_static =
+            |index: usize| self.lifetimes[index].data(self.interner) == &LifetimeData::Static;
+        let is_named = |index: usize| placeholder(index).is_some() || is_static(index);
+        let is_inner = |index: usize| placeholder(index).is_some_and(|ui| ui >= self.universes);
+        (is_inner(a) && is_named(b)) || (is_inner(b) && is_named(a))
+    }
+
+    fn simplify(mut self, constraints: Vec<Constraint<I>>) -> Fallible<Vec<Constraint<I>>> {
+        let mut edges = vec![];
+        let mut type_outlives = vec![];
+        for constraint in constraints {
+            match constraint {
+                Constraint::LifetimeOutlives(a, b) => {
+                    let edge = (self.index(&a), self.index(&b));
+                    if !edges.contains(&edge) {
+                        edges.push(edge);
+                    }
+                }
+                Constraint::TypeOutlives(ty, lifetime) => {
+                    let constraint = (ty, self.index(&lifetime));
+                    if !type_outlives.contains(&constraint) {
+                        type_outlives.push(constraint);
+                    }
+                }
+            }
+        }
+
+        // The constraints we were given come first, followed by the ones
+        // implied by transitivity.
+        let closure = self.closure(&edges);
+        for (a, reached) in closure.iter().enumerate() {
+            let mut reached: Vec<_> = reached.iter().copied().collect();
+            reached.sort_unstable();
+            for b in reached {
+                if !edges.contains(&(a, b)) {
+                    edges.push((a, b));
+                }
+            }
+        }
+
+        let mut simplified = vec![];
+        for (a, b) in edges {
+            if self.always_holds(a, b) {
+                continue;
+            }
+            if self.contradicts(a, b) {
+                debug!(
+                    a = ?self.lifetimes[a],
+                    b = ?self.lifetimes[b],
+                    "region constraint can't hold"
+                );
+                return Err(NoSolution);
+            }
+            simplified.push(Constraint::LifetimeOutlives(
+                self.lifetimes[a].clone(),
+                self.lifetimes[b].clone(),
+            ));
+        }
+        for (ty, lifetime) in type_outlives {
+            let implied = self.type_outlives.iter().any(|(fact_ty, bound)| {
+                *fact_ty == ty && (*bound == lifetime || self.always_holds(*bound, lifetime))
+            });
+            if !implied {
+                simplified.push(Constraint::TypeOutlives(
+                    ty,
+                    self.lifetimes[lifetime].clone(),
+                ));
+            }
+        }
+        Ok(simplified)
+    }
+}
@@ -0,0 +1,110 @@
+//! Counters describing how much work a solver did, for profiling.
+
+use crate::RustIrDatabase;
+use chalk_ir::interner::Interner;
+use chalk_ir::{DomainGoal, WhereClause};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Statistics about the work a solver did, collected once
+/// [`Solver::collect_stats`] has been called.
+///
+/// Not every counter applies to every solver: only the SLG solver processes
+/// strands, and only the recursive solver iterates towards fixed points.
+/// "Tables" are the SLG solver's tables and the recursive solver's search
+/// graph nodes; cache hits and misses count the lookups of completed goals
+/// in the SLG solver's tables and the recursive solver's cache.
+///
+/// [`Solver::collect_stats`]: super::Solver::collect_stats
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolverStats {
+    /// Root goals solved.
+    pub goals_solved: usize,
+    /// Tables created for (sub)goals.
+    pub tables_created: usize,
+    /// Strands picked to work on.
+    pub strands_processed: usize,
+    /// Answers (or, for the recursive solver, results) produced for tables.
+    pub answers_produced: usize,
+    /// Lookups of goals that had already been solved.
+    pub cache_hits: usize,
+    /// Lookups of goals that had to be solved.
+    pub cache_misses: usize,
+    /// Iterations of the recursive solver's fixed-point loop.
+    pub fixed_point_iterations: usize,
+    /// Program clauses generated, by the kind of goal they were generated
+    /// for. Trait goals are split up by trait, as in `Implemented(Clone)`.
+    pub clauses_generated: BTreeMap<String, usize>,
+    /// Time spent solving root goals.
+    pub time_spent: Duration,
+}
+
+impl SolverStats {
+    /// Records that `count` program clauses were generated for `goal`.
+    pub fn record_clauses<I: Interner>(
+        &mut self,
+        db: &dyn RustIrDatabase<I>,
+        goal: &DomainGoal<I>,
+        count: usize,
+    ) {
+        *self
+            .clauses_generated
+            .entry(goal_kind(db, goal))
+            .or_default() += count;
+    }
+
+    /// The total number of program clauses generated.
+    pub fn total_clauses_generated(&self) -> usize {
+        self.clauses_generated.values().sum()
+    }
+}
+
+fn goal_kind<I: Interner>(db: &dyn RustIrDatabase<I>, goal: &DomainGoal<I>) -> String {
+    let kind = match goal {
+        DomainGoal::Holds(WhereClause::Implemented(trait_ref)) => {
+            return format!("Implemented({})", db.trait_name(trait_ref.trait_id));
+        }
+        DomainGoal::Holds(WhereClause::AliasEq(_)) => "AliasEq",
+        DomainGoal::Holds(WhereClause::LifetimeOutlives(_)) => "LifetimeOutlives",
+        DomainGoal::Holds(WhereClause::TypeOutlives(_)) => "TypeOutlives",
+        DomainGoal::WellFormed(_) => "WellFormed",
+        DomainGoal::FromEnv(_) => "FromEnv",
+        DomainGoal::Normalize(_) => "Normalize",
+        DomainGoal::IsLocal(_) => "IsLocal",
+        DomainGoal::IsUpstream(_) => "IsUpstream",
+        DomainGoal::IsFullyVisible(_) => "IsFullyVisible",
+        DomainGoal::LocalImplAllowed(_) => "LocalImplAllowed",
+        DomainGoal::Compatible => "Compatible",
+        DomainGoal::DownstreamType(_) => "DownstreamType",
+        DomainGoal::Reveal => "Reveal",
+        DomainGoal::ObjectSafe(_) => "ObjectSafe",
+    };
+    kind.to_string()
+}
+
+impl fmt::Display for SolverStats {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(fmt, "goals solved: {}", self.goals_solved)?;
+        writeln!(fmt, "time spent: {:?}", self.time_spent)?;
+        writeln!(fmt, "tables created: {}", self.tables_created)?;
+        writeln!(fmt, "strands processed: {}", self.strands_processed)?;
+        writeln!(fmt, "answers produced: {}", self.answers_produced)?;
+        writeln!(fmt, "cache hits: {}", self.cache_hits)?;
+        writeln!(fmt, "cache misses: {}", self.cache_misses)?;
+        writeln!(
+            fmt,
+            "fixed-point iterations: {}",
+            self.fixed_point_iterations
+        )?;
+        write!(
+            fmt,
+            "program clauses generated: {}",
+            self.total_clauses_generated()
+        )?;
+        for (kind, count) in &self.clauses_generated {
+            write!(fmt, "\n    {}: {}", kind, count)?;
+        }
+        Ok(())
+    }
+}
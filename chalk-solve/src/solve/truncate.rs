@@ -1,6 +1,8 @@
 //!
 
 use crate::infer::InferenceTable;
+use chalk_derive::FallibleTypeFolder;
+use chalk_ir::fold::{Shift, TypeFoldable, TypeFolder, TypeSuperFoldable};
 use chalk_ir::interner::Interner;
 use chalk_ir::visit::{TypeSuperVisitable, TypeVisitable, TypeVisitor};
 use chalk_ir::*;
@@ -11,7 +13,12 @@ use std::ops::ControlFlow;
 /// refers to the act of modifying a goal or answer that has become
 /// too large in order to guarantee termination.
 ///
-/// Currently we don't perform truncation (but it might me readded later).
+/// A goal that is too large is replaced with a truncated, more general one
+/// (see [`truncate`]), whose answers are then filtered by unifying them with
+/// the original goal. An answer that is too large is truncated as well, and
+/// flagged as ambiguous, since it is only an approximation of the answers it
+/// stands for. Since there are only finitely many goals and answers of a
+/// given size (up to renaming variables), this guarantees termination.
 ///
 /// Citations:
 ///
@@ -31,6 +38,86 @@ pub fn needs_truncation<I: Interner>(
     visitor.max_size > max_size
 }
 
+/// Truncates `value` so that its types are no bigger than `max_size` (see
+/// `needs_truncation`), by replacing the subterms that make them too big
+/// with fresh inference variables. The result is more general than `value`:
+/// `value` is one of its instances.
+///
+/// Types which refer to variables bound within `value` are not truncated, as
+/// a variable can't stand for them, so the result may still need
+/// truncation. The caller shouldn't truncate anything which is assumed to
+/// hold (like the clauses of an environment), as making it more general
+/// would be unsound.
+pub fn truncate<I: Interner, T: TypeFoldable<I>>(
+    interner: I,
+    infer: &mut InferenceTable<I>,
+    max_size: usize,
+    value: T,
+) -> T {
+    let mut truncater = Truncater {
+        interner,
+        infer,
+        max_size,
+        size: 0,
+    };
+    value.fold_with(&mut truncater, DebruijnIndex::INNERMOST)
+}
+
+#[derive(FallibleTypeFolder)]
+struct Truncater<'infer, I: Interner> {
+    interner: I,
+    infer: &'infer mut InferenceTable<I>,
+    max_size: usize,
+    size: usize,
+}
+
+impl<'infer, I: Interner> TypeFolder<I> for Truncater<'infer, I> {
+    fn as_dyn(&mut self) -> &mut dyn TypeFolder<I> {
+        self
+    }
+
+    fn fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIndex) -> Ty<I> {
+        if let Some(normalized_ty) = self.infer.normalize_ty_shallow(self.interner, &ty) {
+            return self.fold_ty(normalized_ty, outer_binder);
+        }
+
+        let pre_size = self.size;
+        self.size += 1;
+        let result = ty.super_fold_with(self.as_dyn(), outer_binder);
+
+        // If the type was within `max_size` before this subterm, but isn't
+        // after it, replace the subterm with a variable (of size 1). The
+        // subterms within this one were folded first, so this replaces the
+        // largest subterm it can, and no more than it needs to. A subterm
+        // which refers to the variables bound within `value` can't be
+        // replaced, as the variable couldn't refer to them.
+        let result = if pre_size < self.max_size
+            && self.size > self.max_size
+            && result
+                .clone()
+                .shifted_out_to(self.interner, outer_binder)
+                .is_ok()
+        {
+            self.size = pre_size + 1;
+            let universe = self.infer.max_universe();
+            self.infer.new_variable(universe).to_ty(self.interner)
+        } else {
+            result
+        };
+
+        // When we get back to the first invocation, clear the counters.
+        // We process each outermost type independently.
+        if pre_size == 0 {
+            self.size = 0;
+        }
+        result
+    }
+
+    fn interner(&self) -> I {
+        self.interner
+    }
+}
+
 struct TySizeVisitor<'infer, I: Interner> {
     interner: I,
     infer: &'infer mut InferenceTable<I>,
@@ -131,4 +218,27 @@ mod tests {
         vec![&ty0, &ty1].visit_with(&mut visitor, DebruijnIndex::INNERMOST);
         assert!(visitor.max_size == 5);
     }
+
+    #[test]
+    fn truncate_type() {
+        use chalk_integration::interner::ChalkIr;
+        let interner = ChalkIr;
+        let mut table = InferenceTable::<chalk_integration::interner::ChalkIr>::new();
+        let _u1 = table.new_universe();
+
+        // Vec<Vec<Vec<Vec<T>>>>
+        let ty0 = ty!(apply (item 0)
+                      (apply (item 0)
+                       (apply (item 0)
+                        (apply (item 0)
+                         (placeholder 1)))));
+
+        // Vec<Vec<?0>>
+        let ty1 = ty!(apply (item 0)
+                      (apply (item 0)
+                       (infer 0)));
+
+        assert_eq!(truncate(interner, &mut table, 3, ty0.clone()), ty1);
+        assert_eq!(truncate(interner, &mut table, 5, ty0.clone()), ty0);
+    }
 }
@@ -0,0 +1,475 @@
+//! Provides a wrapper over `RustIrDatabase` which records the queries made
+//! while solving goals, so that a solver can tell later on whether the
+//! results it cached still hold for a changed program.
+use std::fmt::{self, Debug};
+use std::sync::{Arc, Mutex};
+
+use crate::rust_ir::*;
+use crate::RustIrDatabase;
+use chalk_ir::{interner::Interner, *};
+use rustc_hash::FxHashSet;
+
+/// Wraps another `RustIrDatabase` and records the queries made through it,
+/// along with their answers.
+///
+/// Reads are recorded per goal: the solver calls [`enter`] when it starts
+/// solving a goal and [`exit`] when it has its result, which returns the
+/// reads made in between. The reads of a goal include the reads of the
+/// subgoals it solved, and of the cached results it reused (see [`reuse`]).
+///
+/// Recording the reads costs a lock and a clone of the answer on every
+/// query, so it is only done when asked for (see [`new`]); otherwise, the
+/// queries are just forwarded and no reads are returned.
+///
+/// [`enter`]: TrackingRustIrDatabase::enter
+/// [`exit`]: TrackingRustIrDatabase::exit
+/// [`reuse`]: TrackingRustIrDatabase::reuse
+/// [`new`]: TrackingRustIrDatabase::new
+pub struct TrackingRustIrDatabase<'db, I: Interner> {
+    db: &'db dyn RustIrDatabase<I>,
+    /// The reads of the goals being solved, innermost last. The first frame
+    /// collects the reads made outside of any goal. `None` if the reads
+    /// aren't being recorded.
+    frames: Option<Mutex<Vec<Vec<ReadItem<I>>>>>,
+}
+
+/// The database reads made while solving a goal, in the order they were
+/// made. Cloning is cheap.
+#[derive(Clone)]
+pub struct Reads<I: Interner> {
+    items: Arc<[ReadItem<I>]>,
+}
+
+#[derive(Debug)]
+enum ReadItem<I: Interner> {
+    Read(DatabaseRead<I>),
+    /// The reads of a subgoal, or of a cached result that was reused.
+    Goal(Reads<I>),
+    /// A cached result was reused whose reads are not known, e.g. because
+    /// it was loaded from disk.
+    Untracked,
+}
+
+/// A query, along with the answer the database gave to it.
+#[derive(Debug)]
+enum DatabaseRead<I: Interner> {
+    CustomClauses(Vec<ProgramClause<I>>),
+    AssociatedTyData(AssocTypeId<I>, Arc<AssociatedTyDatum<I>>),
+    TraitDatum(TraitId<I>, Arc<TraitDatum<I>>),
+    AdtDatum(AdtId<I>, Arc<AdtDatum<I>>),
+    CoroutineDatum(CoroutineId<I>, Arc<CoroutineDatum<I>>),
+    CoroutineWitnessDatum(CoroutineId<I>, Arc<CoroutineWitnessDatum<I>>),
+    AdtRepr(AdtId<I>, Arc<AdtRepr<I>>),
+    AdtSizeAlign(AdtId<I>, Arc<AdtSizeAlign>),
+    FnDefDatum(FnDefId<I>, Arc<FnDefDatum<I>>),
+    ImplDatum(ImplId<I>, Arc<ImplDatum<I>>),
+    AssociatedTyValue(AssociatedTyValueId<I>, Arc<AssociatedTyValue<I>>),
+    OpaqueTyData(OpaqueTyId<I>, Arc<OpaqueTyDatum<I>>),
+    HiddenOpaqueType(OpaqueTyId<I>, Ty<I>),
+    ImplsForTrait(
+        TraitId<I>,
+        Vec<GenericArg<I>>,
+        CanonicalVarKinds<I>,
+        Vec<ImplId<I>>,
+    ),
+    LocalImplsToCoherenceCheck(TraitId<I>, Vec<ImplId<I>>),
+    ImplProvidedFor(TraitId<I>, TyKind<I>, bool),
+    WellKnownTraitId(WellKnownTrait, Option<TraitId<I>>),
+    ProgramClausesForEnv(Environment<I>, ProgramClauses<I>),
+    IsObjectSafe(TraitId<I>, bool),
+    ClosureKind(ClosureId<I>, Substitution<I>, ClosureKind),
+    ClosureInputsAndOutput(
+        ClosureId<I>,
+        Substitution<I>,
+        Binders<FnDefInputsAndOutputDatum<I>>,
+    ),
+    ClosureUpvars(ClosureId<I>, Substitution<I>, Binders<Ty<I>>),
+    ClosureFnSubstitution(ClosureId<I>, Substitution<I>, Substitution<I>),
+    DiscriminantType(Ty<I>, Ty<I>),
+    FnDefVariance(FnDefId<I>, Variances<I>),
+    AdtVariance(AdtId<I>, Variances<I>),
+}
+
+impl<'db, I: Interner> TrackingRustIrDatabase<'db, I> {
+    /// Wraps `db`, recording the reads made through it if `track_reads` is
+    /// true.
+    pub fn new(db: &'db dyn RustIrDatabase<I>, track_reads: bool) -> Self {
+        TrackingRustIrDatabase {
+            db,
+            frames: track_reads.then(|| Mutex::new(vec![vec![]])),
+        }
+    }
+
+    /// The database the queries are forwarded to.
+    pub fn db(&self) -> &'db dyn RustIrDatabase<I> {
+        self.db
+    }
+
+    /// Starts recording the reads for a new goal.
+    pub fn enter(&self) {
+        if let Some(frames) = &self.frames {
+            frames.lock().unwrap().push(vec![]);
+        }
+    }
+
+    /// Stops recording the reads for the innermost goal and returns them,
+    /// unless the reads aren't being recorded. They are also added to the
+    /// reads of the goal that encloses it.
+    pub fn exit(&self) -> Option<Reads<I>> {
+        let mut frames = self.frames.as_ref()?.lock().unwrap();
+        let items = frames.pop().expect("`exit` without `enter`");
+        let reads = Reads {
+            items: items.into(),
+        };
+        frames
+            .last_mut()
+            .expect("`exit` without `enter`")
+            .push(ReadItem::Goal(reads.clone()));
+        Some(reads)
+    }
+
+    /// Adds the reads of a cached result to the reads of the innermost goal,
+    /// since that goal depends on the cached result still holding. `None`
+    /// means that the reads of the cached result are not known, in which
+    /// case neither are those of the goal.
+    pub fn reuse(&self, reads: Option<&Reads<I>>) {
+        let item = match reads {
+            Some(reads) => ReadItem::Goal(reads.clone()),
+            None => ReadItem::Untracked,
+        };
+        self.push(item);
+    }
+
+    fn push(&self, item: ReadItem<I>) {
+        let Some(frames) = &self.frames else {
+            return;
+        };
+        frames
+            .lock()
+            .unwrap()
+            .last_mut()
+            .expect("frame stack is never empty")
+            .push(item);
+    }
+
+    /// Records the read made by `read`, if the reads are being recorded.
+    /// It is a closure so that the answer is only cloned in that case.
+    fn record(&self, read: impl FnOnce() -> DatabaseRead<I>) {
+        if self.frames.is_some() {
+            self.push(ReadItem::Read(read()));
+        }
+    }
+}
+
+impl<I: Interner> Debug for TrackingRustIrDatabase<'_, I> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("TrackingRustIrDatabase")
+            .field("db", &self.db)
+            .finish()
+    }
+}
+
+impl<I: Interner> Reads<I> {
+    /// Checks whether every read still gets the same answer from `db`, i.e.
+    /// whether a result computed with these reads still holds.
+    ///
+    /// The reads are checked in the order they were made, stopping at the
+    /// first one that changed. This way, ids that a read got from an earlier
+    /// one (like the impls of a trait) are only used if they still exist.
+    pub fn still_hold(&self, db: &dyn RustIrDatabase<I>) -> bool {
+        self.still_hold_in(db, &mut FxHashSet::default())
+    }
+
+    fn still_hold_in(&self, db: &dyn RustIrDatabase<I>, checked: &mut FxHashSet<usize>) -> bool {
+        // The same reads are often shared by several goals; each only has to
+        // be checked once.
+        if !checked.insert(Arc::as_ptr(&self.items) as *const () as usize) {
+            return true;
+        }
+        self.items.iter().all(|item| match item {
+            ReadItem::Read(read) => read.still_holds(db),
+            ReadItem::Goal(reads) => reads.still_hold_in(db, checked),
+            ReadItem::Untracked => false,
+        })
+    }
+}
+
+impl<I: Interner> Debug for Reads<I> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Reads")
+            .field("len", &self.items.len())
+            .finish()
+    }
+}
+
+impl<I: Interner> DatabaseRead<I> {
+    fn still_holds(&self, db: &dyn RustIrDatabase<I>) -> bool {
+        match self {
+            DatabaseRead::CustomClauses(clauses) => db.custom_clauses() == *clauses,
+            DatabaseRead::AssociatedTyData(id, datum) => db.associated_ty_data(*id) == *datum,
+            DatabaseRead::TraitDatum(id, datum) => db.trait_datum(*id) == *datum,
+            DatabaseRead::AdtDatum(id, datum) => db.adt_datum(*id) == *datum,
+            DatabaseRead::CoroutineDatum(id, datum) => db.coroutine_datum(*id) == *datum,
+            DatabaseRead::CoroutineWitnessDatum(id, datum) => {
+                db.coroutine_witness_datum(*id) == *datum
+            }
+            DatabaseRead::AdtRepr(id, repr) => db.adt_repr(*id) == *repr,
+            DatabaseRead::AdtSizeAlign(id, size_align) => db.adt_size_align(*id) == *size_align,
+            DatabaseRead::FnDefDatum(id, datum) => db.fn_def_datum(*id) == *datum,
+            DatabaseRead::ImplDatum(id, datum) => db.impl_datum(*id) == *datum,
+            DatabaseRead::AssociatedTyValue(id, value) => db.associated_ty_value(*id) == *value,
+            DatabaseRead::OpaqueTyData(id, datum) => db.opaque_ty_data(*id) == *datum,
+            DatabaseRead::HiddenOpaqueType(id, ty) => db.hidden_opaque_type(*id) == *ty,
+            DatabaseRead::ImplsForTrait(id, parameters, binders, impls) => {
+                db.impls_for_trait(*id, parameters, binders) == *impls
+            }
+            DatabaseRead::LocalImplsToCoherenceCheck(id, impls) => {
+                db.local_impls_to_coherence_check(*id) == *impls
+            }
+            DatabaseRead::ImplProvidedFor(id, ty, provided) => {
+                db.impl_provided_for(*id, ty) == *provided
+            }
+            DatabaseRead::WellKnownTraitId(well_known, id) => {
+                db.well_known_trait_id(*well_known) == *id
+            }
+            DatabaseRead::ProgramClausesForEnv(environment, clauses) => {
+                db.program_clauses_for_env(environment) == *clauses
+            }
+            DatabaseRead::IsObjectSafe(id, safe) => db.is_object_safe(*id) == *safe,
+            DatabaseRead::ClosureKind(id, substs, kind) => db.closure_kind(*id, substs) == *kind,
+            DatabaseRead::ClosureInputsAndOutput(id, substs, datum) => {
+                db.closure_inputs_and_output(*id, substs) == *datum
+            }
+            DatabaseRead::ClosureUpvars(id, substs, upvars) => {
+                db.closure_upvars(*id, substs) == *upvars
+            }
+            DatabaseRead::ClosureFnSubstitution(id, substs, subst) => {
+                db.closure_fn_substitution(*id, substs) == *subst
+            }
+            DatabaseRead::DiscriminantType(ty, discriminant) => {
+                db.discriminant_type(ty.clone()) == *discriminant
+            }
+            DatabaseRead::FnDefVariance(id, variances) => {
+                db.unification_database().fn_def_variance(*id) == *variances
+            }
+            DatabaseRead::AdtVariance(id, variances) => {
+                db.unification_database().adt_variance(*id) == *variances
+            }
+        }
+    }
+}
+
+impl<I: Interner> UnificationDatabase<I> for TrackingRustIrDatabase<'_, I> {
+    fn fn_def_variance(&self, fn_def_id: FnDefId<I>) -> Variances<I> {
+        let variances = self.db.unification_database().fn_def_variance(fn_def_id);
+        self.record(|| DatabaseRead::FnDefVariance(fn_def_id, variances.clone()));
+        variances
+    }
+
+    fn adt_variance(&self, adt_id: AdtId<I>) -> Variances<I> {
+        let variances = self.db.unification_database().adt_variance(adt_id);
+        self.record(|| DatabaseRead::AdtVariance(adt_id, variances.clone()));
+        variances
+    }
+}
+
+impl<I: Interner> RustIrDatabase<I> for TrackingRustIrDatabase<'_, I> {
+    fn custom_clauses(&self) -> Vec<ProgramClause<I>> {
+        let clauses = self.db.custom_clauses();
+        self.record(|| DatabaseRead::CustomClauses(clauses.clone()));
+        clauses
+    }
+
+    fn associated_ty_data(&self, ty: AssocTypeId<I>) -> Arc<AssociatedTyDatum<I>> {
+        let datum = self.db.associated_ty_data(ty);
+        self.record(|| DatabaseRead::AssociatedTyData(ty, datum.clone()));
+        datum
+    }
+
+    fn trait_datum(&self, trait_id: TraitId<I>) -> Arc<TraitDatum<I>> {
+        let datum = self.db.trait_datum(trait_id);
+        self.record(|| DatabaseRead::TraitDatum(trait_id, datum.clone()));
+        datum
+    }
+
+    fn adt_datum(&self, adt_id: AdtId<I>) -> Arc<AdtDatum<I>> {
+        let datum = self.db.adt_datum(adt_id);
+        self.record(|| DatabaseRead::AdtDatum(adt_id, datum.clone()));
+        datum
+    }
+
+    fn coroutine_datum(&self, coroutine_id: CoroutineId<I>) -> Arc<CoroutineDatum<I>> {
+        let datum = self.db.coroutine_datum(coroutine_id);
+        self.record(|| DatabaseRead::CoroutineDatum(coroutine_id, datum.clone()));
+        datum
+    }
+
+    fn coroutine_witness_datum(
+        &self,
+        coroutine_id: CoroutineId<I>,
+    ) -> Arc<CoroutineWitnessDatum<I>> {
+        let datum = self.db.coroutine_witness_datum(coroutine_id);
+        self.record(|| DatabaseRead::CoroutineWitnessDatum(coroutine_id, datum.clone()));
+        datum
+    }
+
+    fn adt_repr(&self, id: AdtId<I>) -> Arc<AdtRepr<I>> {
+        let repr = self.db.adt_repr(id);
+        self.record(|| DatabaseRead::AdtRepr(id, repr.clone()));
+        repr
+    }
+
+    fn adt_size_align(&self, id: AdtId<I>) -> Arc<AdtSizeAlign> {
+        let size_align = self.db.adt_size_align(id);
+        self.record(|| DatabaseRead::AdtSizeAlign(id, size_align.clone()));
+        size_align
+    }
+
+    fn fn_def_datum(&self, fn_def_id: FnDefId<I>) -> Arc<FnDefDatum<I>> {
+        let datum = self.db.fn_def_datum(fn_def_id);
+        self.record(|| DatabaseRead::FnDefDatum(fn_def_id, datum.clone()));
+        datum
+    }
+
+    fn impl_datum(&self, impl_id: ImplId<I>) -> Arc<ImplDatum<I>> {
+        let datum = self.db.impl_datum(impl_id);
+        self.record(|| DatabaseRead::ImplDatum(impl_id, datum.clone()));
+        datum
+    }
+
+    fn associated_ty_value(&self, id: AssociatedTyValueId<I>) -> Arc<AssociatedTyValue<I>> {
+        let value = self.db.associated_ty_value(id);
+        self.record(|| DatabaseRead::AssociatedTyValue(id, value.clone()));
+        value
+    }
+
+    fn opaque_ty_data(&self, id: OpaqueTyId<I>) -> Arc<OpaqueTyDatum<I>> {
+        let datum = self.db.opaque_ty_data(id);
+        self.record(|| DatabaseRead::OpaqueTyData(id, datum.clone()));
+        datum
+    }
+
+    fn hidden_opaque_type(&self, id: OpaqueTyId<I>) -> Ty<I> {
+        let ty = self.db.hidden_opaque_type(id);
+        self.record(|| DatabaseRead::HiddenOpaqueType(id, ty.clone()));
+        ty
+    }
+
+    fn impls_for_trait(
+        &self,
+        trait_id: TraitId<I>,
+        parameters: &[GenericArg<I>],
+        binders: &CanonicalVarKinds<I>,
+    ) -> Vec<ImplId<I>> {
+        let impls = self.db.impls_for_trait(trait_id, parameters, binders);
+        self.record(|| {
+            DatabaseRead::ImplsForTrait(
+                trait_id,
+                parameters.to_vec(),
+                binders.clone(),
+                impls.clone(),
+            )
+        });
+        impls
+    }
+
+    fn local_impls_to_coherence_check(&self, trait_id: TraitId<I>) -> Vec<ImplId<I>> {
+        let impls = self.db.local_impls_to_coherence_check(trait_id);
+        self.record(|| DatabaseRead::LocalImplsToCoherenceCheck(trait_id, impls.clone()));
+        impls
+    }
+
+    fn impl_provided_for(&self, auto_trait_id: TraitId<I>, ty: &TyKind<I>) -> bool {
+        let provided = self.db.impl_provided_for(auto_trait_id, ty);
+        self.record(|| DatabaseRead::ImplProvidedFor(auto_trait_id, ty.clone(), provided));
+        provided
+    }
+
+    fn well_known_trait_id(&self, well_known_trait: WellKnownTrait) -> Option<TraitId<I>> {
+        let trait_id = self.db.well_known_trait_id(well_known_trait);
+        self.record(|| DatabaseRead::WellKnownTraitId(well_known_trait, trait_id));
+        trait_id
+    }
+
+    fn program_clauses_for_env(&self, environment: &Environment<I>) -> ProgramClauses<I> {
+        let clauses = self.db.program_clauses_for_env(environment);
+        self.record(|| DatabaseRead::ProgramClausesForEnv(environment.clone(), clauses.clone()));
+        clauses
+    }
+
+    fn interner(&self) -> I {
+        self.db.interner()
+    }
+
+    fn is_object_safe(&self, trait_id: TraitId<I>) -> bool {
+        let safe = self.db.is_object_safe(trait_id);
+        self.record(|| DatabaseRead::IsObjectSafe(trait_id, safe));
+        safe
+    }
+
+    fn closure_kind(&self, closure_id: ClosureId<I>, substs: &Substitution<I>) -> ClosureKind {
+        let kind = self.db.closure_kind(closure_id, substs);
+        self.record(|| DatabaseRead::ClosureKind(closure_id, substs.clone(), kind));
+        kind
+    }
+
+    fn closure_inputs_and_output(
+        &self,
+        closure_id: ClosureId<I>,
+        substs: &Substitution<I>,
+    ) -> Binders<FnDefInputsAndOutputDatum<I>> {
+        let datum = self.db.closure_inputs_and_output(closure_id, substs);
+        self.record(|| {
+            DatabaseRead::ClosureInputsAndOutput(closure_id, substs.clone(), datum.clone())
+        });
+        datum
+    }
+
+    fn closure_upvars(&self, closure_id: ClosureId<I>, substs: &Substitution<I>) -> Binders<Ty<I>> {
+        let upvars = self.db.closure_upvars(closure_id, substs);
+        self.record(|| DatabaseRead::ClosureUpvars(closure_id, substs.clone(), upvars.clone()));
+        upvars
+    }
+
+    fn closure_fn_substitution(
+        &self,
+        closure_id: ClosureId<I>,
+        substs: &Substitution<I>,
+    ) -> Substitution<I> {
+        let subst = self.db.closure_fn_substitution(closure_id, substs);
+        self.record(|| {
+            DatabaseRead::ClosureFnSubstitution(closure_id, substs.clone(), subst.clone())
+        });
+        subst
+    }
+
+    fn unification_database(&self) -> &dyn UnificationDatabase<I> {
+        self
+    }
+
+    fn trait_name(&self, trait_id: TraitId<I>) -> String {
+        self.db.trait_name(trait_id)
+    }
+
+    fn adt_name(&self, adt_id: AdtId<I>) -> String {
+        self.db.adt_name(adt_id)
+    }
+
+    fn assoc_type_name(&self, assoc_ty_id: AssocTypeId<I>) -> String {
+        self.db.assoc_type_name(assoc_ty_id)
+    }
+
+    fn opaque_type_name(&self, opaque_ty_id: OpaqueTyId<I>) -> String {
+        self.db.opaque_type_name(opaque_ty_id)
+    }
+
+    fn fn_def_name(&self, fn_def_id: FnDefId<I>) -> String {
+        self.db.fn_def_name(fn_def_id)
+    }
+
+    fn discriminant_type(&self, ty: Ty<I>) -> Ty<I> {
+        let discriminant = self.db.discriminant_type(ty.clone());
+        self.record(|| DatabaseRead::DiscriminantType(ty, discriminant.clone()));
+        discriminant
+    }
+}
@@ -2,7 +2,8 @@ use std::ops::ControlFlow;
 use std::{fmt, iter};
 
 use crate::{
-    ext::*, goal_builder::GoalBuilder, rust_ir::*, solve::Solver, split::Split, RustIrDatabase,
+    display, ext::*, goal_builder::GoalBuilder, rust_ir::*, solve::Solver, split::Split,
+    RustIrDatabase, Solution,
 };
 use chalk_ir::{
     cast::*,
@@ -15,25 +16,123 @@ use tracing::debug;
 
 #[derive(Debug)]
 pub enum WfError<I: Interner> {
-    IllFormedTypeDecl(chalk_ir::AdtId<I>),
-    IllFormedOpaqueTypeDecl(chalk_ir::OpaqueTyId<I>),
-    IllFormedTraitImpl(chalk_ir::TraitId<I>),
+    IllFormedTypeDecl(chalk_ir::AdtId<I>, Vec<UnprovableGoal<I>>),
+    IllFormedOpaqueTypeDecl(chalk_ir::OpaqueTyId<I>, Vec<UnprovableGoal<I>>),
+    IllFormedTraitImpl(chalk_ir::TraitId<I>, Vec<UnprovableGoal<I>>),
+}
+
+/// A part of a well-formedness goal which the solver could not prove.
+#[derive(Debug)]
+pub struct UnprovableGoal<I: Interner> {
+    /// The sub-goal, still wrapped in the `forall` and `if` goals that
+    /// enclose it in the full well-formedness goal. Where the goal requires
+    /// a type or trait reference to be well-formed, this is the where clause
+    /// of the type or trait which doesn't hold.
+    pub goal: Goal<I>,
+    /// What the solver returned for `goal`: either no solution, or an
+    /// ambiguous one.
+    pub solution: Option<Solution<I>>,
+    /// The item whose parameters the outermost `forall` of `goal` binds.
+    pub parameters: GoalParameters<I>,
+}
+
+/// An item whose parameters are bound by the outermost `forall` of a
+/// well-formedness goal.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GoalParameters<I: Interner> {
+    Adt(AdtId<I>),
+    OpaqueTy(OpaqueTyId<I>),
+    Impl(ImplId<I>),
+    /// The parameters of the associated type value, followed by those of
+    /// its impl.
+    AssociatedTyValue(AssociatedTyValueId<I>),
+}
+
+impl<I: Interner> WfError<I> {
+    /// The sub-goals which could not be proven. This is empty if the error
+    /// comes from the builtin rules for well-known traits (e.g. `Copy`).
+    pub fn unprovable_goals(&self) -> &[UnprovableGoal<I>] {
+        match self {
+            WfError::IllFormedTypeDecl(_, goals)
+            | WfError::IllFormedOpaqueTypeDecl(_, goals)
+            | WfError::IllFormedTraitImpl(_, goals) => goals,
+        }
+    }
+
+    /// Like the `Display` impl, but also lists each sub-goal which could not
+    /// be proven, using `db` to show them as Rust syntax.
+    pub fn display<'a>(&'a self, db: &'a dyn RustIrDatabase<I>) -> WfErrorDisplay<'a, I> {
+        WfErrorDisplay {
+            error: self,
+            db,
+            parameter_names: None,
+        }
+    }
+}
+
+pub struct WfErrorDisplay<'a, I: Interner> {
+    error: &'a WfError<I>,
+    db: &'a dyn RustIrDatabase<I>,
+    parameter_names: Option<&'a dyn Fn(GoalParameters<I>) -> Vec<String>>,
+}
+
+impl<'a, I: Interner> WfErrorDisplay<'a, I> {
+    /// Writes the parameters of the items the sub-goals quantify over with
+    /// the names `parameter_names` gives them, rather than generated ones.
+    /// The names are in the order of the item's binders, and lifetime names
+    /// are given without their `'`.
+    pub fn with_parameter_names(
+        self,
+        parameter_names: &'a dyn Fn(GoalParameters<I>) -> Vec<String>,
+    ) -> Self {
+        WfErrorDisplay {
+            parameter_names: Some(parameter_names),
+            ..self
+        }
+    }
+}
+
+impl<I: Interner> fmt::Display for WfErrorDisplay<'_, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+        for UnprovableGoal {
+            goal,
+            solution,
+            parameters,
+        } in self.error.unprovable_goals()
+        {
+            let reason = match solution {
+                Some(_) => "ambiguous",
+                None => "no solution",
+            };
+            let names = self
+                .parameter_names
+                .map_or_else(Vec::new, |names| names(*parameters));
+            write!(
+                f,
+                "\n    could not prove `{}` ({})",
+                display::display_value(self.db, goal).with_parameter_names(&names),
+                reason
+            )?;
+        }
+        Ok(())
+    }
 }
 
 impl<I: Interner> fmt::Display for WfError<I> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            WfError::IllFormedTypeDecl(id) => write!(
+            WfError::IllFormedTypeDecl(id, _) => write!(
                 f,
                 "type declaration `{:?}` does not meet well-formedness requirements",
                 id
             ),
-            WfError::IllFormedOpaqueTypeDecl(id) => write!(
+            WfError::IllFormedOpaqueTypeDecl(id, _) => write!(
                 f,
                 "opaque type declaration `{:?}` does not meet well-formedness requirements",
                 id
             ),
-            WfError::IllFormedTraitImpl(id) => write!(
+            WfError::IllFormedTraitImpl(id, _) => write!(
                 f,
                 "trait impl for `{:?}` does not meet well-formedness requirements",
                 id
@@ -232,8 +331,6 @@ where
     }
 
     pub fn verify_adt_decl(&self, adt_id: AdtId<I>) -> Result<(), WfError<I>> {
-        let interner = self.db.interner();
-
         // Given a struct like
         //
         // ```rust
@@ -299,48 +396,31 @@ where
             },
         );
 
-        let wg_goal = wg_goal.into_closed_goal(interner);
-        let mut fresh_solver = (self.solver_builder)();
-        let is_legal = fresh_solver.has_unique_solution(self.db, &wg_goal);
-
-        if !is_legal {
-            Err(WfError::IllFormedTypeDecl(adt_id))
-        } else {
-            Ok(())
-        }
+        self.prove(vec![(GoalParameters::Adt(adt_id), wg_goal)])
+            .map_err(|goals| WfError::IllFormedTypeDecl(adt_id, goals))
     }
 
     pub fn verify_trait_impl(&self, impl_id: ImplId<I>) -> Result<(), WfError<I>> {
-        let interner = self.db.interner();
-
         let impl_datum = self.db.impl_datum(impl_id);
         let trait_id = impl_datum.trait_id();
 
-        let impl_goal = Goal::all(
-            interner,
-            impl_header_wf_goal(self.db, impl_id).into_iter().chain(
-                impl_datum
-                    .associated_ty_value_ids
-                    .iter()
-                    .filter_map(|&id| compute_assoc_ty_goal(self.db, id)),
-            ),
-        );
+        let impl_goals: Vec<_> = impl_header_wf_goal(self.db, impl_id)
+            .map(|goal| (GoalParameters::Impl(impl_id), goal))
+            .into_iter()
+            .chain(impl_datum.associated_ty_value_ids.iter().filter_map(|&id| {
+                let goal = compute_assoc_ty_goal(self.db, id)?;
+                Some((GoalParameters::AssociatedTyValue(id), goal))
+            }))
+            .collect();
 
         if let Some(well_known) = self.db.trait_datum(trait_id).well_known {
             self.verify_well_known_impl(impl_id, well_known)?
         }
 
-        debug!("WF trait goal: {:?}", impl_goal);
-
-        let mut fresh_solver = (self.solver_builder)();
-        let is_legal =
-            fresh_solver.has_unique_solution(self.db, &impl_goal.into_closed_goal(interner));
+        debug!("WF trait goals: {:?}", impl_goals);
 
-        if is_legal {
-            Ok(())
-        } else {
-            Err(WfError::IllFormedTraitImpl(trait_id))
-        }
+        self.prove(impl_goals)
+            .map_err(|goals| WfError::IllFormedTraitImpl(trait_id, goals))
     }
 
     pub fn verify_opaque_ty_decl(&self, opaque_ty_id: OpaqueTyId<I>) -> Result<(), WfError<I>> {
@@ -348,7 +428,6 @@ where
         // ```notrust
         // opaque type Foo<T>: Clone where T: Bar = Baz;
         // ```
-        let interner = self.db.interner();
 
         let mut gb = GoalBuilder::new(self.db);
 
@@ -387,14 +466,8 @@ where
 
         debug!("WF opaque type goal: {:#?}", goal);
 
-        let mut new_solver = (self.solver_builder)();
-        let is_legal = new_solver.has_unique_solution(self.db, &goal.into_closed_goal(interner));
-
-        if is_legal {
-            Ok(())
-        } else {
-            Err(WfError::IllFormedOpaqueTypeDecl(opaque_ty_id))
-        }
+        self.prove(vec![(GoalParameters::OpaqueTy(opaque_ty_id), goal)])
+            .map_err(|goals| WfError::IllFormedOpaqueTypeDecl(opaque_ty_id, goals))
     }
 
     /// Verify builtin rules for well-known traits
@@ -444,8 +517,219 @@ where
         if is_legal {
             Ok(())
         } else {
-            Err(WfError::IllFormedTraitImpl(impl_datum.trait_id()))
+            Err(WfError::IllFormedTraitImpl(impl_datum.trait_id(), vec![]))
+        }
+    }
+
+    /// Tries to prove well-formedness goals, each of which is a `forall`
+    /// over the parameters of an item. If they can't all be proven, the
+    /// sub-goals of each are tried on their own, and the ones which can't be
+    /// proven are returned.
+    fn prove(
+        &self,
+        goals: Vec<(GoalParameters<I>, Goal<I>)>,
+    ) -> Result<(), Vec<UnprovableGoal<I>>> {
+        let interner = self.db.interner();
+        let mut solver = (self.solver_builder)();
+
+        let goal = Goal::all(interner, goals.iter().map(|(_, goal)| goal.clone()));
+        if solver.has_unique_solution(self.db, &goal.into_closed_goal(interner)) {
+            return Ok(());
+        }
+
+        let mut unprovable = vec![];
+        for (parameters, goal) in goals {
+            // We keep the outermost `forall`, even if it has no binders, so
+            // that it always binds the item's parameters.
+            let subgoals = match goal.data(interner) {
+                GoalData::Quantified(QuantifierKind::ForAll, subgoal) => {
+                    split_goal(interner, subgoal.skip_binders())
+                        .into_iter()
+                        .map(|goal| {
+                            GoalData::Quantified(
+                                QuantifierKind::ForAll,
+                                Binders::new(subgoal.binders.clone(), goal),
+                            )
+                            .intern(interner)
+                        })
+                        .collect()
+                }
+                _ => split_goal(interner, &goal),
+            };
+            for goal in subgoals {
+                self.find_unprovable(&mut *solver, goal, parameters, 0, &mut unprovable);
+            }
+        }
+        Err(unprovable)
+    }
+
+    /// Pushes `goal` to `unprovable` if it can't be proven. If it requires
+    /// a type or trait reference to be well-formed, the where clauses of the
+    /// type or trait which can't be proven are pushed instead.
+    fn find_unprovable(
+        &self,
+        solver: &mut dyn Solver<I>,
+        goal: Goal<I>,
+        parameters: GoalParameters<I>,
+        depth: usize,
+        unprovable: &mut Vec<UnprovableGoal<I>>,
+    ) {
+        // Where clauses can refer to each other in a cycle (like
+        // `trait A where Self: B` and `trait B where Self: A`), so we don't
+        // follow them forever.
+        const MAX_DEPTH: usize = 8;
+
+        let interner = self.db.interner();
+        let solution = solver.solve(self.db, &goal.clone().into_closed_goal(interner));
+        if let Some(Solution::Unique(_)) = solution {
+            return;
+        }
+        if depth < MAX_DEPTH {
+            if let Some(requirements) = well_formed_requirements(self.db, &goal) {
+                let found = unprovable.len();
+                for requirement in requirements
+                    .iter()
+                    .flat_map(|goal| split_goal(interner, goal))
+                {
+                    self.find_unprovable(solver, requirement, parameters, depth + 1, unprovable);
+                }
+                if unprovable.len() > found {
+                    return;
+                }
+            }
+        }
+        unprovable.push(UnprovableGoal {
+            goal,
+            solution,
+            parameters,
+        });
+    }
+}
+
+/// If `goal` (within `forall` and `if` goals) requires an ADT or a trait
+/// reference to be well-formed, the goals which that requires, within the
+/// same `forall` and `if` goals: the where clauses of the ADT, or the trait
+/// reference itself and the where clauses of the trait.
+fn well_formed_requirements<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    goal: &Goal<I>,
+) -> Option<Vec<Goal<I>>> {
+    let interner = db.interner();
+    let (where_clauses, trait_ref) = match goal.data(interner) {
+        GoalData::Quantified(QuantifierKind::ForAll, subgoal) => {
+            let requirements = well_formed_requirements(db, subgoal.skip_binders())?;
+            return Some(
+                requirements
+                    .into_iter()
+                    .map(|goal| {
+                        GoalData::Quantified(
+                            QuantifierKind::ForAll,
+                            Binders::new(subgoal.binders.clone(), goal),
+                        )
+                        .intern(interner)
+                    })
+                    .collect(),
+            );
+        }
+        GoalData::Implies(clauses, subgoal) => {
+            let requirements = well_formed_requirements(db, subgoal)?;
+            return Some(
+                requirements
+                    .into_iter()
+                    .map(|goal| GoalData::Implies(clauses.clone(), goal).intern(interner))
+                    .collect(),
+            );
+        }
+        GoalData::DomainGoal(DomainGoal::WellFormed(WellFormed::Ty(ty))) => {
+            match ty.kind(interner) {
+                TyKind::Adt(id, substitution) => {
+                    let where_clauses = db
+                        .adt_datum(*id)
+                        .binders
+                        .clone()
+                        .substitute(interner, substitution)
+                        .where_clauses;
+                    (where_clauses, None)
+                }
+                _ => return None,
+            }
+        }
+        GoalData::DomainGoal(DomainGoal::WellFormed(WellFormed::Trait(trait_ref))) => {
+            let where_clauses = db
+                .trait_datum(trait_ref.trait_id)
+                .binders
+                .map_ref(|bound| bound.where_clauses.clone())
+                .substitute(interner, &trait_ref.substitution);
+            (where_clauses, Some(trait_ref.clone()))
+        }
+        _ => return None,
+    };
+    Some(
+        trait_ref
+            .map(|trait_ref| trait_ref.cast(interner))
+            .into_iter()
+            .chain(
+                where_clauses
+                    .into_iter()
+                    .map(|wc| wc.into_well_formed_goal(interner).cast(interner)),
+            )
+            .collect(),
+    )
+}
+
+/// Splits a goal into the smallest goals which must all hold for it to hold,
+/// keeping the `forall` and `if` goals around each of them. For example:
+///
+/// ```notrust
+/// forall<T> { if (FromEnv(T: Eq)) { WellFormed(Vec<T>), T: Sized } }
+/// ```
+///
+/// becomes
+///
+/// ```notrust
+/// forall<T> { if (FromEnv(T: Eq)) { WellFormed(Vec<T>) } }
+/// forall<T> { if (FromEnv(T: Eq)) { T: Sized } }
+/// ```
+///
+/// `exists` goals are not split, as an `exists` doesn't distribute over the
+/// goals within it.
+fn split_goal<I: Interner>(interner: I, goal: &Goal<I>) -> Vec<Goal<I>> {
+    match goal.data(interner) {
+        GoalData::All(goals) => goals
+            .iter(interner)
+            .flat_map(|goal| split_goal(interner, goal))
+            .collect(),
+        // Likewise for a `forall` without any binders.
+        GoalData::Quantified(QuantifierKind::ForAll, subgoal)
+            if subgoal.binders.is_empty(interner) =>
+        {
+            let subgoal = subgoal
+                .clone()
+                .substitute(interner, &Substitution::empty(interner));
+            split_goal(interner, &subgoal)
+        }
+        GoalData::Quantified(QuantifierKind::ForAll, subgoal) => {
+            split_goal(interner, subgoal.skip_binders())
+                .into_iter()
+                .map(|goal| {
+                    GoalData::Quantified(
+                        QuantifierKind::ForAll,
+                        Binders::new(subgoal.binders.clone(), goal),
+                    )
+                    .intern(interner)
+                })
+                .collect()
+        }
+        // An `if` without any clauses doesn't add anything, so we leave it
+        // out to keep the sub-goals readable.
+        GoalData::Implies(clauses, subgoal) if clauses.is_empty(interner) => {
+            split_goal(interner, subgoal)
         }
+        GoalData::Implies(clauses, subgoal) => split_goal(interner, subgoal)
+            .into_iter()
+            .map(|goal| GoalData::Implies(clauses.clone(), goal).intern(interner))
+            .collect(),
+        _ => vec![goal.clone()],
     }
 }
 
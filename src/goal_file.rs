@@ -0,0 +1,242 @@
+//! Goal files, which pair goals with the solutions they are expected to
+//! yield, so that the `chalk` binary can check them (see `--goals`).
+//!
+//! A goal file contains blocks like the ones the `test!` macro takes:
+//!
+//! ```text
+//! goal {
+//!     exists<T> { Vec<T>: Clone }
+//! } yields {
+//!     Ambiguous; no inference guidance
+//! }
+//! ```
+//!
+//! Goals can also be checked against the answers `solve_multiple` gives for
+//! them, with one answer per line and a last line of `...` if there are more
+//! answers than those:
+//!
+//! ```text
+//! goal {
+//!     exists<T> { T: Clone }
+//! } yields_all {
+//!     substitution [?0 := Foo]
+//!     ...
+//! }
+//! ```
+//!
+//! Like with the `test!` macro, the solutions and answers have to be exactly
+//! the expected ones, including their region constraints, but whitespace is
+//! ignored.
+//!
+//! Everything outside of these blocks is program text, so goals can either
+//! be added to a `.chalk` file or be kept in a file of their own.
+
+/// A goal, along with the solution it is expected to yield.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Expectation {
+    /// The line the `goal` keyword is on, starting at 1.
+    pub line: usize,
+    pub goal: String,
+    /// The solution, as `Solution::display` writes it, or `No possible
+    /// solution`. With `all_answers`, the answers instead, one per line.
+    pub expected: String,
+    /// Whether the goal was given with `yields_all`.
+    pub all_answers: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GoalFile {
+    /// The text of the file, with the goal blocks blanked out. Lines and
+    /// columns are the same as in the file, so that errors point to the
+    /// right place.
+    pub program: String,
+    pub expectations: Vec<Expectation>,
+}
+
+impl Expectation {
+    /// Checks whether `actual` is the solution we expect (or, with
+    /// `all_answers`, the answers, one per line), ignoring whitespace.
+    pub fn matches(&self, actual: &str) -> bool {
+        if !self.all_answers {
+            return same_ignoring_whitespace(&self.expected, actual);
+        }
+        let expected: Vec<_> = self.expected.lines().collect();
+        let actual: Vec<_> = actual.lines().collect();
+        expected.len() == actual.len()
+            && expected
+                .iter()
+                .zip(&actual)
+                .all(|(expected, actual)| same_ignoring_whitespace(expected, actual))
+    }
+
+    /// With `all_answers`, the number of answers we expect (not counting the
+    /// `...` for more answers).
+    pub fn answer_count(&self) -> usize {
+        self.expected
+            .lines()
+            .filter(|line| line.trim() != "...")
+            .count()
+    }
+}
+
+fn same_ignoring_whitespace(expected: &str, actual: &str) -> bool {
+    expected.split_whitespace().eq(actual.split_whitespace())
+}
+
+/// Splits the text of a goal file into its program text and expectations.
+pub fn parse(text: &str) -> Result<GoalFile, String> {
+    let mut program = String::with_capacity(text.len());
+    let mut expectations = vec![];
+    let mut pos = 0;
+    while let Some(start) = find_goal(text, pos) {
+        program.push_str(&text[pos..start]);
+        let line = text[..start].matches('\n').count() + 1;
+        let error = |message: &str| format!("line {}: {}", line, message);
+
+        let (goal, end) =
+            block(text, start + "goal".len()).ok_or_else(|| error("expected `goal { .. }`"))?;
+        let (end, all_answers) = match keyword(text, end, "yields_all") {
+            Some(end) => (end, true),
+            None => (
+                keyword(text, end, "yields")
+                    .ok_or_else(|| error("expected `yields { .. }` after the goal"))?,
+                false,
+            ),
+        };
+        let (expected, end) =
+            block(text, end).ok_or_else(|| error("expected `yields { .. }` after the goal"))?;
+        if expected.is_empty() && !all_answers {
+            return Err(error("the expected solution cannot be empty"));
+        }
+
+        expectations.push(Expectation {
+            line,
+            goal: goal.to_string(),
+            expected: expected
+                .lines()
+                .map(str::trim)
+                .collect::<Vec<_>>()
+                .join("\n"),
+            all_answers,
+        });
+        program.extend(
+            text[start..end]
+                .chars()
+                .map(|c| if c == '\n' { '\n' } else { ' ' }),
+        );
+        pos = end;
+    }
+    program.push_str(&text[pos..]);
+
+    Ok(GoalFile {
+        program,
+        expectations,
+    })
+}
+
+/// Finds the next `goal` keyword at or after `pos` which starts a line.
+fn find_goal(text: &str, pos: usize) -> Option<usize> {
+    text[pos..]
+        .match_indices("goal")
+        .map(|(i, _)| pos + i)
+        .find(|&i| {
+            let line_start = text[..i].rfind('\n').map_or(0, |n| n + 1);
+            let starts_line = text[line_start..i].trim().is_empty();
+            let ends_word =
+                text[i + "goal".len()..].starts_with(|c: char| c.is_whitespace() || c == '{');
+            starts_line && ends_word
+        })
+}
+
+/// Skips whitespace and `keyword` at `pos`, returning the position after it.
+fn keyword(text: &str, pos: usize, keyword: &str) -> Option<usize> {
+    let rest = text[pos..].trim_start();
+    let start = text.len() - rest.len();
+    rest.strip_prefix(keyword).map(|_| start + keyword.len())
+}
+
+/// Parses a block in braces at `pos` (after whitespace), returning its
+/// trimmed contents and the position after the closing brace.
+fn block(text: &str, pos: usize) -> Option<(&str, usize)> {
+    let start = keyword(text, pos, "{")?;
+    let mut depth = 1;
+    for (i, c) in text[start..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => continue,
+        }
+        if depth == 0 {
+            let end = start + i;
+            return Some((text[start..end].trim(), end + 1));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn goals_in_program() {
+        let text = "struct Foo { }\n\
+                    goal {\n    forall<T> { T: Clone }\n} yields {\n    No possible solution\n}\n\
+                    trait Clone { }\n";
+        let file = parse(text).unwrap();
+        assert_eq!(file.program.lines().count(), text.lines().count());
+        assert_eq!(
+            file.program.split_whitespace().collect::<Vec<_>>(),
+            ["struct", "Foo", "{", "}", "trait", "Clone", "{", "}"]
+        );
+        assert_eq!(
+            file.expectations,
+            [Expectation {
+                line: 2,
+                goal: "forall<T> { T: Clone }".to_string(),
+                expected: "No possible solution".to_string(),
+                all_answers: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn missing_yields() {
+        let text = "goal { Foo: Clone }\ngoal { Foo: Clone } yields { Unique }";
+        assert_eq!(
+            parse(text),
+            Err("line 1: expected `yields { .. }` after the goal".to_string())
+        );
+    }
+
+    #[test]
+    fn exact_match() {
+        let expectation = Expectation {
+            line: 1,
+            goal: "exists<T> { T: Clone }".to_string(),
+            expected: "Unique;\n    substitution [?0 := Foo]".to_string(),
+            all_answers: false,
+        };
+        assert!(expectation.matches("Unique; substitution [?0 := Foo]"));
+        assert!(!expectation.matches("Unique; substitution [?0 := Foo], lifetime constraints []"));
+        assert!(!expectation.matches("Ambiguous; no inference guidance"));
+    }
+
+    #[test]
+    fn all_answers() {
+        let text = "goal { exists<T> { T: Clone } } yields_all {\n    [?0 := Foo]\n    ...\n}\n\
+                    goal { Foo: Copy } yields_all { }";
+        let file = parse(text).unwrap();
+        let [some, none] = &file.expectations[..] else {
+            panic!("expected two goals, found {:?}", file.expectations);
+        };
+        assert!(some.all_answers);
+        assert_eq!(some.answer_count(), 1);
+        assert!(some.matches("[?0 := Foo]\n..."));
+        assert!(!some.matches("[?0 := Foo], lifetime constraints []\n..."));
+        assert!(!some.matches("[?0 := Foo]"));
+        assert!(!some.matches("[?0 := Bar]\n..."));
+        assert_eq!(none.answer_count(), 0);
+        assert!(none.matches(""));
+    }
+}
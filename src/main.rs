@@ -1,18 +1,32 @@
 #[macro_use]
 extern crate serde_derive;
 
+mod goal_file;
+
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::Read;
+use std::panic;
+use std::path::Path;
 use std::process::exit;
+use std::sync::Arc;
 
 use chalk_integration::db::ChalkDatabase;
 use chalk_integration::interner::ChalkIr;
 use chalk_integration::lowering::*;
+use chalk_integration::program::Program;
 use chalk_integration::query::LoweringDatabase;
+use chalk_integration::reduce::{reduce, Predicate};
 use chalk_integration::SolverChoice;
+use chalk_ir::{
+    Canonical, ConstrainedSubst, Constraint, Constraints, Goal, GoalData, InEnvironment,
+    ProgramClause, Substitution, UCanonical,
+};
+use chalk_solve::clauses::program_clauses_for_goal;
+use chalk_solve::display;
 use chalk_solve::ext::*;
 use chalk_solve::logging;
-use chalk_solve::RustIrDatabase;
+use chalk_solve::{Guidance, RustIrDatabase, Solution, SubstitutionResult};
 use docopt::Docopt;
 use rustyline::error::ReadlineError;
 
@@ -23,25 +37,184 @@ chalk repl
 
 Usage:
   chalk [options]
+  chalk reduce <predicate> --program=PATH --goal=GOAL [options]
   chalk (-h | --help)
 
+`chalk reduce` removes as much of the program as it can while the goal keeps
+satisfying the predicate, and prints what is left. The predicate is `disagree`
+(the SLG and recursive solvers give different solutions), `panics` or
+`ambiguous` (the solver panics, or gives an ambiguous solution).
+
 Options:
   --help              Show this screen.
   --program=PATH      Specifies the path to the `.chalk` file containing traits/impls.
   --goal=GOAL         Specifies a goal to evaluate (may be given more than once).
   --overflow-depth=N  Specifies the overflow depth [default: 10].
   --multiple          Output multiple answers instead of ambiguous solution.
+  --compare           Solve each goal with both the SLG and the recursive solver, and report
+                      whether their solutions agree.
+  --goals=PATH        Checks the goals in PATH against the solutions they are expected to yield.
   --solver=S          Specifies the solver to use. `slg` or `recursive`. Default is SLG.
+                      With `--goals`, a comma-separated list. Default is both.
+  --format=F          Specifies the output format for solutions. `text` or `json` [default: text].
+  --cache=PATH        Starts the solver with the results saved in PATH, if they were saved for the
+                      same program and solver, and saves its results there when done. Only the
+                      recursive solver keeps such results.
 ";
 
 /// This struct represents the various command line options available.
 #[derive(Debug, Deserialize)]
 struct Args {
+    cmd_reduce: bool,
+    arg_predicate: Option<String>,
     flag_program: Option<String>,
     flag_solver: Option<String>,
     flag_goal: Vec<String>,
+    flag_goals: Option<String>,
     flag_overflow_depth: usize,
     flag_multiple: bool,
+    flag_compare: bool,
+    flag_format: Format,
+    flag_cache: Option<String>,
+}
+
+/// How solutions are printed.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+enum Format {
+    /// As `Solution::display` writes them.
+    Text,
+    /// As one JSON object per line (see [`JsonResult`]), which is easier for
+    /// scripts to consume.
+    Json,
+}
+
+/// The result of solving a goal, as printed by `--format json`.
+#[derive(Debug, Serialize)]
+struct JsonResult {
+    /// The goal, as given by the user. This is `null` for errors in the
+    /// program itself.
+    goal: Option<String>,
+    /// One of `unique`, `ambiguous-definite`, `ambiguous-suggested`,
+    /// `unknown`, `none` or `error`.
+    kind: &'static str,
+    /// Maps the canonical variables of the goal (`?0`, `?1`, ...) to the
+    /// values the solution gives them, written as Rust syntax. Variables the
+    /// solution leaves open are written as `_0_0`, `_0_1`, ...
+    substitution: BTreeMap<String, String>,
+    /// The region constraints the solution requires, written as Rust syntax.
+    constraints: Vec<String>,
+    /// The error message, if `kind` is `error`.
+    error: Option<String>,
+}
+
+impl JsonResult {
+    fn new(goal: Option<&str>, kind: &'static str) -> Self {
+        JsonResult {
+            goal: goal.map(str::to_string),
+            kind,
+            substitution: BTreeMap::new(),
+            constraints: vec![],
+            error: None,
+        }
+    }
+
+    fn solution(
+        db: &dyn RustIrDatabase<ChalkIr>,
+        goal: &str,
+        solution: Option<&Solution<ChalkIr>>,
+    ) -> Self {
+        match solution {
+            Some(Solution::Unique(constrained)) => {
+                JsonResult::new(Some(goal), "unique").with_constrained_subst(db, constrained)
+            }
+            Some(Solution::Ambig(Guidance::Definite(subst))) => {
+                JsonResult::new(Some(goal), "ambiguous-definite").with_subst(db, &subst.value)
+            }
+            Some(Solution::Ambig(Guidance::Suggested(subst))) => {
+                JsonResult::new(Some(goal), "ambiguous-suggested").with_subst(db, &subst.value)
+            }
+            Some(Solution::Ambig(Guidance::Unknown)) => JsonResult::new(Some(goal), "unknown"),
+            None => JsonResult::new(Some(goal), "none"),
+        }
+    }
+
+    /// A single answer, as produced by `--multiple`. Ambiguous answers still
+    /// have a definite substitution, they just may not be the only one.
+    fn answer(
+        db: &dyn RustIrDatabase<ChalkIr>,
+        goal: &str,
+        answer: &SubstitutionResult<Canonical<ConstrainedSubst<ChalkIr>>>,
+    ) -> Self {
+        match answer {
+            SubstitutionResult::Definite(constrained) => {
+                JsonResult::new(Some(goal), "unique").with_constrained_subst(db, constrained)
+            }
+            SubstitutionResult::Ambiguous(constrained) => {
+                JsonResult::new(Some(goal), "ambiguous-definite")
+                    .with_constrained_subst(db, constrained)
+            }
+            SubstitutionResult::Floundered => JsonResult::new(Some(goal), "unknown"),
+        }
+    }
+
+    fn error(goal: Option<&str>, error: &dyn std::error::Error) -> Self {
+        JsonResult {
+            error: Some(error.to_string()),
+            ..JsonResult::new(goal, "error")
+        }
+    }
+
+    fn with_constrained_subst(
+        self,
+        db: &dyn RustIrDatabase<ChalkIr>,
+        constrained: &Canonical<ConstrainedSubst<ChalkIr>>,
+    ) -> Self {
+        self.with_subst(db, &constrained.value.subst)
+            .with_constraints(db, &constrained.value.constraints)
+    }
+
+    fn with_subst(
+        mut self,
+        db: &dyn RustIrDatabase<ChalkIr>,
+        subst: &Substitution<ChalkIr>,
+    ) -> Self {
+        self.substitution = subst
+            .iter(ChalkIr)
+            .enumerate()
+            .map(|(i, value)| {
+                let value = display::display_value(db, value).to_string();
+                (format!("?{}", i), value)
+            })
+            .collect();
+        self
+    }
+
+    fn with_constraints(
+        mut self,
+        db: &dyn RustIrDatabase<ChalkIr>,
+        constraints: &Constraints<ChalkIr>,
+    ) -> Self {
+        self.constraints = constraints
+            .iter(ChalkIr)
+            .map(|constraint| match &constraint.goal {
+                Constraint::LifetimeOutlives(a, b) => format!(
+                    "{}: {}",
+                    display::display_value(db, a),
+                    display::display_value(db, b)
+                ),
+                Constraint::TypeOutlives(ty, lifetime) => format!(
+                    "{}: {}",
+                    display::display_value(db, ty),
+                    display::display_value(db, lifetime)
+                ),
+            })
+            .collect();
+        self
+    }
+
+    fn print(&self) {
+        println!("{}", serde_json::to_string(self).unwrap());
+    }
 }
 
 /// A loaded and parsed program.
@@ -56,18 +229,147 @@ impl LoadedProgram {
     ///
     /// [`SolverChoice`]: struct.solve.SolverChoice.html
     fn new(text: String, solver_choice: SolverChoice) -> Result<LoadedProgram> {
-        let db = ChalkDatabase::with(&text, solver_choice);
+        let mut db = ChalkDatabase::with(&text, solver_choice);
+        // For the `stats` command.
+        db.set_collect_solver_stats(true);
         Ok(LoadedProgram { text, db })
     }
 
-    /// Parse a goal and attempt to solve it, using the specified solver.
+    /// Performs the lowering, coherence and WF checks on the program. Errors
+    /// are rendered together with the parts of the program they refer to.
+    fn check(&self) -> Result<Arc<Program>> {
+        self.db
+            .checked_program()
+            .map_err(|e| e.diagnostic().render(&self.text).into())
+    }
+
+    /// Loads the solver results saved at `path` by `save_cache`, if there
+    /// are any.
+    fn load_cache(&self, path: &str) -> Result<()> {
+        let path = Path::new(path);
+        if path.exists() {
+            self.db.load_solver_cache(path)?;
+        }
+        Ok(())
+    }
+
+    /// Saves the results the solver has cached so far to `path`.
+    fn save_cache(&self, path: &str) -> Result<()> {
+        self.db.save_solver_cache(Path::new(path))?;
+        Ok(())
+    }
+
+    /// Parse a goal and attempt to solve it, using the specified solver, and
+    /// print the solution in the given format.
     fn goal(
+        &self,
+        rl: Option<&mut rustyline::DefaultEditor>,
+        text: &str,
+        multiple_answers: bool,
+        format: Format,
+    ) -> Result<()> {
+        match format {
+            Format::Text => self.goal_text(rl, text, multiple_answers),
+            Format::Json => self.goal_json(text, multiple_answers),
+        }
+    }
+
+    /// Returns the first `count` answers `solve_multiple` gives for `goal`,
+    /// one per line, with a last line of `...` if there are more. (This is
+    /// how goal files write them.)
+    fn answers(&self, goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>, count: usize) -> String {
+        let mut answers = vec![];
+        self.db.solve_multiple(goal, &mut |answer, has_next| {
+            answers.push(answer.as_ref().map(|a| a.display(ChalkIr)).to_string());
+            if answers.len() < count {
+                return true;
+            }
+            if has_next {
+                answers.push("...".to_string());
+            }
+            false
+        });
+        answers.join("\n")
+    }
+
+    /// Parse a domain goal and print the program clauses which could be
+    /// used to prove it.
+    fn clauses(&self, text: &str) -> Result<()> {
+        let program = self.check()?;
+        let goal = lower_goal(&*chalk_parse::parse_goal(text)?, &program)?;
+        let UCanonical {
+            canonical,
+            universes,
+        } = goal.into_peeled_goal(self.db.interner());
+        let InEnvironment { environment, goal } = canonical.value;
+        let goal = match goal.data(self.db.interner()) {
+            GoalData::DomainGoal(domain_goal) => domain_goal.clone(),
+            _ => return Err("`clauses` expects a domain goal, like `T: Trait`".into()),
+        };
+        let goal = UCanonical {
+            canonical: Canonical {
+                binders: canonical.binders,
+                value: InEnvironment::new(&environment, goal),
+            },
+            universes,
+        };
+
+        let clauses = program_clauses_for_goal(&self.db, &goal)
+            .map_err(|_| "the goal is too general to list the clauses for it")?;
+        print_clauses(&self.db, &clauses);
+        Ok(())
+    }
+
+    /// Parse a goal and print the clauses that hold in its environment, i.e.
+    /// its hypotheses and everything they imply.
+    fn env(&self, text: &str) -> Result<()> {
+        let program = self.check()?;
+        let goal = lower_goal(&*chalk_parse::parse_goal(text)?, &program)?;
+        let peeled_goal = goal.into_peeled_goal(self.db.interner());
+        let clauses = self
+            .db
+            .program_clauses_for_env(&peeled_goal.canonical.value.environment);
+        print_clauses(&self.db, clauses.as_slice(self.db.interner()));
+        Ok(())
+    }
+
+    /// Parse a goal and solve it with both the SLG and the recursive solver
+    /// (with their default settings), printing both solutions. Returns
+    /// whether they are the same.
+    fn compare(&self, text: &str) -> Result<bool> {
+        let program = self.check()?;
+        let goal = lower_goal(&*chalk_parse::parse_goal(text)?, &program)?;
+        let peeled_goal = goal.into_peeled_goal(self.db.interner());
+        let solutions: Vec<_> = [
+            ("slg", SolverChoice::slg_default()),
+            ("recursive", SolverChoice::recursive_default()),
+        ]
+        .iter()
+        .map(|&(name, solver_choice)| {
+            let solution = match solver_choice.into_solver().solve(&self.db, &peeled_goal) {
+                Some(v) => v.display(ChalkIr).to_string(),
+                None => "No possible solution.".to_string(),
+            };
+            println!("{:<10} {}", format!("{}:", name), solution);
+            solution
+        })
+        .collect();
+
+        let agree = solutions.windows(2).all(|pair| pair[0] == pair[1]);
+        if !agree {
+            println!("the solvers disagree on `{}`", text);
+        }
+        println!();
+        Ok(agree)
+    }
+
+    fn goal_text(
         &self,
         mut rl: Option<&mut rustyline::DefaultEditor>,
         text: &str,
         multiple_answers: bool,
     ) -> Result<()> {
-        let program = self.db.checked_program()?;
+        let program = self.check()?;
         let goal = lower_goal(&*chalk_parse::parse_goal(text)?, &*program)?;
         let peeled_goal = goal.into_peeled_goal(self.db.interner());
         if multiple_answers {
@@ -106,6 +408,36 @@ impl LoadedProgram {
         }
         Ok(())
     }
+
+    /// Like `goal_text`, but prints JSON. Errors are printed as a result
+    /// too, before being returned. With `multiple_answers`, every answer is
+    /// printed without asking.
+    fn goal_json(&self, text: &str, multiple_answers: bool) -> Result<()> {
+        let peeled_goal = self
+            .check()
+            .and_then(|program| {
+                let goal = lower_goal(&*chalk_parse::parse_goal(text)?, &program)?;
+                Ok(goal.into_peeled_goal(self.db.interner()))
+            })
+            .map_err(|e| {
+                JsonResult::error(Some(text), &*e).print();
+                e
+            })?;
+        if multiple_answers {
+            let mut any_answers = false;
+            self.db.solve_multiple(&peeled_goal, &mut |answer, _| {
+                any_answers = true;
+                JsonResult::answer(&self.db, text, &answer).print();
+                true
+            });
+            if !any_answers {
+                JsonResult::solution(&self.db, text, None).print();
+            }
+        } else {
+            JsonResult::solution(&self.db, text, self.db.solve(&peeled_goal).as_ref()).print();
+        }
+        Ok(())
+    }
 }
 
 fn run() -> Result<()> {
@@ -120,10 +452,14 @@ fn run() -> Result<()> {
         exit(1);
     }
 
+    if args.cmd_reduce {
+        return reduce_program(args);
+    }
+
     // Load the .chalk file, if given.
     let mut prog = None;
     if let Some(program) = &args.flag_program {
-        match load_program(args, program) {
+        match load_program(program, args.solver_choice()) {
             Ok(p) => prog = Some(p),
             Err(err) => {
                 eprintln!("error loading program: {}", err);
@@ -132,10 +468,17 @@ fn run() -> Result<()> {
         }
     }
 
-    if args.flag_goal.is_empty() {
+    if let Some(path) = &args.flag_goals {
+        let failures = check_goal_file(args, path)?;
+        if failures > 0 {
+            exit(1);
+        }
+        Ok(())
+    } else if args.flag_goal.is_empty() {
         // The user specified no goal. Enter interactive mode.
+        let mut solver_choice = args.solver_choice();
         readline_loop(&mut rustyline::Editor::new()?, "?- ", |rl, line| {
-            if let Err(e) = process(args, line, rl, &mut prog) {
+            if let Err(e) = process(args, line, rl, &mut prog, &mut solver_choice) {
                 eprintln!("error: {}", e);
             }
         })
@@ -145,23 +488,158 @@ fn run() -> Result<()> {
         // happens.
         let prog =
             prog.ok_or("error: cannot eval without a program; use `--program` to specify one.")?;
+        if let Err(e) = prog.check() {
+            match args.flag_format {
+                Format::Text => eprintln!("error: {}", e),
+                Format::Json => JsonResult::error(None, &*e).print(),
+            }
+            exit(1);
+        }
+        if let Some(path) = &args.flag_cache {
+            prog.load_cache(path)?;
+        }
 
         // Evaluate the goal(s). If any goal returns an error, print the error
-        // and exit.
+        // and exit. When comparing solvers, exit with an error at the end if
+        // they disagreed on any goal.
+        let mut disagreements = 0;
         prog.db.with_program(|_| -> Result<()> {
             for g in &args.flag_goal {
-                if let Err(e) = prog.goal(None, g, args.flag_multiple) {
-                    eprintln!("error: {}", e);
+                let result = if args.flag_compare {
+                    prog.compare(g).map(|agree| {
+                        if !agree {
+                            disagreements += 1;
+                        }
+                    })
+                } else {
+                    prog.goal(None, g, args.flag_multiple, args.flag_format)
+                };
+                if let Err(e) = result {
+                    // JSON results already include the error.
+                    if args.flag_format == Format::Text {
+                        eprintln!("error: {}", e);
+                    }
                     exit(1);
                 }
             }
             Ok(())
         })?;
+        if let Some(path) = &args.flag_cache {
+            prog.save_cache(path)?;
+        }
+        if disagreements > 0 {
+            exit(1);
+        }
 
         Ok(())
     }
 }
 
+/// Reduces the program given by `--program` for the `reduce` command, and
+/// prints the result.
+fn reduce_program(args: &Args) -> Result<()> {
+    let predicate = match args.arg_predicate.as_deref() {
+        Some("disagree") => Predicate::SolversDisagree,
+        Some("panics") => Predicate::Panics(args.solver_choice()),
+        Some("ambiguous") => Predicate::Ambiguous(args.solver_choice()),
+        Some(p) => return Err(format!("invalid predicate `{}`", p).into()),
+        None => unreachable!(),
+    };
+    let mut text = String::new();
+    File::open(args.flag_program.as_ref().unwrap())?.read_to_string(&mut text)?;
+
+    // Candidates that make the solvers panic would otherwise each print a
+    // panic message.
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let reduced = reduce(&text, &args.flag_goal[0], predicate);
+    panic::set_hook(hook);
+
+    print!("{}", reduced?);
+    Ok(())
+}
+
+/// Solves the goals in the goal file at `path` with each of the solvers, and
+/// reports the ones which don't yield the expected solution. Returns how many
+/// there were.
+///
+/// The program is the rest of the goal file, unless `--program` is given.
+fn check_goal_file(args: &Args, path: &str) -> Result<usize> {
+    let mut text = String::new();
+    File::open(path)?.read_to_string(&mut text)?;
+    let goal_file::GoalFile {
+        program,
+        expectations,
+    } = goal_file::parse(&text).map_err(|e| format!("error in goal file `{}`: {}", path, e))?;
+    let program_text = match &args.flag_program {
+        Some(program) => {
+            let mut text = String::new();
+            File::open(program)?.read_to_string(&mut text)?;
+            text
+        }
+        None => program,
+    };
+
+    let mut failures = 0;
+    let mut checked = 0;
+    for (solver, solver_choice) in args.solver_choices() {
+        let prog = LoadedProgram::new(program_text.clone(), solver_choice)?;
+        prog.check()?;
+        if let Some(path) = &args.flag_cache {
+            prog.load_cache(path)?;
+        }
+        prog.db.with_program(|program| {
+            for expectation in &expectations {
+                // Only the SLG solver can give multiple answers.
+                if expectation.all_answers
+                    && matches!(solver_choice, SolverChoice::Recursive { .. })
+                {
+                    continue;
+                }
+                checked += 1;
+                let goal = || -> Result<_> {
+                    Ok(lower_goal(
+                        &*chalk_parse::parse_goal(&expectation.goal)?,
+                        program,
+                    )?)
+                };
+                let actual = match goal() {
+                    Ok(goal) => {
+                        let peeled_goal = goal.into_peeled_goal(prog.db.interner());
+                        if expectation.all_answers {
+                            prog.answers(&peeled_goal, expectation.answer_count())
+                        } else {
+                            match prog.db.solve(&peeled_goal) {
+                                Some(v) => v.display(ChalkIr).to_string(),
+                                None => "No possible solution".to_string(),
+                            }
+                        }
+                    }
+                    Err(e) => format!("error: {}", e),
+                };
+                if !expectation.matches(&actual) {
+                    failures += 1;
+                    println!(
+                        "{}:{}: goal `{}` failed with the {} solver\n  expected: {}\n  actual: {}",
+                        path,
+                        expectation.line,
+                        expectation.goal,
+                        solver,
+                        expectation.expected,
+                        actual
+                    );
+                }
+            }
+        });
+        if let Some(path) = &args.flag_cache {
+            prog.save_cache(path)?;
+        }
+    }
+
+    println!("{} of {} goals passed", checked - failures, checked);
+    Ok(failures)
+}
+
 /// Reads input lines from the user. Lines start with the string given by `prompt`.
 /// Each line the user enters is passed to the function `f` for processing.
 ///
@@ -193,7 +671,8 @@ where
 }
 
 /// Process a single command. `args` is a struct containing the command-line
-/// arguments, and `prog` is a parsed `.chalk` file.
+/// arguments, `prog` is a parsed `.chalk` file, and `solver_choice` is the
+/// solver to use, which starts out as the one given by `args`.
 // TODO: Could we pass in an Options struct or something? The Args struct
 // still has Strings where it should have Enums... (e.g. solver_choice)
 fn process(
@@ -201,6 +680,7 @@ fn process(
     command: &str,
     rl: &mut rustyline::DefaultEditor,
     prog: &mut Option<LoadedProgram>,
+    solver_choice: &mut SolverChoice,
 ) -> Result<()> {
     if command.is_empty() {
         // Ignore empty commands.
@@ -210,16 +690,36 @@ fn process(
         help()
     } else if command == "program" {
         // Load a .chalk file via stdin, until EOF is found.
-        let chalk_prog = LoadedProgram::new(read_program(rl)?, args.solver_choice())?;
+        let chalk_prog = LoadedProgram::new(read_program(rl)?, *solver_choice)?;
         // Let's do a sanity check before going forward.
-        let _ = chalk_prog.db.checked_program()?;
+        let _ = chalk_prog.check()?;
         *prog = Some(chalk_prog);
     } else if let Some(filename) = command.strip_prefix("load ") {
         // Load a .chalk file.
-        let chalk_prog = load_program(args, filename)?;
+        let chalk_prog = load_program(filename, *solver_choice)?;
         // Let's do a sanity check before going forward.
-        let _ = chalk_prog.db.checked_program()?;
+        let _ = chalk_prog.check()?;
         *prog = Some(chalk_prog);
+    } else if command == "solver" || command.starts_with("solver ") || command.starts_with("set ") {
+        // Change the solver, or one of its limits. The program is kept, but
+        // the solver starts over with an empty cache.
+        match command.split_whitespace().collect::<Vec<_>>()[..] {
+            ["solver"] => {}
+            ["solver", solver @ ("slg" | "recursive")] => {
+                *solver_choice = args.parse_solver(solver)
+            }
+            ["set", option, value] => {
+                let value = value
+                    .parse()
+                    .map_err(|_| format!("expected a number, found `{}`", value))?;
+                set_solver_option(solver_choice, option, value)?
+            }
+            _ => return Err("usage: `solver slg|recursive` or `set <option> <n>`".into()),
+        }
+        if let Some(prog) = prog {
+            prog.db.set_solver_choice(*solver_choice);
+        }
+        println!("{:?}", solver_choice);
     } else if let Some(level) = command.strip_prefix("debug ") {
         if level.is_empty() {
             println!("debug <level> set debug level to <level>");
@@ -234,7 +734,9 @@ fn process(
             .as_ref()
             .ok_or("no program currently loaded; type 'help' to see available commands")?;
 
-        // Attempt to parse the program.
+        // Attempt to parse the program. The checks are done again if the
+        // solver was changed, and may fail with the new one.
+        prog.check()?;
         prog.db.with_program(|_| -> Result<()> {
             match command {
                 // Print out the loaded program.
@@ -243,10 +745,26 @@ fn process(
                 // TODO: Write a line of documentation here.
                 "lowered" => println!("{:#?}", prog.db.environment()),
 
-                // Assume this is a goal.
-                // TODO: Print out "type 'help' to see available commands" if it
-                // fails to parse?
-                _ => prog.goal(Some(rl), command, args.flag_multiple)?,
+                // Print out (or reset) what the solver has done so far.
+                "stats" => match prog.db.solver_stats() {
+                    Some(stats) => println!("{}", stats),
+                    None => println!("(no statistics collected)"),
+                },
+                "stats reset" => prog.db.reset_solver_stats(),
+
+                _ => match command.split_once(' ') {
+                    // Print out the clauses for a goal, or for its environment.
+                    Some(("clauses", goal)) => prog.clauses(goal)?,
+                    Some(("env", goal)) => prog.env(goal)?,
+
+                    // Assume this is a goal.
+                    // TODO: Print out "type 'help' to see available commands" if it
+                    // fails to parse?
+                    _ if args.flag_compare => {
+                        prog.compare(command)?;
+                    }
+                    _ => prog.goal(Some(rl), command, args.flag_multiple, args.flag_format)?,
+                },
             }
             Ok(())
         })?
@@ -256,25 +774,64 @@ fn process(
 }
 
 /// Load the file into a string, and parse it.
-// TODO: Could we pass in an Options struct or something? The Args struct
-// still has Strings where it should have Enums... (e.g. solver_choice)
-fn load_program(args: &Args, filename: &str) -> Result<LoadedProgram> {
+fn load_program(filename: &str, solver_choice: SolverChoice) -> Result<LoadedProgram> {
     let mut text = String::new();
     File::open(filename)?.read_to_string(&mut text)?;
-    LoadedProgram::new(text, args.solver_choice())
+    LoadedProgram::new(text, solver_choice)
+}
+
+/// Sets one of the limits of a solver, for the `set` command.
+fn set_solver_option(solver_choice: &mut SolverChoice, option: &str, value: usize) -> Result<()> {
+    match (solver_choice, option) {
+        (SolverChoice::SLG { max_size, .. }, "max_size")
+        | (SolverChoice::Recursive { max_size, .. }, "max_size") => *max_size = value,
+        (
+            SolverChoice::SLG {
+                expected_answers, ..
+            },
+            "expected_answers",
+        ) => *expected_answers = Some(value),
+        (SolverChoice::Recursive { overflow_depth, .. }, "overflow_depth") => {
+            if value == 0 {
+                return Err("overflow depth must be at least 1".into());
+            }
+            *overflow_depth = value
+        }
+        (_, "expected_answers" | "overflow_depth") => {
+            return Err(format!("`{}` doesn't apply to the current solver", option).into())
+        }
+        _ => return Err(format!("unknown option `{}`", option).into()),
+    }
+    Ok(())
 }
 
 /// Print out help for commands in interpreter mode.
 // TODO: Implement "help <command>" for more info.
 fn help() {
     println!("Commands:");
-    println!("  help          print this output");
-    println!("  program       provide a program via stdin");
-    println!("  load <file>   load program from <file>");
-    println!("  print         print the current program");
-    println!("  lowered       print the lowered program");
-    println!("  <goal>        attempt to solve <goal>");
-    println!("  debug <level> set debug level to <level>");
+    println!("  help           print this output");
+    println!("  program        provide a program via stdin");
+    println!("  load <file>    load program from <file>");
+    println!("  print          print the current program");
+    println!("  lowered        print the lowered program");
+    println!("  <goal>         attempt to solve <goal>");
+    println!("  clauses <goal> print the program clauses that could prove <goal>");
+    println!("  env <goal>     print the clauses that hold in the environment of <goal>");
+    println!("  solver <name>  switch to the `slg` or `recursive` solver");
+    println!("  set <opt> <n>  set `max_size`, `overflow_depth` or `expected_answers` to <n>");
+    println!("  stats          print what the solver did since the program or solver changed");
+    println!("  stats reset    reset the numbers printed by `stats`");
+    println!("  debug <level>  set debug level to <level>");
+}
+
+/// Print program clauses, one per line.
+fn print_clauses(db: &dyn RustIrDatabase<ChalkIr>, clauses: &[ProgramClause<ChalkIr>]) {
+    if clauses.is_empty() {
+        println!("(no clauses)");
+    }
+    for clause in clauses {
+        println!("{}", display::display_value(db, clause));
+    }
 }
 
 /// Read a program from the command-line. Stop reading when EOF is read. If
@@ -291,17 +848,28 @@ fn read_program(rl: &mut rustyline::DefaultEditor) -> Result<String> {
 
 impl Args {
     fn solver_choice(&self) -> SolverChoice {
-        match self.flag_solver.as_deref() {
-            None | Some("slg") => SolverChoice::SLG {
-                max_size: self.flag_overflow_depth,
-                expected_answers: None,
-            },
-            Some("recursive") => SolverChoice::Recursive {
+        self.parse_solver(self.flag_solver.as_deref().unwrap_or("slg"))
+    }
+
+    /// The solvers to check goal files with, along with their names.
+    fn solver_choices(&self) -> Vec<(&str, SolverChoice)> {
+        self.flag_solver
+            .as_deref()
+            .unwrap_or("slg,recursive")
+            .split(',')
+            .map(|solver| (solver, self.parse_solver(solver)))
+            .collect()
+    }
+
+    fn parse_solver(&self, solver: &str) -> SolverChoice {
+        match solver {
+            "slg" => SolverChoice::slg(self.flag_overflow_depth, None),
+            "recursive" => SolverChoice::Recursive {
                 overflow_depth: 100,
                 caching_enabled: true,
                 max_size: 30,
             },
-            Some(s) => panic!("invalid solver {}", s),
+            s => panic!("invalid solver {}", s),
         }
     }
 }
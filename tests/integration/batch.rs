@@ -0,0 +1,92 @@
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::SolverChoice;
+use chalk_ir::{Goal, InEnvironment, UCanonical};
+use chalk_solve::ext::*;
+use chalk_solve::RustIrDatabase;
+
+const PROGRAM: &str = "
+    struct Foo { }
+    struct Baz { }
+    struct Vec<T> { }
+    struct Rc<T> { }
+    trait Bar { }
+    #[coinductive]
+    trait Send where Self: Sync { }
+    #[coinductive]
+    trait Sync where Self: Send { }
+    impl Bar for Foo { }
+    impl<T> Bar for Vec<T> where T: Bar { }
+    impl<T> Bar for Rc<T> where T: Bar { }
+    impl<T> Send for Vec<T> where T: Send { }
+    impl<T> Sync for Vec<T> where T: Sync { }
+    impl Send for Foo { }
+    impl Sync for Foo { }
+";
+
+/// Enough goals for more than one round of the batch solver, many of them
+/// sharing subgoals.
+fn goals(db: &ChalkDatabase) -> Vec<UCanonical<InEnvironment<Goal<ChalkIr>>>> {
+    let mut goals = vec![];
+    for depth in 0..8 {
+        for (inner, outer) in [("Foo", "Vec"), ("Baz", "Rc"), ("Foo", "Rc")] {
+            let ty = (0..depth).fold(inner.to_string(), |ty, _| format!("{}<{}>", outer, ty));
+            for trait_name in ["Bar", "Send", "Sync"] {
+                goals.push(format!("{}: {}", ty, trait_name));
+            }
+        }
+    }
+    goals.push("exists<T> { Vec<T>: Bar }".to_string());
+    goals
+        .iter()
+        .map(|goal| {
+            let goal = db.parse_and_lower_goal(goal).unwrap();
+            goal.into_peeled_goal(db.interner())
+        })
+        .collect()
+}
+
+fn solve_batch(goals: &[UCanonical<InEnvironment<Goal<ChalkIr>>>], threads: usize) -> Vec<String> {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive_default());
+    let solutions = db.solve_batch(goals, threads);
+    assert!(!db.solver_cache().unwrap().is_empty());
+    db.with_program(|_| {
+        solutions
+            .iter()
+            .map(|solution| match solution {
+                Some(solution) => solution.display(ChalkIr).to_string(),
+                None => "No possible solution".to_string(),
+            })
+            .collect()
+    })
+}
+
+#[test]
+fn solutions_do_not_depend_on_thread_count() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive_default());
+    let goals = goals(&db);
+    let serial = solve_batch(&goals, 1);
+    assert_eq!(solve_batch(&goals, 2), serial);
+    assert_eq!(solve_batch(&goals, 8), serial);
+}
+
+#[test]
+fn solutions_match_solving_one_goal_at_a_time() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive_default());
+    let goals = goals(&db);
+    let batch = db.solve_batch(&goals, 4);
+
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive_default());
+    let one_at_a_time: Vec<_> = goals.iter().map(|goal| db.solve(goal)).collect();
+    assert_eq!(batch, one_at_a_time);
+}
+
+#[test]
+fn other_solvers_solve_goals_one_at_a_time() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::slg_default());
+    let goals = &goals(&db)[..9];
+    let batch = db.solve_batch(goals, 4);
+    let one_at_a_time: Vec<_> = goals.iter().map(|goal| db.solve(goal)).collect();
+    assert_eq!(batch, one_at_a_time);
+}
@@ -0,0 +1,106 @@
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::SolverChoice;
+use chalk_ir::{Goal, InEnvironment, UCanonical};
+use chalk_solve::ext::*;
+use chalk_solve::{Budget, BudgetExhausted, RustIrDatabase};
+use std::time::Instant;
+
+const PROGRAM: &str = "
+    struct A { }
+    struct B { }
+    struct C { }
+    struct D { }
+    struct E { }
+    struct Vec<T> { }
+    trait Bar { }
+    impl Bar for A { }
+    impl Bar for B { }
+    impl Bar for C { }
+    impl Bar for D { }
+    impl Bar for E { }
+    impl<T> Bar for Vec<T> where T: Bar { }
+    trait Baz { }
+    trait Qux { }
+    impl<T> Baz for Vec<T> where T: Qux { }
+    impl Qux for E { }
+";
+
+/// Only one of the types that implement `Bar` works, so the solvers have to
+/// try out several.
+const GOAL: &str = "exists<T> { T: Bar, Vec<T>: Baz }";
+
+fn goal(db: &ChalkDatabase) -> UCanonical<InEnvironment<Goal<ChalkIr>>> {
+    let goal = db.parse_and_lower_goal(GOAL).unwrap();
+    goal.into_peeled_goal(db.interner())
+}
+
+/// Solves the goal again and again with a budget of `steps`, returning the
+/// solution once one is found. The budgets used in the tests are too small
+/// to solve the goal in one go, so this only finishes if each attempt picks
+/// up where the last one left off.
+fn solve_in_steps(solver_choice: SolverChoice, steps: usize) -> String {
+    let db = ChalkDatabase::with(PROGRAM, solver_choice);
+    let goal = goal(&db);
+    let budget = Budget::unlimited().with_steps(steps);
+    assert_eq!(db.solve_with_budget(&goal, &budget), Err(BudgetExhausted));
+    for _ in 0..100 {
+        if let Ok(solution) = db.solve_with_budget(&goal, &budget) {
+            return db.with_program(|_| solution.unwrap().display(ChalkIr).to_string());
+        }
+    }
+    panic!("no progress was made with a budget of {} steps", steps);
+}
+
+fn solve_unlimited(solver_choice: SolverChoice) -> String {
+    let db = ChalkDatabase::with(PROGRAM, solver_choice);
+    let solution = db.solve_with_budget(&goal(&db), &Budget::unlimited());
+    db.with_program(|_| solution.unwrap().unwrap().display(ChalkIr).to_string())
+}
+
+#[test]
+fn slg_resumes_from_its_tables() {
+    let solver_choice = SolverChoice::slg_default();
+    assert_eq!(
+        solve_in_steps(solver_choice, 20),
+        solve_unlimited(solver_choice)
+    );
+}
+
+#[test]
+fn recursive_resumes_from_its_cache() {
+    let solver_choice = SolverChoice::recursive_default();
+    assert_eq!(
+        solve_in_steps(solver_choice, 5),
+        solve_unlimited(solver_choice)
+    );
+}
+
+#[test]
+fn exhausted_budget_does_not_affect_later_solutions() {
+    for solver_choice in [
+        SolverChoice::slg_default(),
+        SolverChoice::recursive_default(),
+    ] {
+        let db = ChalkDatabase::with(PROGRAM, solver_choice);
+        let goal = goal(&db);
+        let budget = Budget::unlimited().with_steps(1);
+        assert_eq!(db.solve_with_budget(&goal, &budget), Err(BudgetExhausted));
+        assert_eq!(
+            db.solve_with_budget(&goal, &Budget::unlimited()),
+            Ok(db.solve(&goal))
+        );
+        let solution = db.with_program(|_| db.solve(&goal).unwrap().display(ChalkIr).to_string());
+        assert_eq!(solution, solve_unlimited(solver_choice));
+    }
+}
+
+#[test]
+fn deadlines() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive_default());
+    let goal = goal(&db);
+    let passed = Budget::unlimited().with_deadline(Instant::now());
+    assert_eq!(db.solve_with_budget(&goal, &passed), Err(BudgetExhausted));
+    let budget = Budget::unlimited().with_timeout(std::time::Duration::from_secs(600));
+    assert!(db.solve_with_budget(&goal, &budget).unwrap().is_some());
+}
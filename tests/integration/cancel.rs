@@ -0,0 +1,87 @@
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::SolverChoice;
+use chalk_ir::{Goal, InEnvironment, UCanonical};
+use chalk_solve::ext::*;
+use chalk_solve::{CancellationToken, Cancelled, RustIrDatabase};
+use std::thread;
+
+const PROGRAM: &str = "
+    struct A { }
+    struct B { }
+    struct C { }
+    struct Vec<T> { }
+    trait Bar { }
+    impl Bar for A { }
+    impl Bar for B { }
+    impl<T> Bar for Vec<T> where T: Bar { }
+    trait Baz { }
+    trait Qux { }
+    impl<T> Baz for Vec<T> where T: Qux { }
+    impl Qux for B { }
+";
+
+const GOAL: &str = "exists<T> { T: Bar, Vec<T>: Baz }";
+
+fn goal(db: &ChalkDatabase) -> UCanonical<InEnvironment<Goal<ChalkIr>>> {
+    let goal = db.parse_and_lower_goal(GOAL).unwrap();
+    goal.into_peeled_goal(db.interner())
+}
+
+fn display(db: &ChalkDatabase, solution: Option<chalk_solve::Solution<ChalkIr>>) -> String {
+    db.with_program(|_| solution.unwrap().display(ChalkIr).to_string())
+}
+
+fn for_each_solver(test: impl Fn(SolverChoice)) {
+    test(SolverChoice::slg_default());
+    test(SolverChoice::recursive_default());
+}
+
+#[test]
+fn uncancelled_solve_finds_the_solution() {
+    for_each_solver(|solver_choice| {
+        let db = ChalkDatabase::with(PROGRAM, solver_choice);
+        let goal = goal(&db);
+        let solution = db.solve_cancellable(&goal, &CancellationToken::new());
+        let expected = ChalkDatabase::with(PROGRAM, solver_choice).solve(&goal);
+        assert_eq!(display(&db, solution.unwrap()), display(&db, expected));
+    });
+}
+
+#[test]
+fn cancelled_solve_reports_cancelled() {
+    for_each_solver(|solver_choice| {
+        let db = ChalkDatabase::with(PROGRAM, solver_choice);
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        assert_eq!(
+            db.solve_cancellable(&goal(&db), &cancellation),
+            Err(Cancelled)
+        );
+    });
+}
+
+#[test]
+fn solver_recovers_from_cancellation() {
+    for_each_solver(|solver_choice| {
+        let db = ChalkDatabase::with(PROGRAM, solver_choice);
+        let goal = goal(&db);
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        assert_eq!(db.solve_cancellable(&goal, &cancellation), Err(Cancelled));
+
+        // The solve may or may not be cancelled before it finishes, but
+        // either way the solver must still work afterwards.
+        let cancellation = CancellationToken::new();
+        let canceller = {
+            let cancellation = cancellation.clone();
+            thread::spawn(move || cancellation.cancel())
+        };
+        let _ = db.solve_cancellable(&goal, &cancellation);
+        canceller.join().unwrap();
+
+        let solution = db.solve(&goal);
+        let expected = ChalkDatabase::with(PROGRAM, solver_choice).solve(&goal);
+        assert_eq!(display(&db, solution), display(&db, expected));
+    });
+}
@@ -0,0 +1,93 @@
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::SolverChoice;
+use chalk_solve::ext::*;
+use chalk_solve::{Answers, RustIrDatabase};
+
+const PROGRAM: &str = "
+    struct A { }
+    struct B { }
+    struct C { }
+    struct Box<T> { }
+    struct Vec<T> { }
+    trait Foo { }
+    trait Bar { }
+    trait Baz { }
+    impl Foo for B { }
+    impl Foo for A { }
+    impl<T> Foo for Box<T> where T: Bar { }
+    impl Bar for C { }
+    impl Baz for A { }
+    impl<T> Baz for Vec<T> where T: Baz { }
+";
+
+/// Enumerates the answers to `goal` with each solver, checks that they
+/// agree, and returns the answers (as strings) and whether they are all of
+/// them.
+fn enumerate(goal: &str, limit: usize) -> (Vec<String>, bool) {
+    let enumerate_with = |solver_choice| {
+        let db = ChalkDatabase::with(PROGRAM, solver_choice);
+        db.with_program(|_| {
+            let goal = db.parse_and_lower_goal(goal).unwrap();
+            let answers = db.enumerate_answers(&goal.into_peeled_goal(db.interner()), limit);
+            display(&db, answers)
+        })
+    };
+    let slg = enumerate_with(SolverChoice::slg_default());
+    let recursive = enumerate_with(SolverChoice::recursive_default());
+    assert_eq!(slg, recursive);
+    slg
+}
+
+fn display(db: &ChalkDatabase, answers: Answers<ChalkIr>) -> (Vec<String>, bool) {
+    let strings = answers
+        .answers
+        .iter()
+        .map(|answer| {
+            assert!(!answer.ambiguous);
+            answer.subst.display(db.interner()).to_string()
+        })
+        .collect();
+    (strings, answers.complete)
+}
+
+#[test]
+fn answers_are_ordered() {
+    let (answers, complete) = enumerate("exists<T> { T: Foo }", 10);
+    assert_eq!(
+        answers,
+        [
+            "substitution [?0 := A]",
+            "substitution [?0 := B]",
+            "substitution [?0 := Box<C>]",
+        ]
+    );
+    assert!(complete);
+}
+
+#[test]
+fn answers_are_limited() {
+    let (answers, complete) = enumerate("exists<T> { T: Foo }", 2);
+    assert_eq!(answers.len(), 2);
+    assert!(!complete);
+
+    let (answers, complete) = enumerate("exists<T> { T: Baz }", 3);
+    assert_eq!(
+        answers,
+        [
+            "substitution [?0 := A]",
+            "substitution [?0 := Vec<A>]",
+            "substitution [?0 := Vec<Vec<A>>]",
+        ]
+    );
+    assert!(!complete);
+}
+
+#[test]
+fn answers_are_deduplicated() {
+    // `A: Foo` holds both because of the impl and because it is assumed, but
+    // it's one answer.
+    let (answers, complete) = enumerate("exists<T> { if (A: Foo) { T: Foo } }", 10);
+    assert_eq!(answers.len(), 3);
+    assert!(complete);
+}
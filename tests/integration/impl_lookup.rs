@@ -0,0 +1,100 @@
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::SolverChoice;
+use chalk_ir::{Canonical, DomainGoal, GoalData, WhereClause};
+use chalk_solve::ext::*;
+use chalk_solve::impl_lookup::ImplBound;
+use chalk_solve::RustIrDatabase;
+
+const PROGRAM: &str = "
+    trait Clone { }
+    trait Iterator { type Item; }
+    struct Foo { }
+    struct Bar<T> { }
+    struct Baz { }
+    impl Iterator for Foo { type Item = u32; }
+    impl<T> Iterator for Bar<T> where T: Clone { type Item = T; }
+    impl Iterator for Baz { type Item = i32; }
+";
+
+/// Turns a goal like `exists<T> { T: Iterator<Item = u32> }` into the bound
+/// it requires.
+fn bound(db: &ChalkDatabase, goal: &str) -> Canonical<ImplBound<ChalkIr>> {
+    let interner = db.interner();
+    let goal = db.parse_and_lower_goal(goal).unwrap();
+    let canonical = goal.into_peeled_goal(interner).canonical;
+    let mut trait_ref = None;
+    let mut associated_ty_values = vec![];
+    let mut goals = vec![canonical.value.goal.clone()];
+    while let Some(goal) = goals.pop() {
+        match goal.data(interner) {
+            GoalData::All(all) => goals.extend(all.iter(interner).cloned()),
+            GoalData::DomainGoal(DomainGoal::Holds(WhereClause::Implemented(t))) => {
+                trait_ref = Some(t.clone())
+            }
+            GoalData::DomainGoal(DomainGoal::Holds(WhereClause::AliasEq(a))) => {
+                associated_ty_values.push(a.clone())
+            }
+            _ => panic!("unexpected goal {:?}", goal),
+        }
+    }
+    Canonical {
+        binders: canonical.binders,
+        value: ImplBound {
+            trait_ref: trait_ref.unwrap(),
+            associated_ty_values,
+        },
+    }
+}
+
+/// Looks up the impls for the bound given by `goal`, and returns the values
+/// they give its variables and their where clauses, as strings.
+fn lookup(goal: &str) -> Vec<(String, String)> {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    db.with_program(|_| {
+        let mut impls: Vec<_> = db
+            .matching_impls(&bound(&db, goal))
+            .into_iter()
+            .map(|matching| {
+                let residual = matching.residual.value;
+                assert!(residual.goals.is_empty());
+                (
+                    format!("{:?}", residual.subst),
+                    format!("{:?}", residual.where_clauses),
+                )
+            })
+            .collect();
+        impls.sort();
+        impls
+    })
+}
+
+#[test]
+fn matching_impls() {
+    assert_eq!(
+        lookup("exists<T> { T: Iterator }"),
+        [
+            (
+                "[?0 := Bar<^0.0>]".to_string(),
+                "[for<> Implemented(^1.0: Clone)]".to_string()
+            ),
+            ("[?0 := Baz]".to_string(), "[]".to_string()),
+            ("[?0 := Foo]".to_string(), "[]".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn matching_impls_with_associated_types() {
+    assert_eq!(
+        lookup("exists<T> { T: Iterator<Item = u32> }"),
+        [
+            (
+                "[?0 := Bar<Uint(U32)>]".to_string(),
+                "[for<> Implemented(Uint(U32): Clone)]".to_string()
+            ),
+            ("[?0 := Foo]".to_string(), "[]".to_string()),
+        ]
+    );
+    assert_eq!(lookup("Bar<u32>: Iterator<Item = i32>"), []);
+}
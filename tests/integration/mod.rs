@@ -1 +1,9 @@
+mod batch;
+mod budget;
+mod cancel;
+mod enumerate;
+mod impl_lookup;
 mod panic;
+mod reduce;
+mod solver_cache;
+mod stats;
@@ -0,0 +1,58 @@
+use chalk_integration::reduce::{reduce, reduce_with, Predicate};
+use chalk_integration::SolverChoice;
+
+const PROGRAM: &str = "
+    // A comment with a { brace.
+    #[upstream]
+    struct Foo<T, U> where T: Bar<U>, U: Baz<T, Foo<T, U>> { a: T, b: Vec<U> }
+    trait Bar<T> where T: Baz<(), ()> { type Item<U>: Baz<U> where U: Bar<()>; }
+    impl<T> Bar<T> for Foo<T> { type Item<U> = (); }
+    fn f<T>(x: T) -> T where T: Bar<()>;
+";
+
+#[test]
+fn removes_all_it_can() {
+    let reduced = reduce_with(PROGRAM, |text| {
+        text.contains("impl<T> Bar<T>") && text.contains("fn")
+    });
+    assert_eq!(
+        reduced,
+        "    impl<T> Bar<T> for Foo<T> {  }\n    fn f(x: T) -> T ;\n"
+    );
+}
+
+#[test]
+fn keeps_ambiguity() {
+    let program = "
+        struct A { }
+        struct B { }
+        struct C<T> where T: Copy { }
+        trait Copy { }
+        trait Foo { }
+        trait Bar<T> { }
+        impl Copy for A { }
+        impl Foo for A { }
+        impl Foo for B { }
+        impl<T> Bar<T> for C<T> where T: Copy, T: Foo { }
+    ";
+    let predicate = Predicate::Ambiguous(SolverChoice::default());
+    let reduced = reduce(program, "exists<T> { T: Foo }", predicate).unwrap();
+    assert_eq!(
+        reduced,
+        "        struct A { }\n        struct B { }\n        trait Foo { }\n        \
+         impl Foo for A { }\n        impl Foo for B { }\n"
+    );
+    assert!(predicate.holds(&reduced, "exists<T> { T: Foo }"));
+}
+
+#[test]
+fn predicate_must_hold() {
+    let program = "
+        struct A { }
+        trait Foo { }
+        impl Foo for A { }
+    ";
+    let predicate = Predicate::Ambiguous(SolverChoice::default());
+    assert!(reduce(program, "exists<T> { T: Foo }", predicate).is_err());
+    assert!(!predicate.holds("struct A {", "exists<T> { T: Foo }"));
+}
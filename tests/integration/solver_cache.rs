@@ -0,0 +1,175 @@
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::SolverChoice;
+use chalk_solve::ext::*;
+use chalk_solve::RustIrDatabase;
+use chalk_solve::Solution;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+const PROGRAM: &str = "
+    struct Foo { }
+    struct Vec<T> { }
+    trait Bar { }
+    impl Bar for Foo { }
+    impl<T> Bar for Vec<T> where T: Bar { }
+";
+
+fn cache_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "chalk-solver-cache-{}-{}.json",
+        name,
+        std::process::id()
+    ))
+}
+
+fn solve(db: &ChalkDatabase, goal: &str) -> Option<Solution<ChalkIr>> {
+    let goal = db.parse_and_lower_goal(goal).unwrap();
+    db.solve(&goal.into_peeled_goal(db.interner()))
+}
+
+#[test]
+fn saved_results_are_loaded() {
+    let path = cache_path("loaded");
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive_default());
+    let solution = solve(&db, "Vec<Vec<Foo>>: Bar");
+    let saved = db.save_solver_cache(&path).unwrap();
+    assert!(saved > 0);
+
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive_default());
+    assert_eq!(db.load_solver_cache(&path).unwrap(), saved);
+    // The loaded results are found for the goals they were saved for.
+    let goal = db.parse_and_lower_goal("Vec<Vec<Foo>>: Bar").unwrap();
+    let cached = db
+        .solver_cache()
+        .unwrap()
+        .get(&goal.into_peeled_goal(db.interner()));
+    assert_eq!(cached, Some(solution.ok_or(chalk_ir::NoSolution)));
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn loaded_results_are_not_solved_again() {
+    let path = cache_path("warm");
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive_default());
+    let solution = solve(&db, "Vec<Vec<Foo>>: Bar");
+    assert!(db.save_solver_cache(&path).unwrap() > 0);
+
+    let mut db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive_default());
+    db.set_collect_solver_stats(true);
+    assert!(db.load_solver_cache(&path).unwrap() > 0);
+    assert_eq!(solve(&db, "Vec<Vec<Foo>>: Bar"), solution);
+    let stats = db.solver_stats().unwrap();
+    assert_eq!(stats.cache_hits, 1);
+    assert_eq!(stats.tables_created, 0);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn results_for_other_programs_are_ignored() {
+    let path = cache_path("ignored");
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive_default());
+    solve(&db, "Vec<Foo>: Bar");
+    assert!(db.save_solver_cache(&path).unwrap() > 0);
+
+    let program = format!("{} struct Baz {{ }}", PROGRAM);
+    let db = ChalkDatabase::with(&program, SolverChoice::recursive_default());
+    assert_eq!(db.load_solver_cache(&path).unwrap(), 0);
+
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive(10, 10));
+    assert_eq!(db.load_solver_cache(&path).unwrap(), 0);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn slg_saves_nothing() {
+    let path = cache_path("slg");
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::slg_default());
+    solve(&db, "Vec<Foo>: Bar");
+    assert_eq!(db.save_solver_cache(&path).unwrap(), 0);
+    assert!(!path.exists());
+}
+
+const EDITED_PROGRAM: &str = "
+    struct Foo { }
+    struct Vec<T> { }
+    trait Bar { }
+    impl Bar for Foo { }
+    impl<T> Bar for Vec<T> where T: Bar { }
+    trait Baz { }
+    impl<T> Baz for T where T: Bar { }
+";
+
+/// Checks, like the solver does, whether the cached result for `goal` still
+/// holds for the program `db` has now.
+fn cached_result_holds(db: &ChalkDatabase, goal: &str) -> bool {
+    let goal = db.parse_and_lower_goal(goal).unwrap();
+    let cache = db.solver_cache().unwrap();
+    let result = cache.get_checked(&goal.into_peeled_goal(db.interner()), |reads| {
+        reads.still_hold(db)
+    });
+    result.is_some()
+}
+
+#[test]
+fn program_changes_only_evict_affected_results() {
+    let program = "
+        struct Foo { }
+        struct Baz { }
+        struct Vec<T> { }
+        trait Bar { }
+        trait Qux { }
+        impl Bar for Foo { }
+        impl<T> Bar for Vec<T> where T: Bar { }
+        impl Qux for Foo { }
+    ";
+    let mut db = ChalkDatabase::with(program, SolverChoice::recursive_default());
+    assert!(solve(&db, "Foo: Qux").is_some());
+    assert!(solve(&db, "Vec<Foo>: Bar").is_some());
+    assert!(solve(&db, "Vec<Baz>: Bar").is_none());
+
+    let program = format!("{} impl Bar for Baz {{ }}", program);
+    db.set_program_text(Arc::new(program));
+    // The new impl does not apply to `Foo`, so only the results that looked
+    // for impls of `Bar` for `Baz` are affected.
+    assert!(cached_result_holds(&db, "Foo: Qux"));
+    assert!(cached_result_holds(&db, "Vec<Foo>: Bar"));
+    assert!(!cached_result_holds(&db, "Vec<Baz>: Bar"));
+    assert!(solve(&db, "Vec<Baz>: Bar").is_some());
+}
+
+#[test]
+fn results_are_solved_again_for_changed_programs() {
+    let mut db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive_default());
+    assert!(solve(&db, "Vec<Foo>: Bar").is_some());
+    assert!(solve(&db, "Vec<Vec<Foo>>: Bar").is_some());
+
+    db.set_program_text(Arc::new(EDITED_PROGRAM.replace("impl Bar for Foo { }", "")));
+    assert!(solve(&db, "Vec<Foo>: Bar").is_none());
+    assert!(solve(&db, "Vec<Vec<Foo>>: Bar").is_none());
+
+    db.set_program_text(Arc::new(EDITED_PROGRAM.to_string()));
+    assert!(solve(&db, "Vec<Vec<Foo>>: Baz").is_some());
+}
+
+#[test]
+fn loaded_results_are_evicted_when_the_program_changes() {
+    let path = cache_path("evicted");
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive_default());
+    solve(&db, "Foo: Bar");
+    assert!(db.save_solver_cache(&path).unwrap() > 0);
+
+    let mut db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive_default());
+    assert!(db.load_solver_cache(&path).unwrap() > 0);
+    assert!(cached_result_holds(&db, "Foo: Bar"));
+
+    db.set_program_text(Arc::new(EDITED_PROGRAM.to_string()));
+    assert!(!cached_result_holds(&db, "Foo: Bar"));
+
+    fs::remove_file(&path).unwrap();
+}
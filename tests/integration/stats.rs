@@ -0,0 +1,83 @@
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::SolverChoice;
+use chalk_solve::ext::*;
+use chalk_solve::{RustIrDatabase, SolverStats};
+
+const PROGRAM: &str = "
+    struct Foo { }
+    struct Vec<T> { }
+    trait Bar { }
+    impl Bar for Foo { }
+    impl<T> Bar for Vec<T> where T: Bar { }
+";
+
+fn db(solver_choice: SolverChoice) -> ChalkDatabase {
+    let mut db = ChalkDatabase::with(PROGRAM, solver_choice);
+    db.set_collect_solver_stats(true);
+    db
+}
+
+/// Solves `goal` and returns the statistics collected so far.
+fn solve(db: &ChalkDatabase, goal: &str) -> SolverStats {
+    let goal = db.parse_and_lower_goal(goal).unwrap();
+    // The program is needed to name the traits in `clauses_generated`.
+    db.with_program(|_| assert!(db.solve(&goal.into_peeled_goal(db.interner())).is_some()));
+    db.solver_stats().unwrap()
+}
+
+#[test]
+fn stats_are_opt_in() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    let goal = db.parse_and_lower_goal("Vec<Foo>: Bar").unwrap();
+    db.solve(&goal.into_peeled_goal(db.interner()));
+    assert_eq!(db.solver_stats(), None);
+}
+
+#[test]
+fn slg_stats() {
+    let db = db(SolverChoice::slg_default());
+    let stats = solve(&db, "Vec<Vec<Foo>>: Bar");
+    assert_eq!(stats.goals_solved, 1);
+    assert!(stats.tables_created > 0);
+    assert_eq!(stats.cache_misses, stats.tables_created);
+    assert!(stats.strands_processed >= stats.tables_created);
+    assert!(stats.answers_produced > 0);
+    // Each of the three `Bar` goals gets a clause for each of the two impls.
+    assert_eq!(stats.clauses_generated["Implemented(Bar)"], 6);
+    assert_eq!(stats.fixed_point_iterations, 0);
+
+    // The tables are kept, so solving the goal again only looks it up.
+    let again = solve(&db, "Vec<Vec<Foo>>: Bar");
+    assert_eq!(again.goals_solved, 2);
+    assert_eq!(again.tables_created, stats.tables_created);
+    assert_eq!(again.cache_hits, 1);
+}
+
+#[test]
+fn recursive_stats() {
+    let db = db(SolverChoice::recursive_default());
+    let stats = solve(&db, "Vec<Vec<Foo>>: Bar");
+    assert_eq!(stats.goals_solved, 1);
+    assert!(stats.tables_created > 0);
+    assert_eq!(stats.cache_misses, stats.tables_created);
+    assert_eq!(stats.answers_produced, stats.tables_created);
+    // There are no cycles, so each goal is solved in one iteration.
+    assert_eq!(stats.fixed_point_iterations, stats.tables_created);
+    assert_eq!(stats.clauses_generated["Implemented(Bar)"], 6);
+    assert_eq!(stats.strands_processed, 0);
+
+    // The results are cached, so solving the goal again only looks it up.
+    let again = solve(&db, "Vec<Vec<Foo>>: Bar");
+    assert_eq!(again.goals_solved, 2);
+    assert_eq!(again.tables_created, stats.tables_created);
+    assert_eq!(again.cache_hits, 1);
+}
+
+#[test]
+fn stats_can_be_reset() {
+    let db = db(SolverChoice::recursive_default());
+    solve(&db, "Vec<Foo>: Bar");
+    db.reset_solver_stats();
+    assert_eq!(db.solver_stats(), Some(SolverStats::default()));
+}
@@ -12,6 +12,8 @@
 #[macro_use]
 mod util;
 
+mod replay;
+
 #[test]
 fn records_struct_trait_and_impl() {
     logging_db_output_sufficient! {
@@ -0,0 +1,111 @@
+//! Tests for `RecordingSolver`, which records the goals solved in a
+//! `LoggingRustIrDatabase` so that they can be replayed.
+use chalk_integration::{
+    db::ChalkDatabase, interner::ChalkIr, lowering::lower_goal, program::Program,
+    query::LoweringDatabase, SolverChoice,
+};
+use chalk_solve::ext::*;
+use chalk_solve::logging_db::{LoggingRustIrDatabase, RecordingSolver};
+use chalk_solve::{RustIrDatabase, Solver};
+
+const PROGRAM: &str = "
+    struct Foo { }
+    struct Bar { }
+    struct Vec<T> { }
+    trait Clone { }
+    trait Unused { }
+    impl Clone for Foo { }
+    impl<T> Clone for Vec<T> where T: Clone { }
+";
+
+/// Solves `goals` with a `RecordingSolver`, taking at most two answers for
+/// the ones solved with `solve_multiple`, and returns what was recorded.
+fn record(goals: &[(&str, bool)]) -> String {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    let program = db.program_ir().unwrap();
+    let logging_db = LoggingRustIrDatabase::<_, Program, _>::new(program.clone());
+    chalk_integration::tls::set_current_program(&program, || {
+        let mut solver = RecordingSolver::new(SolverChoice::default().into_solver(), &logging_db);
+        for &(goal, multiple) in goals {
+            let goal = lower_goal(&chalk_parse::parse_goal(goal).unwrap(), &program).unwrap();
+            let goal = goal.into_peeled_goal(db.interner());
+            if multiple {
+                let mut answers = 0;
+                solver.solve_multiple(&logging_db, &goal, &mut |_, _| {
+                    answers += 1;
+                    answers < 2
+                });
+            } else {
+                solver.solve(&logging_db, &goal);
+            }
+        }
+        logging_db.to_string()
+    })
+}
+
+/// Splits recorded output into its program, and its goals with what they
+/// yielded, one line each.
+fn split(output: &str) -> (&str, Vec<(&str, Vec<&str>)>) {
+    let (program, goals) = output.split_once("\ngoal {").unwrap();
+    let goals = goals
+        .split("\ngoal {")
+        .map(|block| {
+            let mut lines = block.lines().map(str::trim).filter(|l| !l.is_empty());
+            let goal = lines.next().unwrap();
+            let results = lines.filter(|l| !l.starts_with('}')).collect();
+            (goal, results)
+        })
+        .collect();
+    (program, goals)
+}
+
+#[test]
+fn records_goals_and_solutions() {
+    let output = record(&[
+        ("Vec<Foo>: Clone", false),
+        ("Bar: Clone", false),
+        ("exists<T> { T: Clone }", true),
+    ]);
+    let (_, goals) = split(&output);
+    assert_eq!(
+        goals,
+        [
+            ("Vec<Foo>: Clone", vec!["Unique"]),
+            ("Bar: Clone", vec!["No possible solution"]),
+            (
+                "exists<_1_0> { _1_0: Clone }",
+                vec![
+                    "substitution [?0 := Foo]",
+                    "substitution [?0 := Vec<Foo>]",
+                    "...",
+                ]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn recorded_goals_can_be_replayed() {
+    let output = record(&[
+        ("forall<T> { if (T: Clone) { Vec<T>: Clone } }", false),
+        ("forall<T> { exists<U> { Vec<U>: Clone, U = T } }", false),
+        ("exists<T> { forall<U> { Vec<T>: Clone } }", false),
+        ("Bar: Clone", false),
+    ]);
+    let (program, goals) = split(&output);
+
+    // `Bar` is only mentioned by a goal, but still defined.
+    let db = ChalkDatabase::with(program, SolverChoice::default());
+    let program = db.program_ir().unwrap();
+    chalk_integration::tls::set_current_program(&program, || {
+        for (goal, expected) in goals {
+            let lowered = lower_goal(&chalk_parse::parse_goal(goal).unwrap(), &program)
+                .unwrap_or_else(|e| panic!("cannot lower recorded goal `{}`: {}", goal, e));
+            let solution = match db.solve(&lowered.into_peeled_goal(db.interner())) {
+                Some(solution) => solution.display(ChalkIr).to_string(),
+                None => "No possible solution".to_string(),
+            };
+            assert_eq!([solution.as_str()], expected[..], "goal `{}`", goal);
+        }
+    });
+}
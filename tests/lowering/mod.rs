@@ -815,3 +815,53 @@ fn algebraic_data_types() {
         }
     }
 }
+
+#[test]
+fn diagnostic_spans() {
+    fn diagnostic_for(program_text: &str) -> chalk_integration::error::Diagnostic {
+        ChalkDatabase::with(program_text, SolverChoice::default())
+            .checked_program()
+            .unwrap_err()
+            .diagnostic()
+            .clone()
+    }
+
+    // Lowering errors point at the offending identifier.
+    let program_text = "struct Foo { }\ntrait Bar { }\nimpl Foo for Bar { }";
+    let primary = diagnostic_for(program_text).primary.unwrap();
+    assert_eq!(&program_text[primary.span.lo..primary.span.hi], "Foo");
+    assert_eq!(primary.span.lo, 34);
+    assert_eq!(primary.message, "not a trait");
+
+    // WF errors point at the ill-formed item.
+    let program_text = "trait Foo { }\nstruct Bar<T> where T: Foo { }\nstruct Baz { b: Bar<u32> }";
+    let primary = diagnostic_for(program_text).primary.unwrap();
+    assert_eq!(&program_text[primary.span.lo..primary.span.hi], "Baz");
+    assert_eq!(primary.message, "ill-formed type");
+
+    // Coherence errors point at both impls.
+    let program_text = "trait Foo { }\nstruct Bar { }\nimpl Foo for Bar { }\nimpl Foo for Bar { }";
+    let diagnostic = diagnostic_for(program_text);
+    let primary = diagnostic.primary.unwrap();
+    assert_eq!(primary.span.lo, 55);
+    assert_eq!(primary.message, "conflicting impl");
+    assert_eq!(diagnostic.secondary.len(), 1);
+    assert_eq!(diagnostic.secondary[0].span.lo, 34);
+    assert_eq!(diagnostic.secondary[0].message, "first impl here");
+}
+
+#[test]
+fn diagnostic_render() {
+    let program_text = "struct Foo { }\ntrait Bar { }\nimpl Foo for Bar { }";
+    let error = ChalkDatabase::with(program_text, SolverChoice::default())
+        .checked_program()
+        .unwrap_err();
+    assert_eq!(
+        error.diagnostic().render(program_text),
+        "expected a trait, found `Foo`, which is not a trait\n \
+         --> 3:6\n  \
+         |\n\
+         3 | impl Foo for Bar { }\n  \
+         |      ^^^ not a trait"
+    );
+}
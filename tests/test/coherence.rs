@@ -27,6 +27,76 @@ fn two_impls_for_same_type() {
     }
 }
 
+#[test]
+fn overlap_witness() {
+    lowering_error! {
+        program {
+            trait Foo { }
+            struct Bar { }
+            impl Foo for Bar { }
+            impl Foo for Bar { }
+        }
+        error_msg {
+            "overlapping impls of trait `Foo`: both impls apply to `Bar: Foo`"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Foo { }
+            struct Bar { }
+            impl<T> Foo for (T, Bar) { }
+            impl<T> Foo for (Bar, T) { }
+        }
+        error_msg {
+            "overlapping impls of trait `Foo`: both impls apply to `(Bar, Bar): Foo`"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Foo<T> { }
+            struct Vec<T> { }
+            struct Bar { }
+            impl<T> Foo<T> for Vec<Bar> { }
+            impl<U> Foo<Bar> for Vec<U> { }
+        }
+        error_msg {
+            "overlapping impls of trait `Foo`: both impls apply to `Vec<Bar>: Foo<Bar>`"
+        }
+    }
+
+    // Parameters that don't matter for the overlap are left unspecified.
+    // (Without `T: Bar`, the second impl would specialize the first; see
+    // `static_impl_specializes_lifetime_generic_impl`.)
+    lowering_error! {
+        program {
+            trait Foo { }
+            trait Bar { }
+            struct Ref<'a, T> { }
+            impl<'a, T> Foo for Ref<'a, T> where T: Bar { }
+            impl<T> Foo for Ref<'static, T> { }
+        }
+        error_msg {
+            "overlapping impls of trait `Foo`: both impls apply to `Ref<'static, _>: Foo`"
+        }
+    }
+}
+
+/// `for<'a> { 'a: 'static }` doesn't hold, so the first impl doesn't
+/// specialize the second one, but the second impl specializes the first.
+#[test]
+fn static_impl_specializes_lifetime_generic_impl() {
+    lowering_success! {
+        program {
+            trait Foo { }
+            struct Ref<'a, T> { }
+            impl<'a, T> Foo for Ref<'a, T> { }
+            impl<T> Foo for Ref<'static, T> { }
+        }
+    }
+}
+
 #[test]
 fn generic_vec_and_specific_vec() {
     lowering_success! {
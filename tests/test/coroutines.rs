@@ -98,6 +98,8 @@ fn coroutine_test() {
             expect![["No possible solution"]]
         }
 
+        // The witness lifetimes can be any lifetimes, so they can't be
+        // required to be the same.
         goal {
             forall<T> {
                 if (T: Send) {
@@ -105,7 +107,7 @@ fn coroutine_test() {
                 }
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!2_0: '!2_1 }, InEnvironment { environment: Env([]), goal: '!2_1: '!2_0 }]"]]
+            expect![["No possible solution"]]
         }
 
         goal {
@@ -160,12 +160,81 @@ fn overflow() {
             impl<X> Q for S<X> where X: Q, S<G<X>>: Q { }
         }
 
-        // Will try to prove S<G<Z>>: Q then S<G<G<Z>>>: Q etc ad infinitum
+        // Will try to prove S<G<Z>>: Q then S<G<G<Z>>>: Q etc ad infinitum.
+        // Once the goal is truncated to `S<G<..<?X>>>: Q`, it depends on
+        // itself, so there is no (finite) proof.
         goal {
             S<Z>: Q
         } yields[SolverChoice::slg(10, None)] {
-            expect![["Ambiguous; no inference guidance"]]
+            expect![["No possible solution"]]
         } yields[SolverChoice::recursive_default()] {
+            expect![["No possible solution"]]
+        }
+    }
+}
+
+#[test]
+fn truncation() {
+    test! {
+        program {
+            trait Foo { }
+            struct Vec<T> { }
+            struct Bar { }
+
+            // Will try to prove Vec<Bar>: Foo then Vec<Vec<Bar>>: Foo etc
+            impl<T> Foo for T where Vec<T>: Foo { }
+        }
+
+        goal {
+            Bar: Foo
+        } yields_all[SolverChoice::slg(10, None)] {
+        }
+
+        goal {
+            Bar: Foo
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            exists<T> { T: Foo }
+        } yields {
+            expect![["No possible solution"]]
+        }
+    }
+}
+
+/// Solutions found after truncating a goal are only approximations.
+#[test]
+fn truncation_approximates() {
+    test! {
+        program {
+            trait Foo { }
+            struct Vec<T> { }
+            struct Bar { }
+
+            impl Foo for Bar { }
+            impl<T> Foo for Vec<T> where T: Foo { }
+        }
+
+        // Both solvers truncate the subgoal `Vec<Vec<Vec<Bar>>>: Foo` to
+        // `Vec<Vec<?X>>: Foo`. The SLG solver filters the answers to that by
+        // unifying them with the subgoal, so it finds the (exact) answer
+        // `?X := Vec<Bar>`, but the recursive solver can only say that the
+        // goal may hold.
+        goal {
+            Vec<Vec<Vec<Vec<Bar>>>>: Foo
+        } yields[SolverChoice::slg(3, None)] {
+            expect![["Unique"]]
+        } yields[SolverChoice::recursive(3, 100)] {
+            expect![["Ambiguous; no inference guidance"]]
+        }
+
+        goal {
+            exists<T> { Vec<Vec<Vec<Vec<T>>>>: Foo }
+        } yields[SolverChoice::slg(3, None)] {
+            expect![["Ambiguous; no inference guidance"]]
+        } yields[SolverChoice::recursive(3, 100)] {
             expect![["Ambiguous; no inference guidance"]]
         }
     }
@@ -324,8 +324,8 @@ fn dyn_binders_reverse() {
             impl<A> Eq<A> for A { }
         }
 
-        // Note: these constraints are ultimately unresolveable (we
-        // have to show that 'a == 'b, basically)
+        // Note: there is no solution, as we would have to show that
+        // 'a == 'b, basically
         goal {
             forall<'s> {
                 dyn forall<'a, 'b> Fn<Refs<'a, 'b>> + 's: Eq<
@@ -333,11 +333,11 @@ fn dyn_binders_reverse() {
                 >
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!5_0: '!5_1 }, InEnvironment { environment: Env([]), goal: '!5_1: '!5_0 }]"]]
+            expect![["No possible solution"]]
         }
 
-        // Note: these constraints are ultimately unresolveable (we
-        // have to show that 'a == 'b, basically)
+        // Note: there is no solution, as we would have to show that
+        // 'a == 'b, basically
         goal {
             forall<'s> {
                 dyn forall<'c> Fn<Refs<'c, 'c>> + 's: Eq<
@@ -345,7 +345,7 @@ fn dyn_binders_reverse() {
                 >
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!3_0: '!3_1 }, InEnvironment { environment: Env([]), goal: '!3_1: '!3_0 }]"]]
+            expect![["No possible solution"]]
         }
 
         // Note: ordering of parameters is reversed here, but that's no problem
@@ -125,3 +125,194 @@ fn erased_impls() {
         }
     }
 }
+
+#[test]
+fn region_constraints_simplified() {
+    test! {
+        program {
+            trait Outlives<'a, 'b> {}
+            impl<'a, 'b> Outlives<'a, 'b> for () where 'a: 'b {}
+        }
+
+        // Duplicates and constraints that always hold are dropped.
+        goal {
+            forall<'a, 'b> {
+                (): Outlives<'a, 'b>,
+                (): Outlives<'a, 'b>,
+                (): Outlives<'static, 'a>,
+                (): Outlives<'a, 'a>
+            }
+        } yields {
+            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }]"]]
+        }
+
+        goal {
+            forall<'a, 'b, 'c> {
+                (): Outlives<'a, 'b>,
+                (): Outlives<'b, 'c>
+            }
+        } yields {
+            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_0: '!1_2 }, InEnvironment { environment: Env([]), goal: '!1_1: '!1_2 }]"]]
+        }
+
+        goal {
+            forall<'a, 'b> {
+                if ('a: 'b) {
+                    (): Outlives<'a, 'b>
+                }
+            }
+        } yields {
+            expect![["Unique"]]
+        }
+
+        // The recursive solver can't choose between the answers it gets from
+        // the environment clauses and the one with a constraint.
+        goal {
+            forall<'a, 'b, 'c> {
+                if ('a: 'b; 'b: 'c) {
+                    (): Outlives<'a, 'c>
+                }
+            }
+        } yields[SolverChoice::slg_default()] {
+            expect![["Unique"]]
+        }
+    }
+}
+
+#[test]
+fn type_outlives_decomposed() {
+    test! {
+        program {
+            trait TypeOutlives<'a> {}
+            impl<'a, T> TypeOutlives<'a> for T where T: 'a {}
+
+            struct Ref<'a, T> where T: 'a {}
+            struct Pair<T, U> {}
+        }
+
+        goal {
+            forall<'a> {
+                Pair<u32, ()>: TypeOutlives<'a>
+            }
+        } yields {
+            expect![["Unique"]]
+        }
+
+        // `T: 'a` follows from the where clauses of `Ref`.
+        goal {
+            forall<'a, 'b, T> {
+                Ref<'a, T>: TypeOutlives<'b>
+            }
+        } yields {
+            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }]"]]
+        }
+
+        goal {
+            forall<'a, 'b, T> {
+                Pair<&'a T, u32>: TypeOutlives<'b>
+            }
+        } yields {
+            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: !1_2: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }]"]]
+        }
+
+        goal {
+            forall<'a, T> {
+                if (T: 'a) {
+                    Pair<T, ()>: TypeOutlives<'a>
+                }
+            }
+        } yields {
+            expect![["Unique"]]
+        }
+
+        // The lifetimes bound by a function pointer don't need to outlive
+        // anything, but the types they are applied to do.
+        goal {
+            forall<'a, T> {
+                for<'x> fn(&'x T): TypeOutlives<'a>
+            }
+        } yields {
+            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: !1_1: '!1_0 }]"]]
+        }
+
+        goal {
+            forall<'a, 'b> {
+                for<'x> fn(&'x u32, Ref<'b, u32>): TypeOutlives<'a>
+            }
+        } yields {
+            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }]"]]
+        }
+    }
+}
+
+/// An object type outlives a lifetime if its own lifetime and the
+/// parameters of its bounds do.
+#[test]
+fn dyn_outlives_decomposed() {
+    test! {
+        program {
+            trait Foo<T> {}
+            struct Ref<'a> {}
+        }
+
+        goal {
+            forall<'a, 'b> {
+                (dyn Foo<Ref<'a>> + 'b): 'static
+            }
+        } yields {
+            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: 'static }, InEnvironment { environment: Env([]), goal: '!1_1: 'static }]"]]
+        }
+
+        goal {
+            forall<'a, 'b> {
+                (dyn Foo<Ref<'a>> + 'b): 'b
+            }
+        } yields {
+            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }]"]]
+        }
+    }
+}
+
+#[test]
+fn placeholder_region_contradictions() {
+    test! {
+        program {
+            trait Outlives<'a, 'b> {}
+            impl<'a, 'b> Outlives<'a, 'b> for () where 'a: 'b {}
+
+            trait OutlivedByAll<'x> {}
+            impl<'x> OutlivedByAll<'x> for () where forall<'a> (): Outlives<'a, 'x> {}
+
+            trait OutlivesAll<'x> {}
+            impl<'x> OutlivesAll<'x> for () where forall<'a> (): Outlives<'x, 'a> {}
+        }
+
+        goal {
+            forall<'x> {
+                (): OutlivedByAll<'x>
+            }
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            (): OutlivedByAll<'static>
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            forall<'x> {
+                (): OutlivesAll<'x>
+            }
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            (): OutlivesAll<'static>
+        } yields {
+            expect![["Unique"]]
+        }
+    }
+}
@@ -72,8 +72,7 @@ fn subgoal_abstraction() {
 
         goal {
             exists<T> { T: Foo }
-        } yields_first[SolverChoice::slg(50, None)] {
-            expect![["Floundered"]]
+        } yields_all[SolverChoice::slg(50, None)] {
         }
     }
 }
@@ -175,26 +174,25 @@ fn subgoal_cycle_uninhabited() {
             impl<T> Foo for Box<T> where Box<Vec<T>>: Foo { }
         }
 
-        // Infinite recursion -> we flounder
-        // Still return the necessary substitution T = Box<..>
+        // Infinite recursion -> we truncate the subgoals, which makes the
+        // recursion a cycle, and so there are no answers
         goal {
             exists<T> { T: Foo }
-        } yields_first[SolverChoice::slg(2, None)] {
-            expect![["Ambiguous(for<?U0> { substitution [?0 := Box<^0.0>] })"]]
+        } yields_all[SolverChoice::slg(2, None)] {
         }
 
-        // Unsurprisingly, applying negation also flounders.
+        // Hence applying negation succeeds.
         goal {
             not { exists<T> { T: Foo } }
-        } yields_first[SolverChoice::slg(2, None)] {
-            expect![["Floundered"]]
+        } yields_all[SolverChoice::slg(2, None)] {
+            expect![[""]]
         }
 
         // Equivalent to the previous.
         goal {
             forall<T> { not { T: Foo } }
-        } yields_first[SolverChoice::slg(2, None)] {
-            expect![["Floundered"]]
+        } yields_all[SolverChoice::slg(2, None)] {
+            expect![[""]]
         }
 
         // However, if we come across a negative goal that exceeds our
@@ -215,9 +213,8 @@ fn subgoal_cycle_uninhabited() {
         // Here, due to the hypothesis, there does indeed exist a suitable T, `U`.
         goal {
             forall<U> { if (U: Foo) { exists<T> { T: Foo } } }
-        } yields_first[SolverChoice::slg(2, None)] {
-            expect![["substitution [?0 := !1_0]"]],
-            expect![["Ambiguous(for<?U1> { substitution [?0 := Box<^0.0>] })"]]
+        } yields_all[SolverChoice::slg(2, None)] {
+            expect![["substitution [?0 := !1_0]"]]
         }
     }
 }
@@ -234,13 +231,12 @@ fn subgoal_cycle_inhabited() {
             impl Foo for Alice { }
         }
 
-        // Exceeds size threshold -> flounder
-        // Still return necessary substitution T = Box<..>
+        // Exceeds size threshold -> truncate, which shows that there are
+        // no `Box<..>` answers
         goal {
             exists<T> { T: Foo }
-        } yields_first[SolverChoice::slg(3, None)] {
-            expect![["substitution [?0 := Alice]"]],
-            expect![["Ambiguous(for<?U0> { substitution [?0 := Box<^0.0>] })"]]
+        } yields_all[SolverChoice::slg(3, None)] {
+            expect![["substitution [?0 := Alice]"]]
         }
     }
 }
@@ -324,7 +320,7 @@ fn cached_answers_1() {
             expect![["substitution [?0 := Vinegar]"]],
             expect![["substitution [?0 := HotSauce<Lemon>]"]],
             expect![["substitution [?0 := HotSauce<Vinegar>]"]],
-            expect![["Floundered"]]
+            expect![["Ambiguous(for<?U0> { substitution [?0 := HotSauce<^0.0>] })"]]
         }
     }
 }
@@ -351,7 +347,7 @@ fn cached_answers_2() {
             expect![["substitution [?0 := Vinegar]"]],
             expect![["substitution [?0 := HotSauce<Lemon>]"]],
             expect![["substitution [?0 := HotSauce<Vinegar>]"]],
-            expect![["Floundered"]]
+            expect![["Ambiguous(for<?U0> { substitution [?0 := HotSauce<^0.0>] })"]]
         }
     }
 }
@@ -377,7 +373,7 @@ fn cached_answers_3() {
             expect![["substitution [?0 := Lemon]"]],
             expect![["substitution [?0 := HotSauce<Lemon>]"]],
             expect![["substitution [?0 := Vinegar]"]],
-            expect![["Floundered"]]
+            expect![["Ambiguous(for<?U0> { substitution [?0 := HotSauce<^0.0>] })"]]
         }
     }
 }
@@ -772,9 +768,9 @@ fn ambiguous_unification_in_fn() {
                 MyClosure<fn(&'static U) -> ()>: FnOnce<(&'static T,)>
             }
         } yields[SolverChoice::slg_default()] {
-            expect![["Unique; for<?U0> { substitution [?0 := ^0.0, ?1 := ^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: 'static: 'static }, InEnvironment { environment: Env([]), goal: 'static: 'static }] }"]]
+            expect![["Unique; for<?U0> { substitution [?0 := ^0.0, ?1 := ^0.0] }"]]
         } yields[SolverChoice::recursive_default()] {
-            expect![["Unique; for<?U0> { substitution [?0 := ^0.0, ?1 := ^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: 'static: 'static }] }"]]
+            expect![["Unique; for<?U0> { substitution [?0 := ^0.0, ?1 := ^0.0] }"]]
         }
     }
 }
@@ -824,7 +820,7 @@ fn env_bound_vars() {
                 }
             }
         } yields {
-            expect![["Unique; for<?U0> { substitution [?0 := '^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: 0: '^0.0 }] }"]]
+            expect![["Unique; for<?U0> { substitution [?0 := '^0.0] }"]]
         }
     }
 }
@@ -878,3 +874,107 @@ fn coinductive_wrapper() {
        }
     */
 }
+
+/// The heuristic subgoal selection should find the same solutions as the
+/// default one, even when it has to put off negative literals and goals with
+/// an unresolved self type.
+#[test]
+fn heuristic_subgoal_selection() {
+    let program = "
+        #[non_enumerable]
+        trait Sized { }
+        #[auto]
+        trait Send { }
+        trait Bar { }
+        struct Foo { }
+        struct Baz { }
+        struct Vec<T> { }
+
+        impl Bar for Foo { }
+        impl<U> Bar for Vec<U> where U: Bar { }
+        impl Sized for Foo { }
+        impl Sized for Baz { }
+        impl<U> Sized for Vec<U> { }
+    ";
+    let goals = [
+        (
+            "exists<T> { T = Baz, not { T: Bar } }",
+            expect![["Unique; substitution [?0 := Baz]"]],
+        ),
+        (
+            "exists<T, U> { T: Sized, U = Foo, Vec<U> = T }",
+            expect![["Unique; substitution [?0 := Vec<Foo>, ?1 := Foo]"]],
+        ),
+        (
+            "exists<T> { T: Send, T: Bar, T = Vec<Foo> }",
+            expect![["Unique; substitution [?0 := Vec<Foo>]"]],
+        ),
+        (
+            "exists<T> { T: Sized, not { T: Bar }, T = Foo }",
+            expect![["No possible solution"]],
+        ),
+    ];
+    for selection in [SubgoalSelection::Last, SubgoalSelection::Heuristic] {
+        let mut db = ChalkDatabase::with(program, SolverChoice::slg_default());
+        db.set_subgoal_selection(selection);
+        for (goal, expected) in &goals {
+            let goal = db.parse_and_lower_goal(goal).unwrap();
+            db.with_program(|_| {
+                let result = db.solve(&goal.into_peeled_goal(db.interner()));
+                assert_result(result, expected, db.interner());
+            });
+        }
+    }
+}
+
+/// SLG tables drop answers that are instances of more general ones, and
+/// retire answers when more general ones come along.
+#[test]
+fn subsumed_answers() {
+    test! {
+        program {
+            struct Vec<T> { }
+            struct Foo { }
+
+            trait Bar { }
+            forall<T> { Vec<T>: Bar }
+            forall<> { Vec<Foo>: Bar }
+
+            trait Baz { }
+            forall<> { Vec<Foo>: Baz }
+            forall<T> { Vec<T>: Baz }
+
+            trait Qux<'a> { }
+            forall<'a, T> { Vec<T>: Qux<'a> if T: 'a }
+            forall<'a> { Vec<Foo>: Qux<'a> }
+        }
+
+        goal {
+            exists<T> { T: Bar }
+        } yields_all[SolverChoice::slg_default()] {
+            expect![["for<?U0> { substitution [?0 := Vec<^0.0>] }"]]
+        }
+
+        goal {
+            exists<T> { T: Bar }
+        } yields[SolverChoice::slg_default()] {
+            expect![["Unique; for<?U0> { substitution [?0 := Vec<^0.0>] }"]]
+        }
+
+        // `Vec<Foo>` was already returned when `Vec<?T>` came along.
+        goal {
+            exists<T> { T: Baz }
+        } yields_all[SolverChoice::slg_default()] {
+            expect![["substitution [?0 := Vec<Foo>]"]],
+            expect![["for<?U0> { substitution [?0 := Vec<^0.0>] }"]]
+        }
+
+        // `Vec<Foo>` doesn't need `Foo: 'a`, so `Vec<?T>` doesn't subsume it.
+        goal {
+            exists<T, 'a> { T: Qux<'a> }
+        } yields_all[SolverChoice::slg_default()] {
+            expect![["for<?U0,?U0> { substitution [?0 := Vec<^0.0>, ?1 := '^0.1], lifetime constraints [InEnvironment { environment: Env([]), goal: ^0.0: '^0.1 }] }"]],
+            expect![["for<?U0> { substitution [?0 := Vec<Foo>, ?1 := '^0.0] }"]]
+        }
+    }
+}
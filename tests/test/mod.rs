@@ -6,6 +6,7 @@ use chalk_integration::program::Program;
 use expect_test::{expect, Expect};
 
 use crate::test_util::assert_same;
+use chalk_engine::slg::SubgoalSelection;
 use chalk_integration::db::ChalkDatabase;
 use chalk_integration::interner::ChalkIr;
 use chalk_integration::lowering::lower_goal;
@@ -22,6 +23,19 @@ mod bench;
 mod coherence;
 mod wf_lowering;
 
+/// Lets the tests be run with a different SLG subgoal selection strategy, to
+/// compare it with the default one: `CHALK_SUBGOAL_SELECTION=heuristic`.
+/// Note that `yields_all` tests depend on the order answers are found in,
+/// which the strategy can change.
+fn subgoal_selection_override() -> Option<SubgoalSelection> {
+    match std::env::var("CHALK_SUBGOAL_SELECTION").as_deref() {
+        Ok("last") => Some(SubgoalSelection::Last),
+        Ok("heuristic") => Some(SubgoalSelection::Heuristic),
+        Ok(other) => panic!("unknown subgoal selection strategy `{}`", other),
+        Err(_) => None,
+    }
+}
+
 fn format_solution(mut result: Option<Solution<ChalkIr>>, interner: ChalkIr) -> String {
     // sort constraints, since the different solvers may output them in different order
     if let Some(Solution::Unique(solution)) = &mut result {
@@ -247,6 +261,9 @@ fn solve_goal(
             &program_text[1..program_text.len() - 1],
             SolverChoice::default(),
         );
+        if let Some(selection) = subgoal_selection_override() {
+            db.set_subgoal_selection(selection);
+        }
 
         let program = if coherence {
             db.checked_program().unwrap()
@@ -429,6 +446,7 @@ mod object_safe;
 mod opaque_types;
 mod pointee;
 mod projection;
+mod proof;
 mod refs;
 mod scalars;
 mod slices;
@@ -206,6 +206,12 @@ fn example_2_2_EWFS() {
         } yields_all[SolverChoice::slg(3, None)] {
             expect![[""]]
         }
+
+        goal {
+            c: M
+        } yields {
+            expect![["Unique"]]
+        }
     }
 }
 
@@ -265,6 +271,52 @@ fn example_3_3_EWFS() {
     }
 }
 
+/// The recursive solver detects negative cycles like the SLG solver does,
+/// but treats the goals in them as ambiguous instead of panicking.
+#[test]
+fn negative_loop_recursive() {
+    test! {
+        program {
+            trait P { }
+            trait Q { }
+            struct Alice { }
+
+            forall<> { Alice: P if not { Alice: Q } }
+            forall<> { Alice: Q if not { Alice: P } }
+        }
+
+        goal {
+            Alice: P
+        } yields[SolverChoice::recursive_default()] {
+            expect![["Ambiguous; no inference guidance"]]
+        }
+    }
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn example_3_3_EWFS_recursive() {
+    test! {
+        program {
+            trait S { }
+            trait P { }
+            trait Q { }
+
+            struct a { }
+
+            forall<> { a: S if not { a: P }, not { a: Q } }
+            forall<> { a: P if not { a: S }, a: Q }
+            forall<> { a: Q if not { a: S }, a: P }
+        }
+
+        goal {
+            a: S
+        } yields[SolverChoice::recursive_default()] {
+            expect![["Ambiguous; no inference guidance"]]
+        }
+    }
+}
+
 /// Here, P is neither true nor false. If it were true, then it would
 /// be false, and so forth.
 #[test]
@@ -287,6 +339,24 @@ fn contradiction() {
     }
 }
 
+#[test]
+fn contradiction_recursive() {
+    test! {
+        program {
+            trait P { }
+            struct Alice { }
+
+            forall<> { Alice: P if not { Alice: P } }
+        }
+
+        goal {
+            Alice: P
+        } yields[SolverChoice::recursive_default()] {
+            expect![["Ambiguous; no inference guidance"]]
+        }
+    }
+}
+
 /// Here, P depends on Q negatively, but Q depends only on itself.
 #[test]
 #[should_panic(expected = "negative cycle")]
@@ -352,5 +422,25 @@ fn negative_reorder() {
         } yields_all[SolverChoice::slg(3, None)] {
             expect![["substitution [?0 := Bar]"]]
         }
+
+        // The recursive solver tries out the answers to `A: Enumerable` to
+        // refute `not { A: IsFoo }`, so both solvers agree.
+        goal {
+            exists<A> { A: Debug1 }
+        } yields {
+            expect![["Unique; substitution [?0 := Bar]"]]
+        }
+
+        goal {
+            exists<A> { A: Debug2 }
+        } yields {
+            expect![["Unique; substitution [?0 := Bar]"]]
+        }
+
+        goal {
+            exists<A> { A: Enumerable, not { A: Enumerable } }
+        } yields {
+            expect![["No possible solution"]]
+        }
     }
 }
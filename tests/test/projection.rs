@@ -853,7 +853,9 @@ fn normalize_under_binder_multi() {
             InEnvironment { environment: Env([]), goal: '!1_0: '^0.1 }, \
             InEnvironment { environment: Env([]), goal: '^0.1: '!1_0 }, \
             InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, \
-            InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }] }"]]
+            InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }, \
+            InEnvironment { environment: Env([]), goal: '^0.1: '^0.0 }, \
+            InEnvironment { environment: Env([]), goal: '^0.0: '^0.1 }] }"]]
         }
 
         goal {
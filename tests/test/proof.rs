@@ -0,0 +1,308 @@
+//! Tests for `Solver::solve_with_proof` and `Solver::explain_failure`.
+
+use super::*;
+
+/// Solves `goal` with each of `solver_choices`, and checks that they all
+/// explain it with the same proof tree.
+fn assert_proof(
+    solver_choices: &[SolverChoice],
+    program_text: &str,
+    goal_text: &str,
+    expected: Expect,
+) {
+    let mut trees = vec![];
+    for &solver_choice in solver_choices {
+        let db = ChalkDatabase::with(program_text, solver_choice);
+        let goal = db.parse_and_lower_goal(goal_text).unwrap();
+        let tree = db.with_program(|_| {
+            let peeled_goal = goal.into_peeled_goal(db.interner());
+            match db.solve_with_proof(&peeled_goal) {
+                Some((_, Ok(tree))) => tree.display(&db).to_string(),
+                Some((_, Err(error))) => error.display(&db).to_string(),
+                None => "No possible solution".to_string(),
+            }
+        });
+        trees.push(tree);
+    }
+    for tree in &trees[1..] {
+        assert_same(tree, &trees[0]);
+    }
+    expected.assert_eq(&trees[0]);
+}
+
+#[test]
+fn impl_with_where_clause() {
+    assert_proof(
+        &[
+            SolverChoice::slg_default(),
+            SolverChoice::recursive_default(),
+        ],
+        "
+        trait Clone { }
+        struct Foo { }
+        struct Vec<T> { }
+        impl Clone for Foo { }
+        impl<T> Clone for Vec<T> where T: Clone { }
+        ",
+        "Vec<Vec<Foo>>: Clone",
+        expect![[r#"
+            `Vec<Vec<Foo>>: Clone` by clause `forall<_1_0> { Vec<_1_0>: Clone :- _1_0: Clone }`
+                `Vec<Foo>: Clone` by clause `forall<_1_0> { Vec<_1_0>: Clone :- _1_0: Clone }`
+                    `Foo: Clone` by clause `Foo: Clone`
+        "#]],
+    );
+}
+
+#[test]
+fn existential_variables() {
+    assert_proof(
+        &[
+            SolverChoice::slg_default(),
+            SolverChoice::recursive_default(),
+        ],
+        "
+        trait Foo { }
+        struct Bar { }
+        impl Foo for Bar { }
+        ",
+        "exists<T> { T: Foo }",
+        expect![[r#"
+            `Bar: Foo` by clause `Bar: Foo`
+        "#]],
+    );
+}
+
+#[test]
+fn assumption() {
+    assert_proof(
+        &[
+            SolverChoice::slg_default(),
+            SolverChoice::recursive_default(),
+        ],
+        "
+        trait Clone { }
+        struct Vec<T> { }
+        impl<T> Clone for Vec<T> where T: Clone { }
+        ",
+        "forall<T> { if (T: Clone) { Vec<T>: Clone } }",
+        expect![[r#"
+            `Vec<<placeholder>>: Clone` by clause `forall<_1_0> { Vec<_1_0>: Clone :- _1_0: Clone }`
+                `<placeholder>: Clone` by assumption `forall<_1_0> { _1_0: Clone :- FromEnv(_1_0: Clone) }`
+                    `FromEnv(<placeholder>: Clone)` by assumption `FromEnv(<placeholder>: Clone)`
+        "#]],
+    );
+}
+
+#[test]
+fn auto_trait() {
+    assert_proof(
+        &[
+            SolverChoice::slg_default(),
+            SolverChoice::recursive_default(),
+        ],
+        "
+        #[auto] trait Send { }
+        struct Foo { }
+        struct Bar { foo: Foo }
+        ",
+        "Bar: Send",
+        expect![[r#"
+            `Bar: Send` by clause `Bar: Send :- Foo: Send`
+                `Foo: Send` by clause `Foo: Send`
+        "#]],
+    );
+}
+
+#[test]
+fn auto_trait_cycle() {
+    assert_proof(
+        &[SolverChoice::recursive_default()],
+        "
+        #[auto] trait Send { }
+        struct List<T> { data: T, next: Option<List<T>> }
+        struct Option<T> { value: T }
+        struct Foo { }
+        ",
+        "List<Foo>: Send",
+        expect![[r#"
+            `List<Foo>: Send` by clause `List<Foo>: Send :- Foo: Send, Option<List<Foo>>: Send`
+                `Foo: Send` by clause `Foo: Send`
+                `Option<List<Foo>>: Send` by clause `Option<List<Foo>>: Send :- List<Foo>: Send`
+                    `List<Foo>: Send` by cycle
+        "#]],
+    );
+}
+
+/// The SLG solver can't prove `Option<List<Foo>>: Send` once it has solved
+/// `List<Foo>: Send` (the tables for the coinductive cycle between them are
+/// left without answers), so it can't build the tree that
+/// `auto_trait_cycle` expects, and says so.
+#[test]
+fn auto_trait_cycle_slg() {
+    assert_proof(
+        &[SolverChoice::slg_default()],
+        "
+        #[auto] trait Send { }
+        struct List<T> { data: T, next: Option<List<T>> }
+        struct Option<T> { value: T }
+        struct Foo { }
+        ",
+        "List<Foo>: Send",
+        expect![[r#"
+            couldn't explain `List<Foo>: Send`: the solver gives a different answer for it, or for one of its sub-goals, when asked about it on its own
+        "#]],
+    );
+}
+
+#[test]
+fn no_solution() {
+    assert_proof(
+        &[
+            SolverChoice::slg_default(),
+            SolverChoice::recursive_default(),
+        ],
+        "
+        trait Clone { }
+        struct Foo { }
+        ",
+        "Foo: Clone",
+        expect![[r#"No possible solution"#]],
+    );
+}
+
+/// Like `assert_proof`, but checks the failure tree explaining why `goal` has
+/// no solution.
+fn assert_failure(
+    solver_choices: &[SolverChoice],
+    program_text: &str,
+    goal_text: &str,
+    expected: Expect,
+) {
+    let mut trees = vec![];
+    for &solver_choice in solver_choices {
+        let db = ChalkDatabase::with(program_text, solver_choice);
+        let goal = db.parse_and_lower_goal(goal_text).unwrap();
+        let tree = db.with_program(|_| {
+            let peeled_goal = goal.into_peeled_goal(db.interner());
+            match db.explain_failure(&peeled_goal) {
+                Some(tree) => tree.display(&db).to_string(),
+                None => "Has a solution".to_string(),
+            }
+        });
+        trees.push(tree);
+    }
+    for tree in &trees[1..] {
+        assert_same(tree, &trees[0]);
+    }
+    expected.assert_eq(&trees[0]);
+}
+
+#[test]
+fn failed_where_clause() {
+    assert_failure(
+        &[
+            SolverChoice::slg_default(),
+            SolverChoice::recursive_default(),
+        ],
+        "
+        trait Clone { }
+        struct Foo { }
+        struct Bar { }
+        struct Vec<T> { }
+        impl Clone for Bar { }
+        impl<T> Clone for Vec<T> where T: Clone { }
+        ",
+        "Vec<Vec<Foo>>: Clone",
+        expect![[r#"
+            `Vec<Vec<Foo>>: Clone` has no solution:
+                clause `forall<_1_0> { Vec<_1_0>: Clone :- _1_0: Clone }` rejected because:
+                    `Vec<Foo>: Clone` has no solution:
+                        clause `forall<_1_0> { Vec<_1_0>: Clone :- _1_0: Clone }` rejected because:
+                            `Foo: Clone` has no solution: no clause applies
+        "#]],
+    );
+}
+
+#[test]
+fn failed_existential() {
+    assert_failure(
+        &[
+            SolverChoice::slg_default(),
+            SolverChoice::recursive_default(),
+        ],
+        "
+        trait Display { }
+        trait Debug { }
+        struct Foo { }
+        struct Bar { }
+        struct Vec<T> { }
+        impl Debug for Bar { }
+        impl<T> Display for Vec<T> where T: Debug { }
+        ",
+        "exists<T> { T = Foo, Vec<T>: Display }",
+        expect![[r#"
+            `Foo = Foo, Vec<Foo>: Display` has no solution:
+                `Vec<Foo>: Display` has no solution:
+                    clause `forall<_1_0> { Vec<_1_0>: Display :- _1_0: Debug }` rejected because:
+                        `Foo: Debug` has no solution: no clause applies
+        "#]],
+    );
+}
+
+#[test]
+fn failed_assumption() {
+    assert_failure(
+        &[
+            SolverChoice::slg_default(),
+            SolverChoice::recursive_default(),
+        ],
+        "
+        trait Foo { }
+        trait Bar { }
+        struct Vec<T> { }
+        ",
+        "forall<T> { if (forall<U> { Vec<U>: Foo :- U: Bar }) { Vec<T>: Foo } }",
+        expect![[r#"
+            `Vec<<placeholder>>: Foo` has no solution:
+                assumption `forall<_1_0> { Vec<_1_0>: Foo :- _1_0: Bar }` rejected because:
+                    `<placeholder>: Bar` has no solution: no clause applies
+        "#]],
+    );
+}
+
+#[test]
+fn failed_unification() {
+    assert_failure(
+        &[
+            SolverChoice::slg_default(),
+            SolverChoice::recursive_default(),
+        ],
+        "
+        struct Foo { }
+        struct Bar { }
+        ",
+        "exists<T> { T = Foo, T = Bar }",
+        expect![[r#"
+            `Foo = Foo, Foo = Bar` has no solution:
+                `Foo = Bar` has no solution: the two sides don't unify
+        "#]],
+    );
+}
+
+#[test]
+fn has_solution() {
+    assert_failure(
+        &[
+            SolverChoice::slg_default(),
+            SolverChoice::recursive_default(),
+        ],
+        "
+        trait Clone { }
+        struct Foo { }
+        impl Clone for Foo { }
+        ",
+        "Foo: Clone",
+        expect![[r#"
+            Has a solution"#]],
+    );
+}
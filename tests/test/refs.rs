@@ -20,7 +20,7 @@ fn immut_refs_are_well_formed() {
                 WellFormed(&'a A)
             }
         } yields {
-            expect![["Unique; for<?U0> { substitution [?0 := '^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: A: '^0.0 }] }"]]
+            expect![["Unique; for<?U0> { substitution [?0 := '^0.0] }"]]
         }
     }
 }
@@ -73,15 +73,15 @@ fn forall_equality_unsolveable_simple() {
         }
 
         goal {
-            // Note: this equality is false, but we get back successful;
-            // this is because the region constraints are unsolvable.
+            // Note: this equality is false, as the region constraints are
+            // unsolvable.
             //
             // Note that `?0` (in universe 2) must be equal to both
             // `!1_0` and `!1_1`, which of course it cannot be.
             for<'a, 'b> fn(Ref<'a, Ref<'b, Ref<'a, Unit>>>): Eq<
                 for<'c, 'd> fn(Ref<'c, Ref<'d, Ref<'d, Unit>>>)>
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }, InEnvironment { environment: Env([]), goal: '!2_0: '!2_1 }, InEnvironment { environment: Env([]), goal: '!2_1: '!2_0 }]"]]
+            expect![["No possible solution"]]
         }
     }
 }
@@ -110,15 +110,15 @@ fn forall_equality() {
         }
 
         goal {
-            // Note: this equality is false, but we get back successful;
-            // this is because the region constraints are unsolvable.
+            // Note: this equality is false, as the region constraints are
+            // unsolvable.
             //
             // Note that `?0` (in universe 2) must be equal to both
             // `!1_0` and `!1_1`, which of course it cannot be.
             for<'a, 'b> fn(Ref<'a, Ref<'b, Ref<'a, Unit>>>): Eq<
                 for<'c, 'd> fn(Ref<'c, Ref<'d, Ref<'d, Unit>>>)>
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }, InEnvironment { environment: Env([]), goal: '!2_0: '!2_1 }, InEnvironment { environment: Env([]), goal: '!2_1: '!2_0 }]"]]
+            expect![["No possible solution"]]
         }
 
         goal {
@@ -263,13 +263,13 @@ fn equality_binder2() {
         goal {
             for<'b, 'c> fn(Ref<'b, 'c>) = for<'a> fn(Ref<'a, 'a>)
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }]"]]
+            expect![["No possible solution"]]
         }
 
         goal {
             for<'a> fn(Ref<'a, 'a>) = for<'b, 'c> fn(Ref<'b, 'c>)
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!2_0: '!2_1 }, InEnvironment { environment: Env([]), goal: '!2_1: '!2_0 }]"]]
+            expect![["No possible solution"]]
         }
     }
 }
@@ -400,7 +400,7 @@ fn quantified_types() {
             forall<'a> { fn(fn1<'a>): Foo }
         } yields {
             // Lifetime constraints are unsatisfiable
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!2_0 }, InEnvironment { environment: Env([]), goal: '!2_0: '!1_0 }]"]]
+            expect![["No possible solution"]]
         }
     }
 }
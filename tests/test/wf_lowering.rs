@@ -149,6 +149,80 @@ fn ill_formed_ty_decl() {
     }
 }
 
+#[test]
+fn unprovable_wf_goals() {
+    lowering_error! {
+        program {
+            trait Hash { }
+            struct Set<K> where K: Hash { }
+
+            struct MyType<K> {
+                value: Set<K>
+            }
+        } error_msg {
+            "type declaration `MyType` does not meet well-formedness requirements
+                could not prove `forall<K> { K: Hash }` (no solution)"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Foo { }
+            struct OnlyFoo<T> where T: Foo { }
+
+            struct MyType { }
+
+            trait Bar {
+                type Value;
+            }
+
+            impl Bar for MyType {
+                type Value = OnlyFoo<MyType>;
+            }
+        } error_msg {
+            "trait impl for `Bar` does not meet well-formedness requirements
+                could not prove `if (FromEnv(MyType)) { MyType: Foo }` (no solution)"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Clone { }
+            trait Copy where Self: Clone { }
+
+            struct Foo { }
+            struct Bar<T> { }
+
+            impl<T> Copy for Bar<T> { }
+        } error_msg {
+            "trait impl for `Copy` does not meet well-formedness requirements
+                could not prove `forall<T> { if (FromEnv(Bar<T>)) { Bar<T>: Clone } }` (no solution)"
+        }
+    }
+
+    // An associated type value has its own parameters, followed by those
+    // of the impl.
+    lowering_error! {
+        program {
+            trait Foo { }
+            struct OnlyFoo<T> where T: Foo { }
+
+            trait Bar {
+                type Value<'x, U>;
+            }
+
+            struct Baz<V> { }
+
+            impl<V> Bar for Baz<V> {
+                type Value<'x, U> = OnlyFoo<(V, &'x U)>;
+            }
+        } error_msg {
+            "trait impl for `Bar` does not meet well-formedness requirements
+                could not prove `forall<'x, U, V> { if (FromEnv(Baz<V>)) { (V, &'x U): Foo } }` (no solution)"
+        }
+    }
+}
+
 #[test]
 fn implied_bounds_on_ty_decl() {
     lowering_success! {